@@ -0,0 +1,140 @@
+use crate::allocators::bit_set::bit_set_allocator::BitSetAllocator;
+use crate::allocators::bit_set::bit_set_word::BitSetWord;
+use crate::allocators::bump_allocator::BumpAllocator;
+use crate::allocators::global::local_allocator::LocalAllocator;
+use crate::extensions::prelude::*;
+use crate::memory_sources::arena_memory_source::arena_memory_source::ArenaMemorySource;
+use crate::memory_sources::memory_source::MemorySource;
+use crate::memory_sources::rc_memory_source::RcMemorySource;
+use std::alloc::AllocError;
+use std::num::NonZeroUsize;
+
+/// The memory source of allocators handed out by an `ArenaPool`; each allocator's memory is one arena.
+pub type PooledArenaMemorySource<MS> = RcMemorySource<ArenaMemorySource<MS>>;
+
+/// An allocator which can be created using all of a fixed-size arena and reset for reuse by an `ArenaPool`.
+pub trait ArenaAllocator<MS: MemorySource>: LocalAllocator + Sized {
+    /// Creates a new instance using an arena of `arena_size` bytes obtained from `memory_source`.
+    fn new_in_arena(memory_source: MS, arena_size: NonZeroUsize) -> Result<Self, AllocError>;
+
+    /// Discards all allocations; all memory previously allocated becomes invalid.
+    fn reset(&mut self);
+}
+
+impl<MS: MemorySource> ArenaAllocator<MS> for BumpAllocator<MS> {
+    #[inline(always)]
+    fn new_in_arena(memory_source: MS, arena_size: NonZeroUsize) -> Result<Self, AllocError> {
+        Self::new(memory_source, arena_size)
+    }
+
+    #[inline(always)]
+    fn reset(&mut self) {
+        BumpAllocator::reset(self)
+    }
+}
+
+/// Uses 8 byte blocks; the bit set is held in the arena, too.
+impl<MS: MemorySource> ArenaAllocator<MS> for BitSetAllocator<MS> {
+    #[inline(always)]
+    fn new_in_arena(memory_source: MS, arena_size: NonZeroUsize) -> Result<Self, AllocError> {
+        const BLOCK_SIZE: usize = 8;
+
+        // Each block needs `BLOCK_SIZE` bytes and a bit; whole bit set words of blocks are used.
+        let number_of_blocks =
+            ((arena_size.get() * 8) / (BLOCK_SIZE * 8 + 1)) & !(BitSetWord::SIZE_IN_BITS - 1);
+        if unlikely!(number_of_blocks == 0) {
+            return Err(AllocError);
+        }
+
+        Self::new(
+            memory_source,
+            BLOCK_SIZE.non_zero(),
+            number_of_blocks.non_zero(),
+        )
+    }
+
+    #[inline(always)]
+    fn reset(&mut self) {
+        BitSetAllocator::reset(self)
+    }
+}
+
+/// A pool of ready-made allocators, each using one fixed-size arena, for use as coroutine local allocators (eg one per task of an executor).
+///
+/// All arenas are obtained up front from one `ArenaMemorySource`; checking out an allocator is then just a pop, and checking it back in resets it.
+///
+/// Creating the pool allocates from the current allocator in use, so it should be created whilst the global (or thread local) allocator is in use; checking out and checking in never allocate.
+///
+/// This pool is not thread-safe.
+#[derive(Debug)]
+pub struct ArenaPool<A: ArenaAllocator<PooledArenaMemorySource<MS>>, MS: MemorySource> {
+    available: Vec<A>,
+    number_of_arenas: NonZeroUsize,
+    memory_source: PooledArenaMemorySource<MS>,
+}
+
+impl<A: ArenaAllocator<PooledArenaMemorySource<MS>>, MS: MemorySource> ArenaPool<A, MS> {
+    /// Creates a new instance with `number_of_arenas` allocators, each using an arena of `arena_size` bytes; the memory for all of them is obtained from `memory_source`.
+    #[inline(always)]
+    pub fn new(
+        memory_source: MS,
+        arena_size: NonZeroUsize,
+        number_of_arenas: NonZeroUsize,
+    ) -> Result<Self, AllocError> {
+        let memory_source = RcMemorySource::new(ArenaMemorySource::new(
+            memory_source,
+            arena_size,
+            number_of_arenas,
+            |_, _| {},
+        )?);
+
+        let mut available = Vec::with_capacity(number_of_arenas.get());
+        for _ in 0..number_of_arenas.get() {
+            available.push(A::new_in_arena(memory_source.clone(), arena_size)?);
+        }
+
+        Ok(Self {
+            available,
+            number_of_arenas,
+            memory_source,
+        })
+    }
+
+    /// Checks out an allocator, or `None` if all are checked out.
+    #[inline(always)]
+    pub fn checkout(&mut self) -> Option<A> {
+        self.available.pop()
+    }
+
+    /// Resets an allocator previously checked out and returns it to the pool.
+    ///
+    /// All memory it allocated becomes invalid.
+    #[inline(always)]
+    pub fn checkin(&mut self, mut allocator: A) {
+        debug_assert!(
+            self.available.len() < self.number_of_arenas.get(),
+            "More allocators checked in than checked out"
+        );
+
+        allocator.reset();
+        self.available.push(allocator)
+    }
+
+    /// Number of allocators available to check out.
+    #[inline(always)]
+    pub fn available(&self) -> usize {
+        self.available.len()
+    }
+
+    /// Number of allocators (arenas) in the pool.
+    #[inline(always)]
+    pub fn number_of_arenas(&self) -> NonZeroUsize {
+        self.number_of_arenas
+    }
+
+    /// The arena memory source all allocators use.
+    #[inline(always)]
+    pub fn memory_source(&self) -> &ArenaMemorySource<MS> {
+        &self.memory_source
+    }
+}
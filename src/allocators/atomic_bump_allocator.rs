@@ -0,0 +1,283 @@
+use crate::allocators::allocator::Allocator;
+use crate::allocators::global::local_allocator::LocalAllocator;
+use crate::allocators::global::memory_range::MemoryRange;
+
+use crate::extensions::non_zero_usize::non_zero_usize;
+use crate::extensions::prelude::*;
+use crate::memory_address::MemoryAddress;
+use crate::memory_sources::memory_source::MemorySource;
+use std::alloc::{AllocError, AllocRef, GlobalAlloc, Layout};
+use std::num::NonZeroUsize;
+use std::ptr::NonNull;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::{AcqRel, Acquire, Release};
+
+/// A lock-free bump allocator which can be shared between threads.
+///
+/// It:-
+///
+/// * Can efficiently shrink and grow (reallocate) for the most recent allocation made, using compare-and-swap on the next allocation pointer.
+/// * Can reclaim the most recent allocation when it is deallocated.
+/// * Has no wrapping around at the end; use `reset()` to reuse memory.
+///
+/// Is suitable for arenas shared between the worker threads of a batch job, or as the `#[global_allocator]` of a short-lived process.
+///
+/// Memory is obtained from the memory source when this allocator is created with `new()`, or on first use when created with `new_lazy()` (which is usable in `static` contexts).
+///
+/// This allocator NEVER grows or shrinks its memory region.
+///
+/// This allocator is thread-safe.
+#[derive(Debug)]
+pub struct AtomicBumpAllocator<MS: MemorySource> {
+    /// Relative to `allocations_start_from`.
+    next_allocation_at_offset: AtomicUsize,
+
+    /// Zero until memory has been obtained from `memory_source`.
+    allocations_start_from: AtomicUsize,
+
+    memory_source: MS,
+    memory_source_size: NonZeroUsize,
+}
+
+unsafe impl<MS: MemorySource + Sync> Sync for AtomicBumpAllocator<MS> {}
+
+impl<MS: MemorySource> Drop for AtomicBumpAllocator<MS> {
+    #[inline(always)]
+    fn drop(&mut self) {
+        let allocations_start_from = *self.allocations_start_from.get_mut();
+        if likely!(allocations_start_from != Self::NOT_YET_OBTAINED) {
+            self.memory_source.release(
+                self.memory_source_size,
+                MemoryAddress::from_usize(allocations_start_from),
+            )
+        }
+    }
+}
+
+unsafe impl<MS: MemorySource> GlobalAlloc for AtomicBumpAllocator<MS> {
+    crate::global_alloc!();
+}
+
+unsafe impl<MS: MemorySource> AllocRef for AtomicBumpAllocator<MS> {
+    crate::alloc_ref!();
+}
+
+impl<MS: MemorySource> Allocator for AtomicBumpAllocator<MS> {
+    #[inline(always)]
+    fn allocate(
+        &self,
+        non_zero_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+    ) -> Result<MemoryAddress, AllocError> {
+        debug_assert!(
+            non_zero_power_of_two_alignment <= Self::MAXIMUM_POWER_OF_TWO_ALIGNMENT,
+            "non_zero_power_of_two_alignment `{}` exceeds `{}`",
+            non_zero_power_of_two_alignment,
+            Self::MAXIMUM_POWER_OF_TWO_ALIGNMENT
+        );
+
+        let allocations_start_from = self.allocations_start_from()?;
+
+        let mut next_allocation_at_offset = self.next_allocation_at_offset.load(Acquire);
+        loop {
+            let allocation_at_offset = (allocations_start_from + next_allocation_at_offset)
+                .round_up_to_power_of_two(non_zero_power_of_two_alignment)
+                - allocations_start_from;
+            let allocation_ends_at_offset =
+                self.allocation_ends_at_offset(allocation_at_offset, non_zero_size)?;
+
+            match self.next_allocation_at_offset.compare_exchange_weak(
+                next_allocation_at_offset,
+                allocation_ends_at_offset,
+                AcqRel,
+                Acquire,
+            ) {
+                Ok(_) => {
+                    return Ok(MemoryAddress::from_usize(
+                        allocations_start_from + allocation_at_offset,
+                    ))
+                }
+
+                Err(was) => next_allocation_at_offset = was,
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn deallocate(
+        &self,
+        non_zero_size: NonZeroUsize,
+        _non_zero_power_of_two_alignment: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) {
+        let allocation_at_offset = self.offset(current_memory);
+
+        // Only succeeds if this was the most recent allocation.
+        let _ = self.next_allocation_at_offset.compare_exchange(
+            allocation_at_offset + non_zero_size.get(),
+            allocation_at_offset,
+            AcqRel,
+            Acquire,
+        );
+    }
+
+    #[inline(always)]
+    fn shrinking_reallocate(
+        &self,
+        non_zero_new_size: NonZeroUsize,
+        _non_zero_power_of_two_alignment: NonZeroUsize,
+        non_zero_current_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> Result<MemoryAddress, AllocError> {
+        let allocation_at_offset = self.offset(current_memory);
+
+        // Only succeeds if this was the most recent allocation.
+        let _ = self.next_allocation_at_offset.compare_exchange(
+            allocation_at_offset + non_zero_current_size.get(),
+            allocation_at_offset + non_zero_new_size.get(),
+            AcqRel,
+            Acquire,
+        );
+
+        Ok(current_memory)
+    }
+
+    #[inline(always)]
+    fn growing_reallocate(
+        &self,
+        non_zero_new_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+        non_zero_current_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> Result<MemoryAddress, AllocError> {
+        let allocation_at_offset = self.offset(current_memory);
+        let allocation_ends_at_offset =
+            self.allocation_ends_at_offset(allocation_at_offset, non_zero_new_size);
+
+        if let Ok(allocation_ends_at_offset) = allocation_ends_at_offset {
+            let was_most_recent_allocation = self
+                .next_allocation_at_offset
+                .compare_exchange(
+                    allocation_at_offset + non_zero_current_size.get(),
+                    allocation_ends_at_offset,
+                    AcqRel,
+                    Acquire,
+                )
+                .is_ok();
+
+            if likely!(was_most_recent_allocation) {
+                return Ok(current_memory);
+            }
+        }
+
+        let new_memory = self.allocate(non_zero_new_size, non_zero_power_of_two_alignment)?;
+        unsafe {
+            new_memory
+                .as_ptr()
+                .copy_from_nonoverlapping(current_memory.as_ptr(), non_zero_current_size.get())
+        };
+        Ok(new_memory)
+    }
+}
+
+impl<MS: MemorySource> LocalAllocator for AtomicBumpAllocator<MS> {
+    /// An empty memory range is returned if memory has not yet been obtained from the memory source.
+    #[inline(always)]
+    fn memory_range(&self) -> MemoryRange {
+        let allocations_start_from = self.allocations_start_from.load(Acquire);
+        if unlikely!(allocations_start_from == Self::NOT_YET_OBTAINED) {
+            return MemoryRange::new(Self::ZERO_SIZED_ALLOCATION, Self::ZERO_SIZED_ALLOCATION);
+        }
+
+        let allocations_start_from = MemoryAddress::from_usize(allocations_start_from);
+        MemoryRange::new(
+            allocations_start_from,
+            allocations_start_from.add_non_zero(self.memory_source_size),
+        )
+    }
+}
+
+impl<MS: MemorySource> AtomicBumpAllocator<MS> {
+    const MAXIMUM_POWER_OF_TWO_ALIGNMENT: NonZeroUsize = non_zero_usize(4096);
+
+    const NOT_YET_OBTAINED: usize = 0;
+
+    /// New instance wrapping a block of memory obtained immediately.
+    #[inline(always)]
+    pub fn new(memory_source: MS, memory_source_size: NonZeroUsize) -> Result<Self, AllocError> {
+        let this = Self::new_lazy(memory_source, memory_source_size);
+        this.allocations_start_from()?;
+        Ok(this)
+    }
+
+    /// New instance which obtains its block of memory on first allocation.
+    ///
+    /// Usable in `const` and `static` contexts, eg for a `#[global_allocator]`.
+    #[inline(always)]
+    pub const fn new_lazy(memory_source: MS, memory_source_size: NonZeroUsize) -> Self {
+        Self {
+            next_allocation_at_offset: AtomicUsize::new(0),
+            allocations_start_from: AtomicUsize::new(Self::NOT_YET_OBTAINED),
+
+            memory_source,
+            memory_source_size,
+        }
+    }
+
+    /// Makes all memory available for allocation again.
+    ///
+    /// # Safety
+    ///
+    /// No memory allocated by this allocator may still be in use, and no other thread may be allocating concurrently.
+    #[inline(always)]
+    pub unsafe fn reset(&self) {
+        self.next_allocation_at_offset.store(0, Release)
+    }
+
+    #[inline(always)]
+    fn allocations_start_from(&self) -> Result<usize, AllocError> {
+        let allocations_start_from = self.allocations_start_from.load(Acquire);
+        if likely!(allocations_start_from != Self::NOT_YET_OBTAINED) {
+            return Ok(allocations_start_from);
+        }
+
+        // Several threads may race to obtain memory; the losers release theirs.
+        let obtained = self.memory_source.obtain(self.memory_source_size)?;
+        match self.allocations_start_from.compare_exchange(
+            Self::NOT_YET_OBTAINED,
+            obtained.to_usize(),
+            AcqRel,
+            Acquire,
+        ) {
+            Ok(_) => Ok(obtained.to_usize()),
+
+            Err(winner) => {
+                self.memory_source
+                    .release(self.memory_source_size, obtained);
+                Ok(winner)
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn allocation_ends_at_offset(
+        &self,
+        allocation_at_offset: usize,
+        non_zero_size: NonZeroUsize,
+    ) -> Result<usize, AllocError> {
+        match allocation_at_offset.checked_add(non_zero_size.get()) {
+            Some(allocation_ends_at_offset)
+                if likely!(allocation_ends_at_offset <= self.memory_source_size.get()) =>
+            {
+                Ok(allocation_ends_at_offset)
+            }
+
+            _ => Err(AllocError),
+        }
+    }
+
+    #[inline(always)]
+    fn offset(&self, current_memory: MemoryAddress) -> usize {
+        current_memory.to_usize() - self.allocations_start_from.load(Acquire)
+    }
+}
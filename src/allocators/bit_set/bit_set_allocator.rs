@@ -31,6 +31,9 @@ pub struct BitSetAllocator<MS: MemorySource> {
     memory_source_size: NonZeroUsize,
 }
 
+/// The bit set is only accessed through this allocator, so it may be moved to another thread.
+unsafe impl<MS: MemorySource + Send> Send for BitSetAllocator<MS> {}
+
 impl<MS: MemorySource> Drop for BitSetAllocator<MS> {
     #[inline(always)]
     fn drop(&mut self) {
@@ -268,6 +271,29 @@ impl<MS: MemorySource> BitSetAllocator<MS> {
         memory_source: MS,
         block_size: NonZeroUsize,
         number_of_blocks: NonZeroUsize,
+    ) -> Result<Self, AllocError> {
+        Self::create(memory_source, block_size, number_of_blocks, true)
+    }
+
+    /// Create a new instance using memory, and allocations, of an existing instance created with the same `block_size` and `number_of_blocks`; the bit set is not reset.
+    ///
+    /// Used to share one heap between processes: one process creates an instance using `new()` with a `SharedMemorySource`, and others attach to the same shared memory segment.
+    /// The bit set is position-independent, so the segment can be mapped at a different address in each process.
+    #[inline(always)]
+    pub fn attach(
+        memory_source: MS,
+        block_size: NonZeroUsize,
+        number_of_blocks: NonZeroUsize,
+    ) -> Result<Self, AllocError> {
+        Self::create(memory_source, block_size, number_of_blocks, false)
+    }
+
+    #[inline(always)]
+    fn create(
+        memory_source: MS,
+        block_size: NonZeroUsize,
+        number_of_blocks: NonZeroUsize,
+        initialize_bit_set: bool,
     ) -> Result<Self, AllocError> {
         debug_assert!(
             block_size.is_power_of_two(),
@@ -283,11 +309,14 @@ impl<MS: MemorySource> BitSetAllocator<MS> {
         let allocations_start_from = memory_source.obtain(memory_source_size)?;
 
         let allocations_end_at = allocations_start_from.add(size_in_bytes);
-        let (inclusive_start_of_bit_set, exclusive_end_of_bit_set) =
+        let (inclusive_start_of_bit_set, exclusive_end_of_bit_set) = if initialize_bit_set {
             Self::initialize_bit_set_so_all_memory_is_unallocated(
                 allocations_end_at,
                 bit_set_size_in_bytes,
-            );
+            )
+        } else {
+            Self::bit_set(allocations_end_at, bit_set_size_in_bytes)
+        };
 
         Ok(Self {
             inclusive_start_of_bit_set,
@@ -304,6 +333,24 @@ impl<MS: MemorySource> BitSetAllocator<MS> {
         })
     }
 
+    /// Discards all allocations so that all blocks are free again.
+    ///
+    /// All memory previously allocated becomes invalid.
+    #[inline(always)]
+    pub fn reset(&mut self) {
+        let bit_set_size_in_bytes = self.memory_source_size.get()
+            - self
+                .allocations_end_at
+                .difference(self.allocations_start_from);
+        let (inclusive_start_of_bit_set, _exclusive_end_of_bit_set) =
+            Self::initialize_bit_set_so_all_memory_is_unallocated(
+                self.allocations_end_at,
+                bit_set_size_in_bytes,
+            );
+        self.start_search_for_next_allocation_at
+            .set(inclusive_start_of_bit_set);
+    }
+
     #[inline(always)]
     fn initialize_bit_set_so_all_memory_is_unallocated(
         allocations_end_at: MemoryAddress,
@@ -314,6 +361,14 @@ impl<MS: MemorySource> BitSetAllocator<MS> {
                 .as_ptr()
                 .write_bytes(0x00, bit_set_size_in_bytes)
         };
+        Self::bit_set(allocations_end_at, bit_set_size_in_bytes)
+    }
+
+    #[inline(always)]
+    fn bit_set(
+        allocations_end_at: MemoryAddress,
+        bit_set_size_in_bytes: usize,
+    ) -> (BitSetWordPointer, BitSetWordPointer) {
         let inclusive_start_of_bit_set = BitSetWordPointer::wrap(allocations_end_at);
         (
             inclusive_start_of_bit_set,
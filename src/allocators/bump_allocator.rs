@@ -22,24 +22,33 @@ use std::num::NonZeroUsize;
 ///
 /// Is suitable for use with short-lived coroutines, such as those used to make a DNS query.
 ///
-/// This allocator NEVER grows or shrinks its memory region.
+/// This allocator NEVER shrinks its memory region, and only grows it if created with `new_growable()`.
+///
+/// If created with `new_with_decommit_threshold()`, `reset()` decommits the memory beyond the first `decommit_threshold` bytes (see `MemorySource::decommit()`), returning it to the operating system, say, after a spike; it is recommitted as allocations reach it again.
 ///
 /// This allocator is not thread-safe.
 #[derive(Debug)]
 pub struct BumpAllocator<MS: MemorySource> {
     most_recent_allocation_pointer: Cell<MemoryAddress>,
     next_allocation_at_pointer: Cell<MemoryAddress>,
-    ends_at_pointer: MemoryAddress,
+    allocations_start_from: MemoryAddress,
+    ends_at_pointer: Cell<MemoryAddress>,
+    committed_to_pointer: Cell<MemoryAddress>,
 
     memory_source: MS,
-    memory_source_size: NonZeroUsize,
+    memory_source_size: Cell<NonZeroUsize>,
+    decommit_threshold: NonZeroUsize,
+    reservation: Option<MemoryRange>,
 }
 
+/// Its pointers refer only to memory obtained by this allocator, so it may be moved to another thread.
+unsafe impl<MS: MemorySource + Send> Send for BumpAllocator<MS> {}
+
 impl<MS: MemorySource> Drop for BumpAllocator<MS> {
     #[inline(always)]
     fn drop(&mut self) {
         self.memory_source
-            .release(self.memory_source_size, self.allocations_start_from())
+            .release(self.memory_source_size.get(), self.allocations_start_from)
     }
 }
 
@@ -60,9 +69,14 @@ macro_rules! allocation_ends_at_pointer
 				unsafe { transmute(pointer) }
 			};
 
-			if unlikely!(allocation_ends_at_pointer > $self.ends_at_pointer)
+			if unlikely!(allocation_ends_at_pointer > $self.ends_at_pointer.get())
+			{
+				$self.extend_to(allocation_ends_at_pointer)?
+			}
+
+			if unlikely!(allocation_ends_at_pointer > $self.committed_to_pointer.get())
 			{
-				return Err(AllocError)
+				$self.recommit_to(allocation_ends_at_pointer)?
 			}
 
 			allocation_ends_at_pointer
@@ -164,31 +178,157 @@ impl<MS: MemorySource> Allocator for BumpAllocator<MS> {
 impl<MS: MemorySource> LocalAllocator for BumpAllocator<MS> {
     #[inline(always)]
     fn memory_range(&self) -> MemoryRange {
-        MemoryRange::new(self.allocations_start_from(), self.ends_at_pointer)
+        match self.reservation {
+            Some(reservation) => reservation,
+            None => MemoryRange::new(self.allocations_start_from, self.ends_at_pointer.get()),
+        }
     }
 }
 
 impl<MS: MemorySource> BumpAllocator<MS> {
     const MAXIMUM_POWER_OF_TWO_ALIGNMENT: NonZeroUsize = non_zero_usize(4096);
 
+    const NEVER_DECOMMIT: NonZeroUsize = non_zero_usize(usize::MAX);
+
     /// New instance wrapping a block of memory.
     #[inline(always)]
     pub fn new(memory_source: MS, memory_source_size: NonZeroUsize) -> Result<Self, AllocError> {
+        Self::new_with_decommit_threshold(memory_source, memory_source_size, Self::NEVER_DECOMMIT)
+    }
+
+    /// As `new()`, but `reset()` decommits the memory beyond the first `decommit_threshold` bytes.
+    ///
+    /// Only whole pages are decommitted; memory is recommitted at least `decommit_threshold` bytes at a time, so that a run of small allocations does not recommit for each one.
+    #[inline(always)]
+    pub fn new_with_decommit_threshold(
+        memory_source: MS,
+        memory_source_size: NonZeroUsize,
+        decommit_threshold: NonZeroUsize,
+    ) -> Result<Self, AllocError> {
         let allocations_start_from = memory_source.obtain(memory_source_size)?;
+        let ends_at_pointer = allocations_start_from.add_non_zero(memory_source_size);
 
         Ok(Self {
             most_recent_allocation_pointer: Cell::new(allocations_start_from),
             next_allocation_at_pointer: Cell::new(allocations_start_from),
-            ends_at_pointer: allocations_start_from.add_non_zero(memory_source_size),
+            allocations_start_from,
+            ends_at_pointer: Cell::new(ends_at_pointer),
+            committed_to_pointer: Cell::new(ends_at_pointer),
 
             memory_source,
-            memory_source_size,
+            memory_source_size: Cell::new(memory_source_size),
+            decommit_threshold,
+            reservation: None,
         })
     }
 
+    /// As `new()`, but, when exhausted, extends its memory in place using `MemorySource::extend()` (eg of a `ReservedMemorySource`), at least doubling it each time.
+    ///
+    /// `memory_range()` is then the whole of `MemorySource::reservation()`, so that it never changes whilst the allocator is registered with a `switchable_allocator!`; hence this allocator must be the only user of `memory_source`.
+    ///
+    /// Fails if `memory_source` has no reservation.
+    #[inline(always)]
+    pub fn new_growable(
+        memory_source: MS,
+        initial_memory_source_size: NonZeroUsize,
+    ) -> Result<Self, AllocError> {
+        let reservation = memory_source.reservation().ok_or(AllocError)?;
+        let mut this = Self::new(memory_source, initial_memory_source_size)?;
+        this.reservation = Some(reservation);
+        Ok(this)
+    }
+
+    /// Discards all allocations so that the memory can be reused from the start.
+    ///
+    /// All memory previously allocated becomes invalid.
+    ///
+    /// If created with `new_with_decommit_threshold()`, memory beyond the first `decommit_threshold` bytes is decommitted.
     #[inline(always)]
-    fn allocations_start_from(&self) -> MemoryAddress {
-        self.ends_at_pointer
-            .subtract_non_zero(self.memory_source_size)
+    pub fn reset(&mut self) {
+        let allocations_start_from = self.allocations_start_from;
+        self.most_recent_allocation_pointer
+            .set(allocations_start_from);
+        self.next_allocation_at_pointer.set(allocations_start_from);
+
+        let committed_to_pointer = self.committed_to_pointer.get();
+        if unlikely!(
+            committed_to_pointer.difference(allocations_start_from) > self.decommit_threshold.get()
+        ) {
+            let decommit_from_pointer =
+                allocations_start_from.add_non_zero(self.decommit_threshold);
+            self.memory_source.decommit(
+                committed_to_pointer
+                    .difference(decommit_from_pointer)
+                    .non_zero(),
+                decommit_from_pointer,
+            );
+            self.committed_to_pointer.set(decommit_from_pointer);
+        }
+    }
+
+    /// The memory source.
+    #[inline(always)]
+    pub fn memory_source(&self) -> &MS {
+        &self.memory_source
+    }
+
+    /// Only called once memory has been decommitted by `reset()`, as until then all memory is committed.
+    #[cold]
+    fn recommit_to(&self, allocation_ends_at_pointer: MemoryAddress) -> Result<(), AllocError> {
+        let committed_to_pointer = self.committed_to_pointer.get();
+        let recommit_to_pointer =
+            match committed_to_pointer.checked_add(self.decommit_threshold.get()) {
+                Some(pointer) if pointer < self.ends_at_pointer.get() => {
+                    pointer.max(allocation_ends_at_pointer)
+                }
+                _ => self.ends_at_pointer.get(),
+            };
+
+        self.memory_source.recommit(
+            recommit_to_pointer
+                .difference(committed_to_pointer)
+                .non_zero(),
+            committed_to_pointer,
+        )?;
+        self.committed_to_pointer.set(recommit_to_pointer);
+        Ok(())
+    }
+
+    /// Only succeeds if created with `new_growable()`; tries doubling first, then just enough for `allocation_ends_at_pointer`.
+    #[cold]
+    fn extend_to(&self, allocation_ends_at_pointer: MemoryAddress) -> Result<(), AllocError> {
+        if self.reservation.is_none() {
+            return Err(AllocError);
+        }
+
+        let current_size = self.memory_source_size.get();
+        let required_size = allocation_ends_at_pointer
+            .difference(self.allocations_start_from)
+            .non_zero();
+        let doubled_size = current_size
+            .get()
+            .saturating_mul(2)
+            .max(required_size.get())
+            .non_zero();
+
+        let new_size = if self
+            .memory_source
+            .extend(self.allocations_start_from, current_size, doubled_size)
+            .is_ok()
+        {
+            doubled_size
+        } else {
+            self.memory_source
+                .extend(self.allocations_start_from, current_size, required_size)?;
+            required_size
+        };
+
+        let ends_at_pointer = self.allocations_start_from.add_non_zero(new_size);
+        if self.committed_to_pointer.get() == self.ends_at_pointer.get() {
+            self.committed_to_pointer.set(ends_at_pointer)
+        }
+        self.ends_at_pointer.set(ends_at_pointer);
+        self.memory_source_size.set(new_size);
+        Ok(())
     }
 }
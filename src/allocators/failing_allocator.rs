@@ -0,0 +1,120 @@
+use crate::allocators::allocator::Allocator;
+use crate::allocators::global::local_allocator::LocalAllocator;
+use crate::allocators::global::memory_range::MemoryRange;
+use crate::memory_address::MemoryAddress;
+use crate::memory_sources::failing_memory_source::{FailureInjector, FailurePolicy};
+use std::alloc::{AllocError, AllocRef, GlobalAlloc, Layout};
+use std::num::NonZeroUsize;
+use std::ptr::NonNull;
+
+/// An allocator which fails allocations and reallocations according to a `FailurePolicy`, and otherwise passes them to an underlying allocator; for testing behaviour under allocation failure.
+///
+/// Allocations, growing reallocations and shrinking reallocations are each a call of their (new) size; deallocations are never failed.
+///
+/// Is a `LocalAllocator` if the underlying allocator is.
+///
+/// This allocator is not thread-safe.
+#[derive(Debug)]
+pub struct FailingAllocator<A: Allocator> {
+    failure_injector: FailureInjector,
+    allocator: A,
+}
+
+unsafe impl<A: Allocator> GlobalAlloc for FailingAllocator<A> {
+    crate::global_alloc!();
+}
+
+unsafe impl<A: Allocator> AllocRef for FailingAllocator<A> {
+    crate::alloc_ref!();
+}
+
+impl<A: Allocator> Allocator for FailingAllocator<A> {
+    #[inline(always)]
+    fn allocate(
+        &self,
+        non_zero_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+    ) -> Result<MemoryAddress, AllocError> {
+        self.failure_injector.call(non_zero_size)?;
+        self.allocator
+            .allocate(non_zero_size, non_zero_power_of_two_alignment)
+    }
+
+    #[inline(always)]
+    fn deallocate(
+        &self,
+        non_zero_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) {
+        self.allocator.deallocate(
+            non_zero_size,
+            non_zero_power_of_two_alignment,
+            current_memory,
+        )
+    }
+
+    #[inline(always)]
+    fn growing_reallocate(
+        &self,
+        non_zero_new_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+        non_zero_current_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> Result<MemoryAddress, AllocError> {
+        self.failure_injector.call(non_zero_new_size)?;
+        self.allocator.growing_reallocate(
+            non_zero_new_size,
+            non_zero_power_of_two_alignment,
+            non_zero_current_size,
+            current_memory,
+        )
+    }
+
+    #[inline(always)]
+    fn shrinking_reallocate(
+        &self,
+        non_zero_new_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+        non_zero_current_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> Result<MemoryAddress, AllocError> {
+        self.failure_injector.call(non_zero_new_size)?;
+        self.allocator.shrinking_reallocate(
+            non_zero_new_size,
+            non_zero_power_of_two_alignment,
+            non_zero_current_size,
+            current_memory,
+        )
+    }
+}
+
+impl<A: LocalAllocator> LocalAllocator for FailingAllocator<A> {
+    #[inline(always)]
+    fn memory_range(&self) -> MemoryRange {
+        self.allocator.memory_range()
+    }
+}
+
+impl<A: Allocator> FailingAllocator<A> {
+    /// Creates a new instance.
+    #[inline(always)]
+    pub fn new(allocator: A, policy: FailurePolicy) -> Self {
+        Self {
+            failure_injector: FailureInjector::new(policy),
+            allocator,
+        }
+    }
+
+    /// Counts of calls and which were failed.
+    #[inline(always)]
+    pub fn failure_injector(&self) -> &FailureInjector {
+        &self.failure_injector
+    }
+
+    /// The underlying allocator.
+    #[inline(always)]
+    pub fn allocator(&self) -> &A {
+        &self.allocator
+    }
+}
@@ -0,0 +1,225 @@
+use crate::allocators::allocator::Allocator;
+use crate::allocators::global::local_allocator::LocalAllocator;
+use crate::allocators::global::memory_range::MemoryRange;
+use crate::memory_address::MemoryAddress;
+use std::alloc::{AllocError, AllocRef, GlobalAlloc, Layout};
+use std::num::NonZeroUsize;
+use std::ptr::NonNull;
+
+/// An allocator which tries a primary allocator first and falls back to a secondary allocator when the primary fails.
+///
+/// It:-
+///
+/// * Uses `LocalAllocator::contains()` of the primary to decide which allocator to use to deallocate and reallocate memory; memory not contained by the primary belongs to the secondary.
+/// * Copies memory from the primary to the secondary when the primary can not grow (or shrink) it.
+///
+/// A typical usage is a small, fast arena (eg a `BumpAllocator`) in front of a general purpose allocator.
+///
+/// Is a `LocalAllocator` if the secondary is, too; its `memory_range()` is then not exact, but spans both (see there).
+#[derive(Debug)]
+pub struct FallbackAllocator<Primary: LocalAllocator, Secondary: Allocator> {
+    primary: Primary,
+    secondary: Secondary,
+}
+
+unsafe impl<Primary: LocalAllocator, Secondary: Allocator> GlobalAlloc
+    for FallbackAllocator<Primary, Secondary>
+{
+    crate::global_alloc!();
+}
+
+unsafe impl<Primary: LocalAllocator, Secondary: Allocator> AllocRef
+    for FallbackAllocator<Primary, Secondary>
+{
+    crate::alloc_ref!();
+}
+
+impl<Primary: LocalAllocator, Secondary: Allocator> Allocator
+    for FallbackAllocator<Primary, Secondary>
+{
+    #[inline(always)]
+    fn allocate(
+        &self,
+        non_zero_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+    ) -> Result<MemoryAddress, AllocError> {
+        match self
+            .primary
+            .allocate(non_zero_size, non_zero_power_of_two_alignment)
+        {
+            Ok(memory) => Ok(memory),
+
+            Err(AllocError) => self
+                .secondary
+                .allocate(non_zero_size, non_zero_power_of_two_alignment),
+        }
+    }
+
+    #[inline(always)]
+    fn deallocate(
+        &self,
+        non_zero_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) {
+        if likely!(self.primary.contains(current_memory)) {
+            self.primary.deallocate(
+                non_zero_size,
+                non_zero_power_of_two_alignment,
+                current_memory,
+            )
+        } else {
+            self.secondary.deallocate(
+                non_zero_size,
+                non_zero_power_of_two_alignment,
+                current_memory,
+            )
+        }
+    }
+
+    #[inline(always)]
+    fn growing_reallocate(
+        &self,
+        non_zero_new_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+        non_zero_current_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> Result<MemoryAddress, AllocError> {
+        if unlikely!(!self.primary.contains(current_memory)) {
+            return self.secondary.growing_reallocate(
+                non_zero_new_size,
+                non_zero_power_of_two_alignment,
+                non_zero_current_size,
+                current_memory,
+            );
+        }
+
+        match self.primary.growing_reallocate(
+            non_zero_new_size,
+            non_zero_power_of_two_alignment,
+            non_zero_current_size,
+            current_memory,
+        ) {
+            Ok(memory) => Ok(memory),
+
+            Err(AllocError) => self.move_to_secondary(
+                non_zero_new_size,
+                non_zero_power_of_two_alignment,
+                non_zero_current_size,
+                current_memory,
+                non_zero_current_size,
+            ),
+        }
+    }
+
+    #[inline(always)]
+    fn shrinking_reallocate(
+        &self,
+        non_zero_new_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+        non_zero_current_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> Result<MemoryAddress, AllocError> {
+        if unlikely!(!self.primary.contains(current_memory)) {
+            return self.secondary.shrinking_reallocate(
+                non_zero_new_size,
+                non_zero_power_of_two_alignment,
+                non_zero_current_size,
+                current_memory,
+            );
+        }
+
+        match self.primary.shrinking_reallocate(
+            non_zero_new_size,
+            non_zero_power_of_two_alignment,
+            non_zero_current_size,
+            current_memory,
+        ) {
+            Ok(memory) => Ok(memory),
+
+            Err(AllocError) => self.move_to_secondary(
+                non_zero_new_size,
+                non_zero_power_of_two_alignment,
+                non_zero_current_size,
+                current_memory,
+                non_zero_new_size,
+            ),
+        }
+    }
+}
+
+impl<Primary: LocalAllocator, Secondary: LocalAllocator> LocalAllocator
+    for FallbackAllocator<Primary, Secondary>
+{
+    /// The smallest memory range covering the memory ranges of both the primary and the secondary; it is ***NOT*** exact, as it may include memory owned by neither.
+    ///
+    /// `contains()` checks both parts, so is exact, but a `LocalAllocatorRegistry` routes memory freed by other threads by memory range alone.
+    /// Memory owned by another allocator but within this range, if freed by another thread, is then queued for this allocator, which leaks it.
+    /// Hence, when used as a coroutine local or thread local allocator, no other allocator should obtain memory between the parts, eg by giving both parts memory from one `ReservedMemorySource`.
+    #[inline(always)]
+    fn memory_range(&self) -> MemoryRange {
+        let primary = self.primary.memory_range();
+        let secondary = self.secondary.memory_range();
+
+        if unlikely!(primary.from == primary.to) {
+            return secondary;
+        }
+        if unlikely!(secondary.from == secondary.to) {
+            return primary;
+        }
+        MemoryRange::new(
+            primary.from.min(secondary.from),
+            primary.to.max(secondary.to),
+        )
+    }
+
+    #[inline(always)]
+    fn contains(&self, from_memory_address: MemoryAddress) -> bool {
+        self.primary.contains(from_memory_address) || self.secondary.contains(from_memory_address)
+    }
+}
+
+impl<Primary: LocalAllocator, Secondary: Allocator> FallbackAllocator<Primary, Secondary> {
+    /// Creates a new instance.
+    #[inline(always)]
+    pub const fn new(primary: Primary, secondary: Secondary) -> Self {
+        Self { primary, secondary }
+    }
+
+    /// The primary allocator.
+    #[inline(always)]
+    pub fn primary(&self) -> &Primary {
+        &self.primary
+    }
+
+    /// The secondary allocator.
+    #[inline(always)]
+    pub fn secondary(&self) -> &Secondary {
+        &self.secondary
+    }
+
+    #[inline(always)]
+    fn move_to_secondary(
+        &self,
+        non_zero_new_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+        non_zero_current_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+        non_zero_size_to_copy: NonZeroUsize,
+    ) -> Result<MemoryAddress, AllocError> {
+        let new_memory = self
+            .secondary
+            .allocate(non_zero_new_size, non_zero_power_of_two_alignment)?;
+        unsafe {
+            new_memory
+                .as_ptr()
+                .copy_from_nonoverlapping(current_memory.as_ptr(), non_zero_size_to_copy.get())
+        };
+        self.primary.deallocate(
+            non_zero_current_size,
+            non_zero_power_of_two_alignment,
+            current_memory,
+        );
+        Ok(new_memory)
+    }
+}
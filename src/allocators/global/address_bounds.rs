@@ -0,0 +1,70 @@
+use crate::allocators::global::memory_range::MemoryRange;
+use crate::extensions::prelude::*;
+use crate::memory_address::MemoryAddress;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::{Acquire, Release};
+
+/// The lowest and highest addresses of a set of memory ranges, so that an address outside all of them can be rejected without examining each.
+///
+/// Changed only whilst holding the lock guarding the set; read without it.
+/// Whilst being changed, the bounds still cover every memory range in the set both before and after the change.
+#[derive(Debug)]
+pub struct AddressBounds {
+    from: AtomicUsize,
+    to: AtomicUsize,
+}
+
+impl AddressBounds {
+    const EMPTY_FROM: usize = usize::MAX;
+
+    const EMPTY_TO: usize = 0;
+
+    /// Creates a new instance bounding no memory ranges.
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self {
+            from: AtomicUsize::new(Self::EMPTY_FROM),
+            to: AtomicUsize::new(Self::EMPTY_TO),
+        }
+    }
+
+    /// Is `address` within the bounds?
+    ///
+    /// If `false`, no memory range in the set contains it; if `true`, one may.
+    #[inline(always)]
+    pub fn may_contain(&self, address: MemoryAddress) -> bool {
+        let address = address.to_usize();
+        address >= self.from.load(Acquire) && address < self.to.load(Acquire)
+    }
+
+    /// Widens the bounds to cover `memory_range`, which is being added to the set.
+    #[inline(always)]
+    pub fn widen(&self, memory_range: MemoryRange) {
+        let from = memory_range.from.to_usize();
+        if from < self.from.load(Acquire) {
+            self.from.store(from, Release)
+        }
+
+        let to = memory_range.to.to_usize();
+        if to > self.to.load(Acquire) {
+            self.to.store(to, Release)
+        }
+    }
+
+    /// Narrows the bounds to just cover `memory_ranges`, which remain in the set after one or more have been removed.
+    #[inline(always)]
+    pub fn narrow(&self, memory_ranges: impl Iterator<Item = MemoryRange>) {
+        let (from, to) = memory_ranges.fold(
+            (Self::EMPTY_FROM, Self::EMPTY_TO),
+            |(from, to), memory_range| {
+                (
+                    from.min(memory_range.from.to_usize()),
+                    to.max(memory_range.to.to_usize()),
+                )
+            },
+        );
+
+        self.from.store(from, Release);
+        self.to.store(to, Release)
+    }
+}
@@ -0,0 +1,39 @@
+use crate::allocators::global::current_allocator_in_use::CurrentAllocatorInUse;
+use crate::allocators::global::global_switchable_allocator::GlobalSwitchableAllocator;
+
+/// Restores the current allocator in use when dropped, including when unwinding from a panic.
+///
+/// Create using `GlobalSwitchableAllocator::enter()`.
+#[derive(Debug)]
+#[must_use = "The previous allocator in use is restored as soon as the guard is dropped"]
+pub struct AllocatorScopeGuard<'a, GSA: GlobalSwitchableAllocator + ?Sized> {
+    global_switchable_allocator: &'a GSA,
+    restore_to: CurrentAllocatorInUse,
+}
+
+impl<'a, GSA: GlobalSwitchableAllocator + ?Sized> Drop for AllocatorScopeGuard<'a, GSA> {
+    #[inline(always)]
+    fn drop(&mut self) {
+        self.global_switchable_allocator
+            .restore_current_allocator_in_use(self.restore_to)
+    }
+}
+
+impl<'a, GSA: GlobalSwitchableAllocator + ?Sized> AllocatorScopeGuard<'a, GSA> {
+    #[inline(always)]
+    pub(crate) fn new(
+        global_switchable_allocator: &'a GSA,
+        restore_to: CurrentAllocatorInUse,
+    ) -> Self {
+        Self {
+            global_switchable_allocator,
+            restore_to,
+        }
+    }
+
+    /// The allocator in use that will be restored.
+    #[inline(always)]
+    pub fn restore_to(&self) -> CurrentAllocatorInUse {
+        self.restore_to
+    }
+}
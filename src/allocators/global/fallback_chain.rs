@@ -0,0 +1,21 @@
+/// Where allocations go when the current allocator in use is absent (not assigned or initialized) or exhausted.
+///
+/// Defaults to `ThreadLocalThenGlobal`.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum FallbackChain {
+    /// Do not spill; the allocation fails.
+    None,
+
+    /// Coroutine local and thread local allocations spill to the global allocator.
+    Global,
+
+    /// Coroutine local allocations spill to the thread local allocator, then to the global allocator; thread local allocations spill to the global allocator.
+    ThreadLocalThenGlobal,
+}
+
+impl Default for FallbackChain {
+    #[inline(always)]
+    fn default() -> Self {
+        FallbackChain::ThreadLocalThenGlobal
+    }
+}
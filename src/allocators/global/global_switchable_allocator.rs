@@ -1,6 +1,14 @@
 use crate::allocators::allocator::Allocator;
+use crate::allocators::global::allocator_scope_guard::AllocatorScopeGuard;
 use crate::allocators::global::current_allocator_in_use::CurrentAllocatorInUse;
+use crate::allocators::global::fallback_chain::FallbackChain;
 use crate::allocators::global::local_allocator::LocalAllocator;
+use crate::allocators::global::local_allocator_registry::LocalAllocatorRegistration;
+use crate::allocators::global::orphaned_local_allocators::OrphanedLocalAllocators;
+use crate::allocators::global::spills::Spills;
+use crate::allocators::global::thread_exit_leaks::ThreadExitLeaks;
+use crate::allocators::global::thread_exit_policy::ThreadExitPolicy;
+use crate::memory_address::MemoryAddress;
 use std::alloc::{AllocRef, GlobalAlloc};
 
 /// A trait that all such allocators implement.
@@ -26,6 +34,52 @@ pub trait GlobalSwitchableAllocator: Sync + GlobalAlloc + AllocRef + Allocator {
         replacement: Option<Self::CoroutineLocalAllocator>,
     ) -> Option<Self::CoroutineLocalAllocator>;
 
+    /// As `replace_coroutine_local_allocator()`, but `replacement` keeps the registration, if any, it was given by `register_coroutine_local_allocator()`, and the replaced coroutine local allocator is returned with its registration rather than being unregistered.
+    ///
+    /// Used to swap in the arena of an async task for each poll whilst keeping it registered between polls; see `WithAllocator`.
+    fn replace_registered_coroutine_local_allocator(
+        &self,
+        replacement: Option<(
+            Self::CoroutineLocalAllocator,
+            Option<LocalAllocatorRegistration>,
+        )>,
+    ) -> Option<(
+        Self::CoroutineLocalAllocator,
+        Option<LocalAllocatorRegistration>,
+    )>;
+
+    /// Registers a coroutine local allocator which is not in use, so that memory it allocated which is freed, by any thread, before it is next swapped in is queued for it rather than passed to the global allocator.
+    ///
+    /// Returns `None` if there are too many registrations.
+    fn register_coroutine_local_allocator(
+        &self,
+        coroutine_local_allocator: &Self::CoroutineLocalAllocator,
+    ) -> Option<LocalAllocatorRegistration>;
+
+    /// Frees memory queued for a coroutine local allocator which is not in use, then unregisters it; it can then be dropped.
+    fn unregister_coroutine_local_allocator(
+        &self,
+        coroutine_local_allocator: &Self::CoroutineLocalAllocator,
+        registration: LocalAllocatorRegistration,
+    );
+
+    /// Pushes a coroutine local allocator, which becomes the current coroutine local allocator, eg when entering a nested scope.
+    ///
+    /// The previous coroutine local allocator, if any, is kept until popped with `pop_coroutine_local_allocator()`; memory it allocated can still be deallocated and reallocated, and memory other threads free to it is still freed.
+    /// Coroutine local allocators still kept when the thread exits are dropped (Unix only).
+    ///
+    /// Returns `pushed` if memory to keep the previous coroutine local allocator could not be allocated from the global allocator.
+    fn push_coroutine_local_allocator(
+        &self,
+        pushed: Self::CoroutineLocalAllocator,
+    ) -> Result<(), Self::CoroutineLocalAllocator>;
+
+    /// Pops the current coroutine local allocator, eg when leaving a nested scope; the previously pushed coroutine local allocator, if any, becomes current again.
+    fn pop_coroutine_local_allocator(&self) -> Option<Self::CoroutineLocalAllocator>;
+
+    /// Number of coroutine local allocators, current and pushed, on the current thread.
+    fn coroutine_local_allocator_depth(&self) -> usize;
+
     /// Initializes the thread local allocator.
     fn initialize_thread_local_allocator(&self, thread_local_allocator: Self::ThreadLocalAllocator);
 
@@ -33,9 +87,31 @@ pub trait GlobalSwitchableAllocator: Sync + GlobalAlloc + AllocRef + Allocator {
     ///
     /// Panics in debug if no thread local allocator has been initialized with `initialize_thread_local_allocator()`.
     ///
-    /// Could be made hidden by using a destructor with `libc::pthread_key_create()` for an otherwise unused key.
+    /// On Unix, this is called automatically when the thread exits.
+    /// If memory allocated by the thread local allocator is still in use, the current thread's `ThreadExitPolicy` applies.
     fn drop_thread_local_allocator(&self);
 
+    /// Sets what happens to the current thread's thread local allocator if memory it allocated is still in use when it is dropped.
+    ///
+    /// Defaults to `ThreadExitPolicy::Drop`.
+    fn set_thread_exit_policy(&self, thread_exit_policy: ThreadExitPolicy);
+
+    /// Thread local allocators, from all threads, dropped with `ThreadExitPolicy::Drop` whilst memory they allocated was still in use.
+    fn thread_exit_leaks(&self) -> &ThreadExitLeaks;
+
+    /// Thread local allocators, from all threads, handed to the global tier when dropped whilst memory they allocated was still in use.
+    fn orphaned_thread_local_allocators(
+        &self,
+    ) -> &OrphanedLocalAllocators<Self::ThreadLocalAllocator>;
+
+    /// Sets where the current thread's allocations go when the coroutine local or thread local allocator is absent or exhausted.
+    ///
+    /// Defaults to `FallbackChain::ThreadLocalThenGlobal`.
+    fn set_fallback_chain(&self, fallback_chain: FallbackChain);
+
+    /// Allocations, from all threads, which spilled from the coroutine local or thread local allocator to the next allocator in the `FallbackChain`.
+    fn spills(&self) -> &Spills;
+
     /// Save the current allocator in use.
     fn save_current_allocator_in_use(&self) -> CurrentAllocatorInUse;
 
@@ -53,7 +129,7 @@ pub trait GlobalSwitchableAllocator: Sync + GlobalAlloc + AllocRef + Allocator {
         was
     }
 
-    /// Switch the current allocator in use to coroutine local and execute the callback; restore it after calling the callback unless a panic occurs.
+    /// Switch the current allocator in use to coroutine local and execute the callback; restore it after calling the callback, even if the callback panics.
     #[inline(always)]
     fn callback_with_coroutine_local_allocator<R>(&self, callback: impl FnOnce() -> R) -> R {
         self.callback_with_different_current_allocator(
@@ -62,35 +138,44 @@ pub trait GlobalSwitchableAllocator: Sync + GlobalAlloc + AllocRef + Allocator {
         )
     }
 
-    /// Switch the current allocator in use to thread local and execute the callback; restore it after calling the callback unless a panic occurs.
+    /// Switch the current allocator in use to thread local and execute the callback; restore it after calling the callback, even if the callback panics.
     #[inline(always)]
     fn callback_with_thread_local_allocator<R>(&self, callback: impl FnOnce() -> R) -> R {
         self.callback_with_different_current_allocator(CurrentAllocatorInUse::ThreadLocal, callback)
     }
 
-    /// Switch the current allocator in use to global and execute the callback; restore it after calling the callback unless a panic occurs.
+    /// Switch the current allocator in use to global and execute the callback; restore it after calling the callback, even if the callback panics.
     #[inline(always)]
     fn callback_with_global_allocator<R>(&self, callback: impl FnOnce() -> R) -> R {
         self.callback_with_different_current_allocator(CurrentAllocatorInUse::Global, callback)
     }
 
-    /// Switch the current allocator in use and execute the callback; restore it after calling the callback unless a panic occurs.
+    /// Switch the current allocator in use and execute the callback; restore it after calling the callback, even if the callback panics.
     #[inline(always)]
     fn callback_with_different_current_allocator<R>(
         &self,
         different: CurrentAllocatorInUse,
         callback: impl FnOnce() -> R,
     ) -> R {
-        let restore_to = self.save_current_allocator_in_use();
-        self.restore_current_allocator_in_use(different);
-        let result = callback();
-        self.restore_current_allocator_in_use(restore_to);
-        result
+        let _guard = self.enter(different);
+        callback()
+    }
+
+    /// Switch the current allocator in use until the returned guard is dropped, which restores the previous allocator in use (even when unwinding from a panic).
+    #[inline(always)]
+    fn enter(&self, different: CurrentAllocatorInUse) -> AllocatorScopeGuard<Self> {
+        AllocatorScopeGuard::new(self, self.replace_current_allocator_in_use(different))
     }
 
     /// Obtain the current coroutine local allocator, if any.
     fn coroutine_local_allocator(&self) -> Option<&Self::CoroutineLocalAllocator>;
 
+    /// Obtain the coroutine local allocator, current or pushed, that allocated `current_memory`, if any.
+    fn coroutine_local_allocator_containing(
+        &self,
+        current_memory: MemoryAddress,
+    ) -> Option<&Self::CoroutineLocalAllocator>;
+
     /// Obtain the coroutine local allocator.
     ///
     /// Panics if no coroutine local allocator has been assigned with `replace_coroutine_local_allocator()`.
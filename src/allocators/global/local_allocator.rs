@@ -8,6 +8,8 @@ use crate::memory_address::MemoryAddress;
 pub trait LocalAllocator: Allocator {
     /// The range of memory addresses that can be used to allocate memory by this allocator.
     ///
+    /// A `LocalAllocatorRegistry` uses it to route memory freed by other threads, so it should not include memory allocated by other allocators.
+    ///
     /// This function is called repeatedly, so ideally should be inline and fast.
     fn memory_range(&self) -> MemoryRange;
 
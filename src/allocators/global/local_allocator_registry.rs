@@ -0,0 +1,207 @@
+use crate::allocators::global::address_bounds::AddressBounds;
+use crate::allocators::global::local_allocator::LocalAllocator;
+use crate::allocators::global::memory_range::MemoryRange;
+use crate::allocators::global::remote_free_queue::RemoteFreeQueue;
+use crate::allocators::locked::allocator_lock::AllocatorLock;
+use crate::allocators::locked::spin_lock::SpinLock;
+use crate::extensions::prelude::*;
+use crate::memory_address::MemoryAddress;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use std::sync::atomic::{fence, AtomicUsize};
+
+/// Identifies a local allocator registered with a `LocalAllocatorRegistry`.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub struct LocalAllocatorRegistration(usize);
+
+/// A registry, shared by all threads, of the memory ranges of coroutine and thread local allocators.
+///
+/// When memory is freed by a thread other than the one whose local allocator allocated it, the registry is used to find the owning allocator's `RemoteFreeQueue`; the owning thread then frees the memory on its next allocation (as done by mimalloc).
+///
+/// Registering and unregistering take a lock and are relatively slow; finding an owner is lock-free, and memory outside the bounds of all registered memory ranges, such as most memory of the global allocator, is rejected without scanning the registrations.
+///
+/// Memory must not be freed by another thread whilst its owning allocator is being unregistered: reusing a registration discards its remote free queue, so memory pushed concurrently with `register()` or `unregister()` is leaked.
+///
+/// Owners are found by `LocalAllocator::memory_range()` alone; memory within a registered range which the allocator's `contains()` rejects (eg between the parts of a `FallbackAllocator`) is leaked when freed by another thread.
+#[derive(Debug)]
+pub struct LocalAllocatorRegistry {
+    lock: SpinLock,
+    bounds: AddressBounds,
+    high_water_mark: AtomicUsize,
+    registrations: [Registration; Self::MAXIMUM_NUMBER_OF_REGISTRATIONS],
+}
+
+impl LocalAllocatorRegistry {
+    /// Local allocators registered after this number are not tracked, and memory they allocated is leaked if freed by another thread.
+    pub const MAXIMUM_NUMBER_OF_REGISTRATIONS: usize = 1024;
+
+    /// Creates a new, empty instance.
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self {
+            lock: SpinLock::UNLOCKED,
+            bounds: AddressBounds::new(),
+            high_water_mark: AtomicUsize::new(0),
+            registrations: [Registration::UNREGISTERED; Self::MAXIMUM_NUMBER_OF_REGISTRATIONS],
+        }
+    }
+
+    /// Registers the memory range of a local allocator.
+    ///
+    /// Returns `None` if there are too many registrations.
+    #[inline(always)]
+    pub fn register(&self, memory_range: MemoryRange) -> Option<LocalAllocatorRegistration> {
+        self.lock.lock();
+
+        let result = self
+            .registrations
+            .iter()
+            .position(|registration| registration.is_unregistered())
+            .map(|index| {
+                self.registrations[index]
+                    .update(memory_range.from.to_usize(), memory_range.to.to_usize());
+
+                if index >= self.high_water_mark.load(Relaxed) {
+                    self.high_water_mark.store(index + 1, Release)
+                }
+                self.bounds.widen(memory_range);
+
+                LocalAllocatorRegistration(index)
+            });
+
+        self.lock.unlock();
+        result
+    }
+
+    /// Unregisters a local allocator.
+    ///
+    /// Any memory still queued for it, or pushed whilst unregistering, is forgotten; use `free_remotely_freed()` first.
+    #[inline(always)]
+    pub fn unregister(&self, registration: LocalAllocatorRegistration) {
+        self.lock.lock();
+        self.registrations[registration.0].update(
+            Registration::UNREGISTERED_ADDRESS,
+            Registration::UNREGISTERED_ADDRESS,
+        );
+        self.bounds.narrow(
+            self.registrations[..self.high_water_mark.load(Relaxed)]
+                .iter()
+                .filter(|registration| !registration.is_unregistered())
+                .map(|registration| registration.memory_range()),
+        );
+        self.lock.unlock();
+    }
+
+    /// Finds the remote free queue of the local allocator which allocated `current_memory`, if any.
+    #[inline(always)]
+    pub fn remote_free_queue_for(&self, current_memory: MemoryAddress) -> Option<&RemoteFreeQueue> {
+        if !self.bounds.may_contain(current_memory) {
+            return None;
+        }
+
+        let address = current_memory.to_usize();
+        let high_water_mark = self.high_water_mark.load(Acquire);
+
+        self.registrations[..high_water_mark]
+            .iter()
+            .find(|registration| registration.contains(address))
+            .map(|registration| &registration.remote_free_queue)
+    }
+
+    /// Frees memory queued by other threads for the local allocator; called only by the thread owning `local_allocator`.
+    ///
+    /// Returns the number of allocations freed.
+    #[inline(always)]
+    pub fn free_remotely_freed<LA: LocalAllocator>(
+        &self,
+        registration: LocalAllocatorRegistration,
+        local_allocator: &LA,
+    ) -> usize {
+        let remote_free_queue = &self.registrations[registration.0].remote_free_queue;
+
+        if likely!(remote_free_queue.is_empty()) {
+            return 0;
+        }
+
+        let mut number_freed = 0;
+        remote_free_queue.drain(
+            |current_memory, non_zero_size, non_zero_power_of_two_alignment| {
+                // A stale push may have raced with a previous owner of this registration being unregistered.
+                if likely!(local_allocator.contains(current_memory)) {
+                    local_allocator.deallocate(
+                        non_zero_size,
+                        non_zero_power_of_two_alignment,
+                        current_memory,
+                    );
+                    number_freed += 1
+                }
+            },
+        );
+        number_freed
+    }
+}
+
+/// Uses a sequence lock so that a memory range can be read consistently without taking the registry's lock.
+#[derive(Debug)]
+struct Registration {
+    /// Odd whilst being updated.
+    sequence: AtomicUsize,
+    from: AtomicUsize,
+    to: AtomicUsize,
+    remote_free_queue: RemoteFreeQueue,
+}
+
+impl Registration {
+    const UNREGISTERED_ADDRESS: usize = 0;
+
+    const UNREGISTERED: Self = Self {
+        sequence: AtomicUsize::new(0),
+        from: AtomicUsize::new(Self::UNREGISTERED_ADDRESS),
+        to: AtomicUsize::new(Self::UNREGISTERED_ADDRESS),
+        remote_free_queue: RemoteFreeQueue::new(),
+    };
+
+    /// Only called with the registry's lock held.
+    #[inline(always)]
+    fn is_unregistered(&self) -> bool {
+        self.to.load(Relaxed) == Self::UNREGISTERED_ADDRESS
+    }
+
+    /// Only called with the registry's lock held.
+    #[inline(always)]
+    fn memory_range(&self) -> MemoryRange {
+        MemoryRange::new(
+            MemoryAddress::from_usize(self.from.load(Relaxed)),
+            MemoryAddress::from_usize(self.to.load(Relaxed)),
+        )
+    }
+
+    /// Only called with the registry's lock held.
+    #[inline(always)]
+    fn update(&self, from: usize, to: usize) {
+        let sequence = self.sequence.load(Relaxed);
+        self.sequence.store(sequence.wrapping_add(1), Relaxed);
+        fence(Release);
+
+        self.remote_free_queue.discard();
+        self.from.store(from, Relaxed);
+        self.to.store(to, Relaxed);
+
+        self.sequence.store(sequence.wrapping_add(2), Release);
+    }
+
+    #[inline(always)]
+    fn contains(&self, address: usize) -> bool {
+        let sequence_before = self.sequence.load(Acquire);
+        if unlikely!(sequence_before & 1 == 1) {
+            return false;
+        }
+
+        let from = self.from.load(Relaxed);
+        let to = self.to.load(Relaxed);
+
+        fence(Acquire);
+        let sequence_after = self.sequence.load(Relaxed);
+
+        sequence_before == sequence_after && address >= from && address < to
+    }
+}
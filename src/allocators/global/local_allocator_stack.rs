@@ -0,0 +1,152 @@
+use crate::allocators::allocator::Allocator;
+use crate::allocators::global::local_allocator::LocalAllocator;
+use crate::allocators::global::local_allocator_registry::{
+    LocalAllocatorRegistration, LocalAllocatorRegistry,
+};
+use crate::extensions::prelude::*;
+use crate::memory_address::MemoryAddress;
+use std::fmt;
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::mem::{align_of, size_of};
+use std::num::NonZeroUsize;
+use std::ptr::{null_mut, read, write, NonNull};
+
+/// Local allocators that have been pushed down by a newer local allocator, for nested scopes (eg request, then sub-task, then coroutine).
+///
+/// Only the newest local allocator, which is not held here, allocates; memory allocated by those held here can still be deallocated and reallocated until they are popped.
+///
+/// Records of the local allocators are allocated from a global allocator, so pushing can fail.
+///
+/// Not thread-safe; intended to be a field of a thread-local static.
+pub struct LocalAllocatorStack<LA: LocalAllocator> {
+    top: *mut Entry<LA>,
+    depth: usize,
+}
+
+impl<LA: LocalAllocator> Debug for LocalAllocatorStack<LA> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "LocalAllocatorStack({})", self.depth)
+    }
+}
+
+impl<LA: LocalAllocator> LocalAllocatorStack<LA> {
+    /// Creates a new, empty instance.
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self {
+            top: null_mut(),
+            depth: 0,
+        }
+    }
+
+    /// Number of local allocators held.
+    #[inline(always)]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Pushes `local_allocator` and its registration, if any.
+    ///
+    /// The record is allocated from `global_allocator`; if that fails, `local_allocator` is returned.
+    #[inline(always)]
+    pub fn push<GA: Allocator>(
+        &mut self,
+        global_allocator: &GA,
+        local_allocator: LA,
+        registration: Option<LocalAllocatorRegistration>,
+    ) -> Result<(), LA> {
+        let entry = match global_allocator.allocate(Self::entry_size(), Self::entry_alignment()) {
+            Ok(memory) => memory.as_ptr() as *mut Entry<LA>,
+
+            Err(_) => return Err(local_allocator),
+        };
+
+        unsafe {
+            write(
+                entry,
+                Entry {
+                    next: self.top,
+                    local_allocator,
+                    registration,
+                },
+            )
+        };
+        self.top = entry;
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Pops the most recently pushed local allocator and its registration, freeing its record using `global_allocator`.
+    #[inline(always)]
+    pub fn pop<GA: Allocator>(
+        &mut self,
+        global_allocator: &GA,
+    ) -> Option<(LA, Option<LocalAllocatorRegistration>)> {
+        if self.top.is_null() {
+            return None;
+        }
+
+        let entry = self.top;
+        let Entry {
+            next,
+            local_allocator,
+            registration,
+        } = unsafe { read(entry) };
+        self.top = next;
+        self.depth -= 1;
+
+        global_allocator.deallocate(Self::entry_size(), Self::entry_alignment(), unsafe {
+            NonNull::new_unchecked(entry as *mut u8)
+        });
+        Some((local_allocator, registration))
+    }
+
+    /// Finds the local allocator that allocated `current_memory`, searching from the most recently pushed.
+    #[inline(always)]
+    pub fn owner_of(&self, current_memory: MemoryAddress) -> Option<&LA> {
+        let mut entry = self.top;
+        while !entry.is_null() {
+            let this = unsafe { &*entry };
+            if this.local_allocator.contains(current_memory) {
+                return Some(&this.local_allocator);
+            }
+            entry = this.next;
+        }
+        None
+    }
+
+    /// Frees memory queued by other threads for every local allocator held, not just the most recently pushed.
+    ///
+    /// Returns the number of allocations freed.
+    #[inline(always)]
+    pub fn free_remotely_freed(&self, local_allocator_registry: &LocalAllocatorRegistry) -> usize {
+        let mut number_freed = 0;
+        let mut entry = self.top;
+        while !entry.is_null() {
+            let this = unsafe { &*entry };
+            if let Some(registration) = this.registration {
+                number_freed += local_allocator_registry
+                    .free_remotely_freed(registration, &this.local_allocator);
+            }
+            entry = this.next;
+        }
+        number_freed
+    }
+
+    #[inline(always)]
+    fn entry_size() -> NonZeroUsize {
+        size_of::<Entry<LA>>().non_zero()
+    }
+
+    #[inline(always)]
+    fn entry_alignment() -> NonZeroUsize {
+        align_of::<Entry<LA>>().non_zero()
+    }
+}
+
+struct Entry<LA: LocalAllocator> {
+    next: *mut Entry<LA>,
+    local_allocator: LA,
+    registration: Option<LocalAllocatorRegistration>,
+}
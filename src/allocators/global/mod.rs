@@ -1,17 +1,43 @@
+pub mod address_bounds;
+pub mod allocator_scope_guard;
 pub mod current_allocator_in_use;
+pub mod fallback_chain;
 pub mod global_switchable_allocator;
 pub mod local_allocator;
+pub mod local_allocator_registry;
+pub mod local_allocator_stack;
 pub mod memory_range;
+pub mod orphaned_local_allocators;
 pub mod per_thread_state;
+pub mod remote_free_queue;
+pub mod spills;
+#[cfg(unix)]
+pub mod thread_exit_hook;
+pub mod thread_exit_leaks;
+pub mod thread_exit_policy;
+pub mod with_allocator;
 #[macro_use]
 pub mod switchable_allocator;
 
 #[macro_use]
 pub mod prelude {
+    pub use super::address_bounds::*;
+    pub use super::allocator_scope_guard::*;
     pub use super::current_allocator_in_use::*;
+    pub use super::fallback_chain::*;
     pub use super::global_switchable_allocator::*;
     pub use super::local_allocator::*;
+    pub use super::local_allocator_registry::*;
+    pub use super::local_allocator_stack::*;
     pub use super::memory_range::*;
+    pub use super::orphaned_local_allocators::*;
     pub use super::per_thread_state::*;
+    pub use super::remote_free_queue::*;
+    pub use super::spills::*;
     pub use super::switchable_allocator::*;
+    #[cfg(unix)]
+    pub use super::thread_exit_hook::*;
+    pub use super::thread_exit_leaks::*;
+    pub use super::thread_exit_policy::*;
+    pub use super::with_allocator::*;
 }
@@ -0,0 +1,222 @@
+use crate::allocators::allocator::Allocator;
+use crate::allocators::global::address_bounds::AddressBounds;
+use crate::allocators::global::local_allocator::LocalAllocator;
+use crate::allocators::global::memory_range::MemoryRange;
+use crate::allocators::locked::allocator_lock::AllocatorLock;
+use crate::allocators::locked::locked_allocator::UnlockOnDrop;
+use crate::allocators::locked::spin_lock::SpinLock;
+use crate::extensions::prelude::*;
+use crate::memory_address::MemoryAddress;
+use std::fmt;
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::iter::from_fn;
+use std::mem::{align_of, forget, size_of};
+use std::num::NonZeroUsize;
+use std::ptr::{drop_in_place, null_mut, write, NonNull};
+use std::sync::atomic::AtomicPtr;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+
+/// Local allocators whose thread has exited whilst memory they allocated was still in use.
+///
+/// They are adopted by the global tier, which frees their memory as it is deallocated by other threads and drops each once all of its memory has been freed.
+///
+/// Checking whether memory is owned by an orphan is cheap when it is outside the bounds of all orphans' memory ranges (always so when there are no orphans); otherwise it takes a lock.
+pub struct OrphanedLocalAllocators<LA: LocalAllocator> {
+    lock: SpinLock,
+    bounds: AddressBounds,
+    head: AtomicPtr<Orphan<LA>>,
+}
+
+unsafe impl<LA: LocalAllocator> Send for OrphanedLocalAllocators<LA> {}
+
+unsafe impl<LA: LocalAllocator> Sync for OrphanedLocalAllocators<LA> {}
+
+impl<LA: LocalAllocator> Debug for OrphanedLocalAllocators<LA> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "OrphanedLocalAllocators")
+    }
+}
+
+impl<LA: LocalAllocator> OrphanedLocalAllocators<LA> {
+    /// Creates a new instance with no orphans.
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self {
+            lock: SpinLock::UNLOCKED,
+            bounds: AddressBounds::new(),
+            head: AtomicPtr::new(null_mut()),
+        }
+    }
+
+    /// Adopts `local_allocator`, which has `allocations_outstanding` allocations still in use.
+    ///
+    /// The record of the orphan is allocated from `global_allocator`; if that fails, `local_allocator` is leaked so that its memory remains valid.
+    #[inline(always)]
+    pub fn adopt<GA: Allocator>(
+        &self,
+        global_allocator: &GA,
+        local_allocator: LA,
+        allocations_outstanding: usize,
+    ) {
+        let orphan = match global_allocator.allocate(Self::orphan_size(), Self::orphan_alignment())
+        {
+            Ok(memory) => memory.as_ptr() as *mut Orphan<LA>,
+
+            Err(_) => {
+                forget(local_allocator);
+                return;
+            }
+        };
+
+        self.lock.lock();
+        let _guard = UnlockOnDrop(&self.lock);
+
+        self.bounds.widen(local_allocator.memory_range());
+        unsafe {
+            write(
+                orphan,
+                Orphan {
+                    next: self.head.load(Relaxed),
+                    allocations_outstanding,
+                    local_allocator,
+                },
+            )
+        };
+        self.head.store(orphan, Release)
+    }
+
+    /// Was `current_memory` allocated by an orphan?
+    #[inline(always)]
+    pub fn contains(&self, current_memory: MemoryAddress) -> bool {
+        if likely!(!self.bounds.may_contain(current_memory)) {
+            return false;
+        }
+
+        self.lock.lock();
+        let _guard = UnlockOnDrop(&self.lock);
+
+        let mut orphan = self.head.load(Relaxed);
+        while !orphan.is_null() {
+            let this = unsafe { &*orphan };
+            if this.local_allocator.contains(current_memory) {
+                return true;
+            }
+            orphan = this.next;
+        }
+        false
+    }
+
+    /// Number of orphans; each is dropped once all of its memory has been deallocated.
+    #[inline(always)]
+    pub fn number_of_orphans(&self) -> usize {
+        if likely!(self.head.load(Acquire).is_null()) {
+            return 0;
+        }
+
+        self.lock.lock();
+        let _guard = UnlockOnDrop(&self.lock);
+
+        let mut number_of_orphans = 0;
+        let mut orphan = self.head.load(Relaxed);
+        while !orphan.is_null() {
+            number_of_orphans += 1;
+            orphan = unsafe { &*orphan }.next;
+        }
+        number_of_orphans
+    }
+
+    /// Deallocates memory allocated by an orphan, dropping the orphan (and freeing its record using `global_allocator`) if this was the last of its memory in use.
+    ///
+    /// Returns `false` if no orphan allocated `current_memory`.
+    #[inline(always)]
+    pub fn deallocate<GA: Allocator>(
+        &self,
+        global_allocator: &GA,
+        non_zero_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> bool {
+        if likely!(!self.bounds.may_contain(current_memory)) {
+            return false;
+        }
+
+        let finished_with = {
+            self.lock.lock();
+            let _guard = UnlockOnDrop(&self.lock);
+
+            let mut previous: *mut Orphan<LA> = null_mut();
+            let mut orphan = self.head.load(Relaxed);
+            loop {
+                if orphan.is_null() {
+                    return false;
+                }
+
+                let this = unsafe { &mut *orphan };
+                if this.local_allocator.contains(current_memory) {
+                    this.local_allocator.deallocate(
+                        non_zero_size,
+                        non_zero_power_of_two_alignment,
+                        current_memory,
+                    );
+                    this.allocations_outstanding = this.allocations_outstanding.saturating_sub(1);
+
+                    if this.allocations_outstanding != 0 {
+                        return true;
+                    }
+
+                    if previous.is_null() {
+                        self.head.store(this.next, Release)
+                    } else {
+                        unsafe { (*previous).next = this.next }
+                    }
+                    self.bounds.narrow(self.memory_ranges());
+                    break orphan;
+                }
+
+                previous = orphan;
+                orphan = this.next;
+            }
+        };
+
+        unsafe {
+            drop_in_place(finished_with);
+            global_allocator.deallocate(
+                Self::orphan_size(),
+                Self::orphan_alignment(),
+                NonNull::new_unchecked(finished_with as *mut u8),
+            )
+        }
+        true
+    }
+
+    /// Only called with the lock held.
+    #[inline(always)]
+    fn memory_ranges(&self) -> impl Iterator<Item = MemoryRange> + '_ {
+        let mut orphan = self.head.load(Relaxed);
+        from_fn(move || {
+            if orphan.is_null() {
+                return None;
+            }
+            let this = unsafe { &*orphan };
+            orphan = this.next;
+            Some(this.local_allocator.memory_range())
+        })
+    }
+
+    #[inline(always)]
+    fn orphan_size() -> NonZeroUsize {
+        size_of::<Orphan<LA>>().non_zero()
+    }
+
+    #[inline(always)]
+    fn orphan_alignment() -> NonZeroUsize {
+        align_of::<Orphan<LA>>().non_zero()
+    }
+}
+
+struct Orphan<LA: LocalAllocator> {
+    next: *mut Orphan<LA>,
+    allocations_outstanding: usize,
+    local_allocator: LA,
+}
@@ -1,5 +1,9 @@
 use crate::allocators::global::current_allocator_in_use::CurrentAllocatorInUse;
+use crate::allocators::global::fallback_chain::FallbackChain;
 use crate::allocators::global::local_allocator::LocalAllocator;
+use crate::allocators::global::local_allocator_registry::LocalAllocatorRegistration;
+use crate::allocators::global::local_allocator_stack::LocalAllocatorStack;
+use crate::allocators::global::thread_exit_policy::ThreadExitPolicy;
 
 #[doc(hidden)]
 #[allow(dead_code)]
@@ -9,7 +13,14 @@ pub struct PerThreadState<
 > {
     pub current_allocator_in_use: CurrentAllocatorInUse,
     pub coroutine_local_allocator: Option<CoroutineLocalAllocator>,
+    pub pushed_coroutine_local_allocators: LocalAllocatorStack<CoroutineLocalAllocator>,
     pub thread_local_allocator: Option<ThreadLocalAllocator>,
+    pub coroutine_local_allocator_registration: Option<LocalAllocatorRegistration>,
+    pub thread_local_allocator_registration: Option<LocalAllocatorRegistration>,
+    pub thread_local_allocations_outstanding: usize,
+    pub thread_exit_policy: ThreadExitPolicy,
+    pub thread_local_allocator_factory_failed: bool,
+    pub fallback_chain: FallbackChain,
 }
 
 impl<CoroutineLocalAllocator: LocalAllocator, ThreadLocalAllocator: LocalAllocator>
@@ -21,7 +32,14 @@ impl<CoroutineLocalAllocator: LocalAllocator, ThreadLocalAllocator: LocalAllocat
         Self {
             current_allocator_in_use: CurrentAllocatorInUse::Global,
             coroutine_local_allocator: None,
+            pushed_coroutine_local_allocators: LocalAllocatorStack::new(),
             thread_local_allocator: None,
+            coroutine_local_allocator_registration: None,
+            thread_local_allocator_registration: None,
+            thread_local_allocations_outstanding: 0,
+            thread_exit_policy: ThreadExitPolicy::Drop,
+            thread_local_allocator_factory_failed: false,
+            fallback_chain: FallbackChain::ThreadLocalThenGlobal,
         }
     }
 
@@ -31,7 +49,14 @@ impl<CoroutineLocalAllocator: LocalAllocator, ThreadLocalAllocator: LocalAllocat
         Self {
             current_allocator_in_use: CurrentAllocatorInUse::ThreadLocal,
             coroutine_local_allocator: None,
+            pushed_coroutine_local_allocators: LocalAllocatorStack::new(),
             thread_local_allocator: None,
+            coroutine_local_allocator_registration: None,
+            thread_local_allocator_registration: None,
+            thread_local_allocations_outstanding: 0,
+            thread_exit_policy: ThreadExitPolicy::Drop,
+            thread_local_allocator_factory_failed: false,
+            fallback_chain: FallbackChain::ThreadLocalThenGlobal,
         }
     }
 }
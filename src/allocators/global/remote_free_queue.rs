@@ -0,0 +1,122 @@
+use crate::extensions::prelude::*;
+use crate::memory_address::MemoryAddress;
+use std::mem::size_of;
+use std::num::NonZeroUsize;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+
+/// A lock-free, multiple producer, single consumer queue of memory freed by threads other than the one owning the allocator that allocated it.
+///
+/// The freed memory itself is used as the queue's nodes, so no memory is allocated; the first two words of each allocation are overwritten with a next pointer and the allocation's size and alignment.
+///
+/// Allocations smaller than `MINIMUM_ALLOCATION_SIZE` can not be queued, so allocators whose memory may be freed remotely must be asked for at least that much.
+#[derive(Debug)]
+pub struct RemoteFreeQueue {
+    head: AtomicUsize,
+}
+
+impl RemoteFreeQueue {
+    /// Allocations smaller than this can not be queued.
+    pub const MINIMUM_ALLOCATION_SIZE: usize = 2 * size_of::<usize>();
+
+    const EMPTY: usize = 0;
+
+    const ALIGNMENT_BITS: usize = 6;
+
+    const ALIGNMENT_MASK: usize = (1 << Self::ALIGNMENT_BITS) - 1;
+
+    /// Creates a new, empty instance.
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self {
+            head: AtomicUsize::new(Self::EMPTY),
+        }
+    }
+
+    /// Is this queue empty?
+    ///
+    /// Cheap enough to call before every allocation.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Relaxed) == Self::EMPTY
+    }
+
+    /// Push freed memory onto the queue; called by any thread.
+    ///
+    /// Returns `false` if the allocation is too small to be queued.
+    #[inline(always)]
+    pub fn push(
+        &self,
+        current_memory: MemoryAddress,
+        non_zero_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+    ) -> bool {
+        if unlikely!(non_zero_size.get() < Self::MINIMUM_ALLOCATION_SIZE) {
+            return false;
+        }
+
+        let size_and_alignment = (non_zero_size.get() << Self::ALIGNMENT_BITS)
+            | non_zero_power_of_two_alignment.logarithm_base2();
+        Self::write_word(current_memory, 1, size_and_alignment);
+
+        let mut head = self.head.load(Relaxed);
+        loop {
+            Self::write_word(current_memory, 0, head);
+
+            match self
+                .head
+                .compare_exchange_weak(head, current_memory.to_usize(), Release, Relaxed)
+            {
+                Ok(_) => return true,
+
+                Err(was) => head = was,
+            }
+        }
+    }
+
+    /// Takes all queued memory and passes each allocation to `callback` as `(current_memory, non_zero_size, non_zero_power_of_two_alignment)`; called only by the owning thread.
+    #[inline(always)]
+    pub fn drain(&self, mut callback: impl FnMut(MemoryAddress, NonZeroUsize, NonZeroUsize)) {
+        let mut node = self.head.swap(Self::EMPTY, Acquire);
+        while node != Self::EMPTY {
+            let current_memory = MemoryAddress::from_usize(node);
+            node = Self::read_word(current_memory, 0);
+
+            let size_and_alignment = Self::read_word(current_memory, 1);
+            let non_zero_size = (size_and_alignment >> Self::ALIGNMENT_BITS).non_zero();
+            let non_zero_power_of_two_alignment =
+                (1usize << (size_and_alignment & Self::ALIGNMENT_MASK)).non_zero();
+
+            callback(
+                current_memory,
+                non_zero_size,
+                non_zero_power_of_two_alignment,
+            )
+        }
+    }
+
+    /// Forgets all queued memory without walking it.
+    #[inline(always)]
+    pub(crate) fn discard(&self) {
+        self.head.store(Self::EMPTY, Relaxed)
+    }
+
+    /// Allocations may not be aligned to a word.
+    #[inline(always)]
+    fn write_word(current_memory: MemoryAddress, word_index: usize, value: usize) {
+        unsafe {
+            (current_memory.as_ptr() as *mut usize)
+                .add(word_index)
+                .write_unaligned(value)
+        }
+    }
+
+    #[inline(always)]
+    fn read_word(current_memory: MemoryAddress, word_index: usize) -> usize {
+        unsafe {
+            (current_memory.as_ptr() as *const usize)
+                .add(word_index)
+                .read_unaligned()
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use crate::allocators::global::current_allocator_in_use::CurrentAllocatorInUse;
+use std::mem::transmute;
+use std::num::NonZeroUsize;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::Relaxed;
+
+/// Why an allocation spilled from one allocator to the next in the `FallbackChain`.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum SpillReason {
+    /// There was no coroutine local or thread local allocator.
+    Absent,
+
+    /// The allocator could not satisfy the allocation.
+    Exhausted,
+}
+
+/// Counts, for all threads, allocations which spilled from a coroutine local or thread local allocator to the next allocator in the `FallbackChain`.
+///
+/// Optionally calls a callback on every spill, with the size of the allocation spilled, eg to update metrics; the callback is called from within the allocator, so must not allocate.
+#[derive(Debug)]
+pub struct Spills {
+    counts: [AtomicUsize; 4],
+
+    /// Zero if there is no callback.
+    callback: AtomicUsize,
+}
+
+impl Spills {
+    const NO_CALLBACK: usize = 0;
+
+    /// Creates a new instance with all counts zero and no callback.
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self {
+            counts: [
+                AtomicUsize::new(0),
+                AtomicUsize::new(0),
+                AtomicUsize::new(0),
+                AtomicUsize::new(0),
+            ],
+            callback: AtomicUsize::new(Self::NO_CALLBACK),
+        }
+    }
+
+    /// Number of allocations which spilled from `from` for `reason`.
+    ///
+    /// Always zero if `from` is `CurrentAllocatorInUse::Global`.
+    #[inline(always)]
+    pub fn count(&self, from: CurrentAllocatorInUse, reason: SpillReason) -> usize {
+        match Self::index(from, reason) {
+            None => 0,
+            Some(index) => self.counts[index].load(Relaxed),
+        }
+    }
+
+    /// Number of allocations which spilled, for any reason.
+    #[inline(always)]
+    pub fn total(&self) -> usize {
+        self.counts.iter().map(|count| count.load(Relaxed)).sum()
+    }
+
+    /// Sets (or, if `None`, removes) a callback to call on every spill.
+    #[inline(always)]
+    pub fn set_callback(
+        &self,
+        callback: Option<fn(CurrentAllocatorInUse, SpillReason, NonZeroUsize)>,
+    ) {
+        let callback = match callback {
+            None => Self::NO_CALLBACK,
+            Some(callback) => callback as usize,
+        };
+        self.callback.store(callback, Relaxed)
+    }
+
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn record(
+        &self,
+        from: CurrentAllocatorInUse,
+        reason: SpillReason,
+        non_zero_size: NonZeroUsize,
+    ) {
+        if let Some(index) = Self::index(from, reason) {
+            self.counts[index].fetch_add(1, Relaxed);
+        }
+
+        let callback = self.callback.load(Relaxed);
+        if unlikely!(callback != Self::NO_CALLBACK) {
+            let callback: fn(CurrentAllocatorInUse, SpillReason, NonZeroUsize) =
+                unsafe { transmute(callback) };
+            callback(from, reason, non_zero_size)
+        }
+    }
+
+    #[inline(always)]
+    fn index(from: CurrentAllocatorInUse, reason: SpillReason) -> Option<usize> {
+        use self::CurrentAllocatorInUse::*;
+        use self::SpillReason::*;
+
+        match (from, reason) {
+            (CoroutineLocal, Absent) => Some(0),
+            (CoroutineLocal, Exhausted) => Some(1),
+            (ThreadLocal, Absent) => Some(2),
+            (ThreadLocal, Exhausted) => Some(3),
+            (Global, _) => None,
+        }
+    }
+}
@@ -7,9 +7,22 @@
 /// * `$ThreadLocalAllocator`: the type of the thread local allocator. Must implement `LocalAllocator`.
 /// * `$GlobalAllocator`: the type of the thread local allocator. Must implement `Allocator`; a common usage is `GlobalAllocToAllocatorAdaptor<System>`.
 /// * `global_allocator_instance`: a constant expression for instantiating the global allocator. A common usage is `GlobalAllocToAllocatorAdaptor(System)`.
+/// * `thread_local_allocator_factory` (optional): an expression of type `Result<$ThreadLocalAllocator, AllocError>` evaluated to create a thread's thread local allocator on its first thread local allocation, eg `MultipleBinarySearchTreeAllocator::new(MemoryMapSource::default(), size)`. It is evaluated with the global allocator in use. If it fails, that thread does not try again. Without a factory, a thread's thread local allocator is absent until `initialize_thread_local_allocator()` is called.
 ///
 /// To access the switchable allocator, call `$mod_name::global_thread_and_coroutine_switchable_allocator()`; this returns an object reference that implements the trait `GlobalSwitchableAllocator`.
 ///
+/// Memory freed by a thread other than the one whose coroutine or thread local allocator allocated it is queued for that thread, which frees it on its next allocation from that allocator.
+/// So that any such memory can be queued, coroutine local and thread local allocations are rounded up to at least `RemoteFreeQueue::MINIMUM_ALLOCATION_SIZE` bytes.
+/// Coroutine local allocators are only tracked whilst assigned to a thread with `replace_coroutine_local_allocator()` or `push_coroutine_local_allocator()`.
+///
+/// For nested scopes (eg request, then sub-task, then coroutine), coroutine local allocators can be pushed with `push_coroutine_local_allocator()` and popped with `pop_coroutine_local_allocator()`; only the most recently pushed allocates, but memory allocated by any of them is freed by its owner, including memory freed by other threads.
+/// Only the coroutine tier can be stacked, and every allocator on the stack is a `$CoroutineLocalAllocator`; to push allocators of different kinds, make it an enum of them.
+/// On Unix, coroutine local allocators still assigned or pushed when a thread exits are dropped.
+///
+/// When the coroutine local or thread local allocator is absent or exhausted, allocations spill to the next allocator in the thread's `FallbackChain` (by default coroutine local, then thread local, then global) rather than failing; spills are counted by `spills()`, which can also call a callback for metrics.
+///
+/// On Unix, a thread's thread local allocator is dropped automatically when the thread exits, returning its memory to its memory source; memory it allocated that is still in use is reported as a leak by `thread_exit_leaks()` unless `set_thread_exit_policy()` hands it to the global tier instead.
+///
 /// Done using a macro due to a limitation when combining thread-local statics with generics (which could be solved using pthread keys, but these aren't always the most efficient of approaches); in essence, a thread-local struct field is needed.
 ///
 /// # Example
@@ -18,10 +31,13 @@
 ///
 /// switchable_allocator!(application_allocator, BumpAllocator<ArenaMemorySource<MemoryMapSource>>, MultipleBinarySearchTreeAllocator<MemoryMapSource>, GlobalAllocToAllocatorAdaptor<System>, GlobalAllocToAllocatorAdaptor(System));
 ///
+/// // Or, to create each thread's thread local allocator on first use:-
+/// switchable_allocator!(application_allocator, BumpAllocator<ArenaMemorySource<MemoryMapSource>>, MultipleBinarySearchTreeAllocator<MemoryMapSource>, GlobalAllocToAllocatorAdaptor<System>, GlobalAllocToAllocatorAdaptor(System), MultipleBinarySearchTreeAllocator::new(MemoryMapSource::default(), (1024 * 1024 * 1024).non_zero()));
+///
 /// ```
 #[macro_export]
 macro_rules! switchable_allocator {
-    ($mod_name: ident, $CoroutineLocalAllocator: ty, $ThreadLocalAllocator: ty, $GlobalAllocator: ty, $global_allocator_instance: expr) => {
+    ($mod_name: ident, $CoroutineLocalAllocator: ty, $ThreadLocalAllocator: ty, $GlobalAllocator: ty, $global_allocator_instance: expr $(, $thread_local_allocator_factory: expr)?) => {
         #[global_allocator]
         pub(crate) static GLOBAL: $mod_name::SwitchableAllocator =
             $mod_name::SwitchableAllocator {
@@ -41,10 +57,15 @@ macro_rules! switchable_allocator {
             use allocator_suite::allocators::global::prelude::*;
             use allocator_suite::memory_sources::prelude::*;
 
+            /// Also for use in a thread local allocator factory.
+            use allocator_suite::extensions::usize_ext::UsizeExt;
+
             /// Std imports
             use std::num::NonZeroUsize;
             use std::alloc::{AllocRef, AllocError, GlobalAlloc, Layout, System};
             use std::mem::replace;
+            #[cfg(unix)]
+            use std::ffi::c_void;
 
             /// Effectively this is a field of `SwitchableAllocator` with a different value for each thread.
             ///
@@ -55,6 +76,35 @@ macro_rules! switchable_allocator {
                 $ThreadLocalAllocator,
             > = PerThreadState::empty();
 
+            /// Memory ranges of every thread's coroutine and thread local allocators, so memory freed by a thread other than its owner can be handed back to the owner.
+            static LOCAL_ALLOCATOR_REGISTRY: LocalAllocatorRegistry = LocalAllocatorRegistry::new();
+
+            /// Thread local allocators of exited threads which still had memory in use.
+            static ORPHANED_THREAD_LOCAL_ALLOCATORS: OrphanedLocalAllocators<$ThreadLocalAllocator> = OrphanedLocalAllocators::new();
+
+            /// Allocations which spilled from a coroutine local or thread local allocator to the next allocator in the fallback chain.
+            static SPILLS: Spills = Spills::new();
+
+            /// Thread local allocators dropped whilst memory they allocated was still in use.
+            static THREAD_EXIT_LEAKS: ThreadExitLeaks = ThreadExitLeaks::new();
+
+            #[cfg(unix)]
+            static THREAD_EXIT_HOOK: ThreadExitHook = ThreadExitHook::new(thread_exited);
+
+            /// Drops the coroutine local allocators, current and pushed, and the thread local allocator, if any, when a thread exits.
+            #[cfg(unix)]
+            unsafe extern "C" fn thread_exited(_value: *mut c_void) {
+                per_thread_state.current_allocator_in_use = CurrentAllocatorInUse::Global;
+
+                while super::GLOBAL.coroutine_local_allocator_depth() != 0 {
+                    drop(super::GLOBAL.pop_coroutine_local_allocator())
+                }
+
+                if per_thread_state.thread_local_allocator.is_some() {
+                    super::GLOBAL.drop_thread_local_allocator()
+                }
+            }
+
             #[derive(Debug)]
             pub(crate) struct SwitchableAllocator {
                 pub(crate) global_allocator: $GlobalAllocator,
@@ -81,14 +131,12 @@ macro_rules! switchable_allocator {
 
                     match self.save_current_allocator_in_use() {
                         CoroutineLocal => self
-                            .coroutine_local_allocator()
-                            .expect("Should have assigned a coroutine local allocator")
-                            .allocate(non_zero_size, non_zero_power_of_two_alignment),
+                            .coroutine_local_allocate(non_zero_size, non_zero_power_of_two_alignment)
+                            .or_else(|spill_reason| self.spill(CoroutineLocal, spill_reason, non_zero_size, non_zero_power_of_two_alignment)),
 
                         ThreadLocal => self
-                            .thread_local_allocator()
-                            .expect("Should have assigned a thread local allocator")
-                            .allocate(non_zero_size, non_zero_power_of_two_alignment),
+                            .thread_local_allocate(non_zero_size, non_zero_power_of_two_alignment)
+                            .or_else(|spill_reason| self.spill(ThreadLocal, spill_reason, non_zero_size, non_zero_power_of_two_alignment)),
 
                         Global => self
                             .global_allocator()
@@ -103,14 +151,18 @@ macro_rules! switchable_allocator {
                     non_zero_power_of_two_alignment: NonZeroUsize,
                     current_memory: MemoryAddress,
                 ) {
-                    choose_allocator!(
-                        self,
-                        current_memory,
-                        deallocate,
-                        non_zero_size,
-                        non_zero_power_of_two_alignment,
-                        current_memory
-                    )
+                    if let Some(coroutine_local_allocator) = self.coroutine_local_allocator_containing(current_memory) {
+                        return coroutine_local_allocator.deallocate(Self::local_size(non_zero_size), non_zero_power_of_two_alignment, current_memory);
+                    }
+
+                    if let Some(thread_local_allocator) = self.thread_local_allocator() {
+                        if likely!(thread_local_allocator.contains(current_memory)) {
+                            Self::thread_local_freed(1);
+                            return thread_local_allocator.deallocate(Self::local_size(non_zero_size), non_zero_power_of_two_alignment, current_memory);
+                        }
+                    }
+
+                    self.remote_or_global_deallocate(non_zero_size, non_zero_power_of_two_alignment, current_memory)
                 }
 
                 #[inline(always)]
@@ -121,15 +173,32 @@ macro_rules! switchable_allocator {
                     non_zero_current_size: NonZeroUsize,
                     current_memory: MemoryAddress,
                 ) -> Result<MemoryAddress, AllocError> {
-                    choose_allocator!(
-                        self,
-                        current_memory,
-                        growing_reallocate,
-                        non_zero_new_size,
-                        non_zero_power_of_two_alignment,
-                        non_zero_current_size,
-                        current_memory
-                    )
+                    use allocator_suite::allocators::global::current_allocator_in_use::CurrentAllocatorInUse::*;
+
+                    let local_new_size = Self::local_size(non_zero_new_size);
+                    let local_current_size = Self::local_size(non_zero_current_size);
+
+                    if let Some(coroutine_local_allocator) = self.coroutine_local_allocator_containing(current_memory) {
+                        if unlikely!(local_new_size == local_current_size) {
+                            return Ok(current_memory);
+                        }
+                        return coroutine_local_allocator
+                            .growing_reallocate(local_new_size, non_zero_power_of_two_alignment, local_current_size, current_memory)
+                            .or_else(|AllocError| self.spilling_growing_reallocate(CoroutineLocal, non_zero_new_size, non_zero_power_of_two_alignment, non_zero_current_size, current_memory));
+                    }
+
+                    if let Some(thread_local_allocator) = self.thread_local_allocator() {
+                        if likely!(thread_local_allocator.contains(current_memory)) {
+                            if unlikely!(local_new_size == local_current_size) {
+                                return Ok(current_memory);
+                            }
+                            return thread_local_allocator
+                                .growing_reallocate(local_new_size, non_zero_power_of_two_alignment, local_current_size, current_memory)
+                                .or_else(|AllocError| self.spilling_growing_reallocate(ThreadLocal, non_zero_new_size, non_zero_power_of_two_alignment, non_zero_current_size, current_memory));
+                        }
+                    }
+
+                    self.remote_or_global_growing_reallocate(non_zero_new_size, non_zero_power_of_two_alignment, non_zero_current_size, current_memory)
                 }
 
                 #[inline(always)]
@@ -140,18 +209,262 @@ macro_rules! switchable_allocator {
                     non_zero_current_size: NonZeroUsize,
                     current_memory: MemoryAddress,
                 ) -> Result<MemoryAddress, AllocError> {
+                    let local_new_size = Self::local_size(non_zero_new_size);
+                    let local_current_size = Self::local_size(non_zero_current_size);
+
+                    if unlikely!(local_new_size == local_current_size) {
+                        if self.coroutine_local_allocator_containing(current_memory).is_some() || self.thread_local_allocator().map_or(false, |thread_local_allocator| thread_local_allocator.contains(current_memory)) {
+                            return Ok(current_memory);
+                        }
+                    }
+
                     choose_allocator!(
                         self,
                         current_memory,
-                        growing_reallocate,
-                        non_zero_new_size,
+                        shrinking_reallocate,
+                        local_new_size,
                         non_zero_power_of_two_alignment,
-                        non_zero_current_size,
-                        current_memory
+                        local_current_size,
+                        current_memory;
+                        self.remote_or_global_shrinking_reallocate(non_zero_new_size, non_zero_power_of_two_alignment, non_zero_current_size, current_memory)
                     )
                 }
             }
 
+            impl SwitchableAllocator {
+                /// Creates the thread local allocator using the factory passed to `switchable_allocator!`.
+                ///
+                /// Returns `None` if there is no factory or the factory failed, now or before on this thread.
+                #[cold]
+                #[allow(unreachable_code)]
+                fn create_thread_local_allocator(&self) -> Option<&$ThreadLocalAllocator> {
+                    $(
+                        return self.create_thread_local_allocator_using(|| $thread_local_allocator_factory);
+                    )?
+
+                    None
+                }
+
+                #[allow(dead_code)]
+                #[inline(always)]
+                fn create_thread_local_allocator_using(
+                    &self,
+                    thread_local_allocator_factory: impl FnOnce() -> Result<$ThreadLocalAllocator, AllocError>,
+                ) -> Option<&$ThreadLocalAllocator> {
+                    if unlikely!(unsafe { per_thread_state.thread_local_allocator_factory_failed }) {
+                        return None;
+                    }
+
+                    // The factory may itself allocate.
+                    match self.callback_with_global_allocator(thread_local_allocator_factory) {
+                        Ok(thread_local_allocator) => {
+                            self.initialize_thread_local_allocator(thread_local_allocator);
+                            self.thread_local_allocator()
+                        }
+
+                        Err(AllocError) => {
+                            unsafe { per_thread_state.thread_local_allocator_factory_failed = true };
+                            None
+                        }
+                    }
+                }
+
+                /// Allocates from the coroutine local allocator, first freeing memory other threads have freed to it and to the pushed coroutine local allocators.
+                #[inline(always)]
+                fn coroutine_local_allocate(
+                    &self,
+                    non_zero_size: NonZeroUsize,
+                    non_zero_power_of_two_alignment: NonZeroUsize,
+                ) -> Result<MemoryAddress, SpillReason> {
+                    let coroutine_local_allocator = self.coroutine_local_allocator().ok_or(SpillReason::Absent)?;
+                    unsafe {
+                        if let Some(registration) = per_thread_state.coroutine_local_allocator_registration {
+                            LOCAL_ALLOCATOR_REGISTRY.free_remotely_freed(registration, coroutine_local_allocator);
+                        }
+                        per_thread_state.pushed_coroutine_local_allocators.free_remotely_freed(&LOCAL_ALLOCATOR_REGISTRY);
+                    }
+
+                    coroutine_local_allocator
+                        .allocate(Self::local_size(non_zero_size), non_zero_power_of_two_alignment)
+                        .map_err(|AllocError| SpillReason::Exhausted)
+                }
+
+                /// Allocates from the thread local allocator, creating it if there is a factory and first freeing memory other threads have freed to it.
+                #[inline(always)]
+                fn thread_local_allocate(
+                    &self,
+                    non_zero_size: NonZeroUsize,
+                    non_zero_power_of_two_alignment: NonZeroUsize,
+                ) -> Result<MemoryAddress, SpillReason> {
+                    let thread_local_allocator = match self.thread_local_allocator() {
+                        Some(thread_local_allocator) => thread_local_allocator,
+
+                        None => self.create_thread_local_allocator().ok_or(SpillReason::Absent)?,
+                    };
+                    if let Some(registration) = unsafe { per_thread_state.thread_local_allocator_registration } {
+                        Self::thread_local_freed(LOCAL_ALLOCATOR_REGISTRY.free_remotely_freed(registration, thread_local_allocator));
+                    }
+
+                    let memory = thread_local_allocator
+                        .allocate(Self::local_size(non_zero_size), non_zero_power_of_two_alignment)
+                        .map_err(|AllocError| SpillReason::Exhausted)?;
+                    Self::thread_local_allocated();
+                    Ok(memory)
+                }
+
+                /// Coroutine local and thread local allocators are always asked for at least `RemoteFreeQueue::MINIMUM_ALLOCATION_SIZE` bytes, so that any of their memory can be queued for them when freed by another thread.
+                #[inline(always)]
+                fn local_size(non_zero_size: NonZeroUsize) -> NonZeroUsize {
+                    if unlikely!(non_zero_size.get() < RemoteFreeQueue::MINIMUM_ALLOCATION_SIZE) {
+                        RemoteFreeQueue::MINIMUM_ALLOCATION_SIZE.non_zero()
+                    } else {
+                        non_zero_size
+                    }
+                }
+
+                /// Counts an allocation by the thread local allocator, so that it can be known whether any are still in use when it is dropped.
+                #[inline(always)]
+                fn thread_local_allocated() {
+                    unsafe { per_thread_state.thread_local_allocations_outstanding = per_thread_state.thread_local_allocations_outstanding.saturating_add(1) }
+                }
+
+                /// Counts deallocations by the thread local allocator, including of memory queued by other threads.
+                ///
+                /// Saturates rather than underflows should memory the thread local allocator contains, but which was not counted, be deallocated.
+                #[inline(always)]
+                fn thread_local_freed(number_freed: usize) {
+                    unsafe { per_thread_state.thread_local_allocations_outstanding = per_thread_state.thread_local_allocations_outstanding.saturating_sub(number_freed) }
+                }
+
+                /// Allocates from the allocators after `from` in this thread's fallback chain, recording the spill.
+                #[cold]
+                fn spill(
+                    &self,
+                    from: CurrentAllocatorInUse,
+                    spill_reason: SpillReason,
+                    non_zero_size: NonZeroUsize,
+                    non_zero_power_of_two_alignment: NonZeroUsize,
+                ) -> Result<MemoryAddress, AllocError> {
+                    let fallback_chain = unsafe { per_thread_state.fallback_chain };
+                    if unlikely!(fallback_chain == FallbackChain::None) {
+                        return Err(AllocError);
+                    }
+
+                    SPILLS.record(from, spill_reason, non_zero_size);
+
+                    if from == CurrentAllocatorInUse::CoroutineLocal && fallback_chain == FallbackChain::ThreadLocalThenGlobal {
+                        match self.thread_local_allocate(non_zero_size, non_zero_power_of_two_alignment) {
+                            Ok(memory) => return Ok(memory),
+
+                            Err(spill_reason) => SPILLS.record(CurrentAllocatorInUse::ThreadLocal, spill_reason, non_zero_size),
+                        }
+                    }
+
+                    self.global_allocator().allocate(non_zero_size, non_zero_power_of_two_alignment)
+                }
+
+                /// Memory whose local allocator can not grow it is moved to the allocators after `from` in this thread's fallback chain.
+                #[cold]
+                fn spilling_growing_reallocate(
+                    &self,
+                    from: CurrentAllocatorInUse,
+                    non_zero_new_size: NonZeroUsize,
+                    non_zero_power_of_two_alignment: NonZeroUsize,
+                    non_zero_current_size: NonZeroUsize,
+                    current_memory: MemoryAddress,
+                ) -> Result<MemoryAddress, AllocError> {
+                    let new_memory = self.spill(from, SpillReason::Exhausted, non_zero_new_size, non_zero_power_of_two_alignment)?;
+                    unsafe { new_memory.as_ptr().copy_from_nonoverlapping(current_memory.as_ptr(), non_zero_current_size.get()) };
+                    self.deallocate(non_zero_current_size, non_zero_power_of_two_alignment, current_memory);
+                    Ok(new_memory)
+                }
+
+                /// Memory not allocated by this thread's local allocators was allocated by another thread's local allocator, by an orphaned thread local allocator or by the global allocator.
+                #[inline(always)]
+                fn remote_or_global_deallocate(
+                    &self,
+                    non_zero_size: NonZeroUsize,
+                    non_zero_power_of_two_alignment: NonZeroUsize,
+                    current_memory: MemoryAddress,
+                ) {
+                    match LOCAL_ALLOCATOR_REGISTRY.remote_free_queue_for(current_memory) {
+                        Some(remote_free_queue) => Self::push_remotely_freed(remote_free_queue, current_memory, non_zero_size, non_zero_power_of_two_alignment),
+
+                        None => if !ORPHANED_THREAD_LOCAL_ALLOCATORS.deallocate(self.global_allocator(), Self::local_size(non_zero_size), non_zero_power_of_two_alignment, current_memory) {
+                            self.global_allocator().deallocate(non_zero_size, non_zero_power_of_two_alignment, current_memory)
+                        },
+                    }
+                }
+
+                /// Queues memory owned by another thread's local allocator for that thread to free.
+                #[inline(always)]
+                fn push_remotely_freed(
+                    remote_free_queue: &RemoteFreeQueue,
+                    current_memory: MemoryAddress,
+                    non_zero_size: NonZeroUsize,
+                    non_zero_power_of_two_alignment: NonZeroUsize,
+                ) {
+                    let pushed = remote_free_queue.push(current_memory, Self::local_size(non_zero_size), non_zero_power_of_two_alignment);
+                    debug_assert!(pushed, "Local allocators should never allocate less than RemoteFreeQueue::MINIMUM_ALLOCATION_SIZE");
+                }
+
+                /// Memory owned by another thread's local allocator or an orphaned thread local allocator can not be reallocated in place, so is copied.
+                #[inline(always)]
+                fn remote_or_global_growing_reallocate(
+                    &self,
+                    non_zero_new_size: NonZeroUsize,
+                    non_zero_power_of_two_alignment: NonZeroUsize,
+                    non_zero_current_size: NonZeroUsize,
+                    current_memory: MemoryAddress,
+                ) -> Result<MemoryAddress, AllocError> {
+                    match LOCAL_ALLOCATOR_REGISTRY.remote_free_queue_for(current_memory) {
+                        Some(remote_free_queue) => {
+                            let new_memory = self.allocate(non_zero_new_size, non_zero_power_of_two_alignment)?;
+                            unsafe { new_memory.as_ptr().copy_from_nonoverlapping(current_memory.as_ptr(), non_zero_current_size.get()) };
+                            Self::push_remotely_freed(remote_free_queue, current_memory, non_zero_current_size, non_zero_power_of_two_alignment);
+                            Ok(new_memory)
+                        }
+
+                        None if ORPHANED_THREAD_LOCAL_ALLOCATORS.contains(current_memory) => {
+                            let new_memory = self.allocate(non_zero_new_size, non_zero_power_of_two_alignment)?;
+                            unsafe { new_memory.as_ptr().copy_from_nonoverlapping(current_memory.as_ptr(), non_zero_current_size.get()) };
+                            ORPHANED_THREAD_LOCAL_ALLOCATORS.deallocate(self.global_allocator(), Self::local_size(non_zero_current_size), non_zero_power_of_two_alignment, current_memory);
+                            Ok(new_memory)
+                        }
+
+                        None => self.global_allocator().growing_reallocate(non_zero_new_size, non_zero_power_of_two_alignment, non_zero_current_size, current_memory),
+                    }
+                }
+
+                /// Memory owned by another thread's local allocator or an orphaned thread local allocator can not be reallocated in place, so is copied.
+                #[inline(always)]
+                fn remote_or_global_shrinking_reallocate(
+                    &self,
+                    non_zero_new_size: NonZeroUsize,
+                    non_zero_power_of_two_alignment: NonZeroUsize,
+                    non_zero_current_size: NonZeroUsize,
+                    current_memory: MemoryAddress,
+                ) -> Result<MemoryAddress, AllocError> {
+                    match LOCAL_ALLOCATOR_REGISTRY.remote_free_queue_for(current_memory) {
+                        Some(remote_free_queue) => {
+                            let new_memory = self.allocate(non_zero_new_size, non_zero_power_of_two_alignment)?;
+                            unsafe { new_memory.as_ptr().copy_from_nonoverlapping(current_memory.as_ptr(), non_zero_new_size.get()) };
+                            Self::push_remotely_freed(remote_free_queue, current_memory, non_zero_current_size, non_zero_power_of_two_alignment);
+                            Ok(new_memory)
+                        }
+
+                        None if ORPHANED_THREAD_LOCAL_ALLOCATORS.contains(current_memory) => {
+                            let new_memory = self.allocate(non_zero_new_size, non_zero_power_of_two_alignment)?;
+                            unsafe { new_memory.as_ptr().copy_from_nonoverlapping(current_memory.as_ptr(), non_zero_new_size.get()) };
+                            ORPHANED_THREAD_LOCAL_ALLOCATORS.deallocate(self.global_allocator(), Self::local_size(non_zero_current_size), non_zero_power_of_two_alignment, current_memory);
+                            Ok(new_memory)
+                        }
+
+                        None => self.global_allocator().shrinking_reallocate(non_zero_new_size, non_zero_power_of_two_alignment, non_zero_current_size, current_memory),
+                    }
+                }
+            }
+
             impl GlobalSwitchableAllocator
                 for SwitchableAllocator
             {
@@ -166,7 +479,104 @@ macro_rules! switchable_allocator {
                     &self,
                     replacement: Option<Self::CoroutineLocalAllocator>,
                 ) -> Option<Self::CoroutineLocalAllocator> {
-                    unsafe { replace(&mut per_thread_state.coroutine_local_allocator, replacement) }
+                    let replacement = replacement.map(|coroutine_local_allocator| {
+                        let registration = self.register_coroutine_local_allocator(&coroutine_local_allocator);
+                        (coroutine_local_allocator, registration)
+                    });
+
+                    self.replace_registered_coroutine_local_allocator(replacement).map(|(coroutine_local_allocator, registration)| {
+                        if let Some(registration) = registration {
+                            self.unregister_coroutine_local_allocator(&coroutine_local_allocator, registration);
+                        }
+                        coroutine_local_allocator
+                    })
+                }
+
+                #[inline(always)]
+                fn replace_registered_coroutine_local_allocator(
+                    &self,
+                    replacement: Option<(Self::CoroutineLocalAllocator, Option<LocalAllocatorRegistration>)>,
+                ) -> Option<(Self::CoroutineLocalAllocator, Option<LocalAllocatorRegistration>)> {
+                    let (replacement, replacement_registration) = match replacement {
+                        None => (None, None),
+
+                        Some((coroutine_local_allocator, registration)) => (Some(coroutine_local_allocator), registration),
+                    };
+
+                    #[cfg(unix)]
+                    {
+                        if replacement.is_some() {
+                            THREAD_EXIT_HOOK.register_current_thread();
+                        }
+                    }
+
+                    unsafe {
+                        let registration = replace(&mut per_thread_state.coroutine_local_allocator_registration, replacement_registration);
+                        replace(&mut per_thread_state.coroutine_local_allocator, replacement).map(|coroutine_local_allocator| (coroutine_local_allocator, registration))
+                    }
+                }
+
+                #[inline(always)]
+                fn register_coroutine_local_allocator(
+                    &self,
+                    coroutine_local_allocator: &Self::CoroutineLocalAllocator,
+                ) -> Option<LocalAllocatorRegistration> {
+                    LOCAL_ALLOCATOR_REGISTRY.register(coroutine_local_allocator.memory_range())
+                }
+
+                #[inline(always)]
+                fn unregister_coroutine_local_allocator(
+                    &self,
+                    coroutine_local_allocator: &Self::CoroutineLocalAllocator,
+                    registration: LocalAllocatorRegistration,
+                ) {
+                    LOCAL_ALLOCATOR_REGISTRY.free_remotely_freed(registration, coroutine_local_allocator);
+                    LOCAL_ALLOCATOR_REGISTRY.unregister(registration);
+                }
+
+                #[inline(always)]
+                fn push_coroutine_local_allocator(
+                    &self,
+                    pushed: Self::CoroutineLocalAllocator,
+                ) -> Result<(), Self::CoroutineLocalAllocator> {
+                    unsafe {
+                        if let Some(previous) = per_thread_state.coroutine_local_allocator.take() {
+                            let registration = per_thread_state.coroutine_local_allocator_registration.take();
+                            if let Err(previous) = per_thread_state.pushed_coroutine_local_allocators.push(self.global_allocator(), previous, registration) {
+                                per_thread_state.coroutine_local_allocator = Some(previous);
+                                per_thread_state.coroutine_local_allocator_registration = registration;
+                                return Err(pushed);
+                            }
+                        }
+
+                        per_thread_state.coroutine_local_allocator_registration = LOCAL_ALLOCATOR_REGISTRY.register(pushed.memory_range());
+                        per_thread_state.coroutine_local_allocator = Some(pushed);
+                    }
+
+                    #[cfg(unix)]
+                    THREAD_EXIT_HOOK.register_current_thread();
+                    Ok(())
+                }
+
+                #[inline(always)]
+                fn pop_coroutine_local_allocator(&self) -> Option<Self::CoroutineLocalAllocator> {
+                    unsafe {
+                        let popped = self.replace_coroutine_local_allocator(None);
+
+                        if let Some((previous, registration)) = per_thread_state.pushed_coroutine_local_allocators.pop(self.global_allocator()) {
+                            per_thread_state.coroutine_local_allocator_registration = registration;
+                            per_thread_state.coroutine_local_allocator = Some(previous);
+                        }
+
+                        popped
+                    }
+                }
+
+                #[inline(always)]
+                fn coroutine_local_allocator_depth(&self) -> usize {
+                    unsafe {
+                        per_thread_state.pushed_coroutine_local_allocators.depth() + (per_thread_state.coroutine_local_allocator.is_some() as usize)
+                    }
                 }
 
                 #[inline(always)]
@@ -180,8 +590,14 @@ macro_rules! switchable_allocator {
                     );
 
                     unsafe {
+                        per_thread_state.thread_local_allocator_registration = LOCAL_ALLOCATOR_REGISTRY.register(thread_local_allocator.memory_range());
+                        per_thread_state.thread_local_allocations_outstanding = 0;
                         per_thread_state.thread_local_allocator = Some(thread_local_allocator)
                     }
+
+                    // If registration fails, `drop_thread_local_allocator()` must be called before the thread exits.
+                    #[cfg(unix)]
+                    THREAD_EXIT_HOOK.register_current_thread();
                 }
 
                 #[inline(always)]
@@ -191,7 +607,54 @@ macro_rules! switchable_allocator {
                         "Already deinitialized thread local allocator"
                     );
 
-                    unsafe { per_thread_state.thread_local_allocator = None }
+                    unsafe {
+                        if let Some(registration) = per_thread_state.thread_local_allocator_registration.take() {
+                            if let Some(ref thread_local_allocator) = per_thread_state.thread_local_allocator {
+                                Self::thread_local_freed(LOCAL_ALLOCATOR_REGISTRY.free_remotely_freed(registration, thread_local_allocator));
+                            }
+                            LOCAL_ALLOCATOR_REGISTRY.unregister(registration);
+                        }
+
+                        let allocations_outstanding = replace(&mut per_thread_state.thread_local_allocations_outstanding, 0);
+                        if let Some(thread_local_allocator) = per_thread_state.thread_local_allocator.take() {
+                            match per_thread_state.thread_exit_policy {
+                                _ if likely!(allocations_outstanding == 0) => drop(thread_local_allocator),
+
+                                // This may be called as the thread exits, so reports rather than panics.
+                                ThreadExitPolicy::Drop => {
+                                    THREAD_EXIT_LEAKS.record(allocations_outstanding);
+                                    drop(thread_local_allocator)
+                                }
+
+                                ThreadExitPolicy::HandToGlobal => ORPHANED_THREAD_LOCAL_ALLOCATORS.adopt(self.global_allocator(), thread_local_allocator, allocations_outstanding),
+                            }
+                        }
+                    }
+                }
+
+                #[inline(always)]
+                fn set_thread_exit_policy(&self, thread_exit_policy: ThreadExitPolicy) {
+                    unsafe { per_thread_state.thread_exit_policy = thread_exit_policy }
+                }
+
+                #[inline(always)]
+                fn thread_exit_leaks(&self) -> &ThreadExitLeaks {
+                    &THREAD_EXIT_LEAKS
+                }
+
+                #[inline(always)]
+                fn orphaned_thread_local_allocators(&self) -> &OrphanedLocalAllocators<Self::ThreadLocalAllocator> {
+                    &ORPHANED_THREAD_LOCAL_ALLOCATORS
+                }
+
+                #[inline(always)]
+                fn set_fallback_chain(&self, fallback_chain: FallbackChain) {
+                    unsafe { per_thread_state.fallback_chain = fallback_chain }
+                }
+
+                #[inline(always)]
+                fn spills(&self) -> &Spills {
+                    &SPILLS
                 }
 
                 #[inline(always)]
@@ -209,6 +672,20 @@ macro_rules! switchable_allocator {
                     unsafe { per_thread_state.coroutine_local_allocator.as_ref() }
                 }
 
+                #[inline(always)]
+                fn coroutine_local_allocator_containing(
+                    &self,
+                    current_memory: MemoryAddress,
+                ) -> Option<&Self::CoroutineLocalAllocator> {
+                    if let Some(coroutine_local_allocator) = self.coroutine_local_allocator() {
+                        if likely!(coroutine_local_allocator.contains(current_memory)) {
+                            return Some(coroutine_local_allocator);
+                        }
+                    }
+
+                    unsafe { per_thread_state.pushed_coroutine_local_allocators.owner_of(current_memory) }
+                }
+
                 #[inline(always)]
                 fn thread_local_allocator(&self) -> Option<&Self::ThreadLocalAllocator> {
                     unsafe { per_thread_state.thread_local_allocator.as_ref() }
@@ -223,19 +700,17 @@ macro_rules! switchable_allocator {
     };
 }
 
+/// Calls `$callback` on the coroutine local or thread local allocator which allocated `$current_memory`; otherwise, evaluates `$fallback` if given after a `;`, or calls `$callback` on the global allocator.
 #[doc(hidden)]
 #[macro_export]
 macro_rules! choose_allocator
 {
-	($self: ident, $current_memory: ident, $callback: ident, $($argument: ident),*) =>
+	($self: ident, $current_memory: ident, $callback: ident, $($argument: ident),*; $fallback: expr) =>
 	{
 		{
-			if let Some(coroutine_local_allocator) = $self.coroutine_local_allocator()
+			if let Some(coroutine_local_allocator) = $self.coroutine_local_allocator_containing($current_memory)
 			{
-				if likely!(coroutine_local_allocator.contains($current_memory))
-				{
-					return coroutine_local_allocator.$callback($($argument, )*)
-				}
+				return coroutine_local_allocator.$callback($($argument, )*)
 			}
 
 			if let Some(thread_local_allocator) = $self.thread_local_allocator()
@@ -246,7 +721,12 @@ macro_rules! choose_allocator
 				}
 			}
 
-			$self.global_allocator().$callback($($argument, )*)
+			$fallback
 		}
-	}
+	};
+
+	($self: ident, $current_memory: ident, $callback: ident, $($argument: ident),*) =>
+	{
+		$crate::choose_allocator!($self, $current_memory, $callback, $($argument),*; $self.global_allocator().$callback($($argument, )*))
+	};
 }
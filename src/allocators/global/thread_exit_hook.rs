@@ -0,0 +1,71 @@
+use libc::{pthread_key_create, pthread_key_delete, pthread_key_t, pthread_setspecific};
+use std::ffi::c_void;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::{AcqRel, Acquire};
+
+/// Calls a function as each registered thread exits, using the destructor of a pthread key.
+///
+/// Unlike `thread_local!`, this never allocates from the global allocator, so can be used inside allocators.
+/// Thread-local statics are still accessible when the function is called.
+///
+/// Only on Unix.
+#[derive(Debug)]
+pub struct ThreadExitHook {
+    /// Zero until created; otherwise one more than the key.
+    key: AtomicUsize,
+    on_thread_exit: unsafe extern "C" fn(*mut c_void),
+}
+
+impl ThreadExitHook {
+    const NO_KEY: usize = 0;
+
+    /// Creates a new instance; `on_thread_exit` is passed a non-null pointer which should be ignored.
+    ///
+    /// Usable in `const` and `static` contexts.
+    #[inline(always)]
+    pub const fn new(on_thread_exit: unsafe extern "C" fn(*mut c_void)) -> Self {
+        Self {
+            key: AtomicUsize::new(Self::NO_KEY),
+            on_thread_exit,
+        }
+    }
+
+    /// Arranges for the function to be called when the current thread exits; registering a thread more than once has no further effect.
+    ///
+    /// Returns `false` if the pthread key could not be created or set.
+    #[inline(always)]
+    pub fn register_current_thread(&self) -> bool {
+        const NOT_NULL: *const c_void = 1 as *const c_void;
+
+        match self.key() {
+            None => false,
+            Some(key) => unsafe { pthread_setspecific(key, NOT_NULL) == 0 },
+        }
+    }
+
+    #[inline(always)]
+    fn key(&self) -> Option<pthread_key_t> {
+        let key = self.key.load(Acquire);
+        if likely!(key != Self::NO_KEY) {
+            return Some((key - 1) as pthread_key_t);
+        }
+
+        let mut key = 0;
+        if unlikely!(unsafe { pthread_key_create(&mut key, Some(self.on_thread_exit)) } != 0) {
+            return None;
+        }
+
+        // Another thread may have created a key first.
+        match self
+            .key
+            .compare_exchange(Self::NO_KEY, (key as usize) + 1, AcqRel, Acquire)
+        {
+            Ok(_) => Some(key),
+
+            Err(winner) => {
+                unsafe { pthread_key_delete(key) };
+                Some((winner - 1) as pthread_key_t)
+            }
+        }
+    }
+}
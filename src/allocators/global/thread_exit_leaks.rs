@@ -0,0 +1,64 @@
+use std::mem::transmute;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::Relaxed;
+
+/// Counts, for all threads, thread local allocators dropped with `ThreadExitPolicy::Drop` whilst memory they allocated was still in use, ie had leaked.
+///
+/// Optionally calls a callback on every such leak with the number of allocations still in use, eg to update metrics; the callback is called from within the allocator, often as a thread exits, so must not allocate or panic.
+#[derive(Debug)]
+pub struct ThreadExitLeaks {
+    threads: AtomicUsize,
+    allocations: AtomicUsize,
+
+    /// Zero if there is no callback.
+    callback: AtomicUsize,
+}
+
+impl ThreadExitLeaks {
+    const NO_CALLBACK: usize = 0;
+
+    /// Creates a new instance with all counts zero and no callback.
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self {
+            threads: AtomicUsize::new(0),
+            allocations: AtomicUsize::new(0),
+            callback: AtomicUsize::new(Self::NO_CALLBACK),
+        }
+    }
+
+    /// Number of thread local allocators which leaked.
+    #[inline(always)]
+    pub fn threads(&self) -> usize {
+        self.threads.load(Relaxed)
+    }
+
+    /// Number of allocations still in use when their thread local allocators were dropped.
+    #[inline(always)]
+    pub fn allocations(&self) -> usize {
+        self.allocations.load(Relaxed)
+    }
+
+    /// Sets (or, if `None`, removes) a callback to call on every leak with the number of allocations still in use.
+    #[inline(always)]
+    pub fn set_callback(&self, callback: Option<fn(usize)>) {
+        let callback = match callback {
+            None => Self::NO_CALLBACK,
+            Some(callback) => callback as usize,
+        };
+        self.callback.store(callback, Relaxed)
+    }
+
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn record(&self, allocations_outstanding: usize) {
+        self.threads.fetch_add(1, Relaxed);
+        self.allocations.fetch_add(allocations_outstanding, Relaxed);
+
+        let callback = self.callback.load(Relaxed);
+        if unlikely!(callback != Self::NO_CALLBACK) {
+            let callback: fn(usize) = unsafe { transmute(callback) };
+            callback(allocations_outstanding)
+        }
+    }
+}
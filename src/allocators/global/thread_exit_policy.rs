@@ -0,0 +1,23 @@
+/// What to do with a thread local allocator when its thread exits (or `drop_thread_local_allocator()` is called) whilst memory it allocated is still in use.
+///
+/// Defaults to `Drop`.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum ThreadExitPolicy {
+    /// Drop the allocator, returning all its memory to its memory source.
+    ///
+    /// If any memory is still in use, ie has leaked, the leak is recorded in `GlobalSwitchableAllocator::thread_exit_leaks()` and the allocator is dropped anyway; that memory is then invalid, and must be neither used nor freed.
+    /// Use when threads are expected to free all they allocate, so that a leak is a bug to report rather than memory to keep.
+    Drop,
+
+    /// Hand the allocator to the global tier, which frees the memory still in use as it is deallocated by other threads, then drops the allocator once all of it has been.
+    ///
+    /// Use when memory allocated by a thread is expected to outlive it, eg when it is sent to another thread; it is then not reported as a leak.
+    HandToGlobal,
+}
+
+impl Default for ThreadExitPolicy {
+    #[inline(always)]
+    fn default() -> Self {
+        ThreadExitPolicy::Drop
+    }
+}
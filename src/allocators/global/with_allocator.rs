@@ -0,0 +1,125 @@
+use crate::allocators::global::current_allocator_in_use::CurrentAllocatorInUse;
+use crate::allocators::global::global_switchable_allocator::GlobalSwitchableAllocator;
+use crate::allocators::global::local_allocator_registry::LocalAllocatorRegistration;
+use std::fmt;
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::future::Future;
+use std::mem::ManuallyDrop;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A future which polls an inner future with its own coroutine local allocator (eg an arena for an async task).
+///
+/// On every poll, the coroutine local allocator is swapped in with `replace_registered_coroutine_local_allocator()` and the current allocator in use switched to `CurrentAllocatorInUse::CoroutineLocal`; both are restored afterwards, even if polling panics.
+///
+/// The coroutine local allocator stays registered from creation until drop, so memory it allocated which is freed between polls, by this or another thread, is queued for it and freed when it is next swapped in.
+///
+/// The inner future is dropped with its coroutine local allocator swapped in, then the coroutine local allocator is unregistered and dropped.
+/// Memory allocated by the coroutine local allocator must therefore not outlive this future; in particular, the output of the inner future must not own any of it.
+pub struct WithAllocator<F: Future, GSA: 'static + GlobalSwitchableAllocator> {
+    future: ManuallyDrop<F>,
+    coroutine_local_allocator: Option<RegisteredCoroutineLocalAllocator<GSA>>,
+    global_switchable_allocator: &'static GSA,
+}
+
+type RegisteredCoroutineLocalAllocator<GSA> = (
+    <GSA as GlobalSwitchableAllocator>::CoroutineLocalAllocator,
+    Option<LocalAllocatorRegistration>,
+);
+
+impl<F: Future, GSA: 'static + GlobalSwitchableAllocator> Debug for WithAllocator<F, GSA> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "WithAllocator")
+    }
+}
+
+impl<F: Future, GSA: 'static + GlobalSwitchableAllocator> Drop for WithAllocator<F, GSA> {
+    #[inline(always)]
+    fn drop(&mut self) {
+        let future = &mut self.future;
+        Self::swapped_in(
+            self.global_switchable_allocator,
+            &mut self.coroutine_local_allocator,
+            || unsafe { ManuallyDrop::drop(future) },
+        );
+
+        if let Some((coroutine_local_allocator, Some(registration))) =
+            self.coroutine_local_allocator.take()
+        {
+            self.global_switchable_allocator
+                .unregister_coroutine_local_allocator(&coroutine_local_allocator, registration)
+        }
+    }
+}
+
+impl<F: Future, GSA: 'static + GlobalSwitchableAllocator> Future for WithAllocator<F, GSA> {
+    type Output = F::Output;
+
+    #[inline(always)]
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        // The inner future is never moved out of `self`.
+        let this = unsafe { self.get_unchecked_mut() };
+        let future = unsafe { Pin::new_unchecked(&mut *this.future) };
+        Self::swapped_in(
+            this.global_switchable_allocator,
+            &mut this.coroutine_local_allocator,
+            || future.poll(cx),
+        )
+    }
+}
+
+impl<F: Future, GSA: 'static + GlobalSwitchableAllocator> WithAllocator<F, GSA> {
+    /// Creates a new instance which polls `future` with `coroutine_local_allocator` as the coroutine local allocator of `global_switchable_allocator`.
+    #[inline(always)]
+    pub fn new(
+        future: F,
+        coroutine_local_allocator: GSA::CoroutineLocalAllocator,
+        global_switchable_allocator: &'static GSA,
+    ) -> Self {
+        let registration = global_switchable_allocator
+            .register_coroutine_local_allocator(&coroutine_local_allocator);
+        Self {
+            future: ManuallyDrop::new(future),
+            coroutine_local_allocator: Some((coroutine_local_allocator, registration)),
+            global_switchable_allocator,
+        }
+    }
+
+    #[inline(always)]
+    fn swapped_in<R>(
+        global_switchable_allocator: &'static GSA,
+        coroutine_local_allocator: &mut Option<RegisteredCoroutineLocalAllocator<GSA>>,
+        callback: impl FnOnce() -> R,
+    ) -> R {
+        let swap_back = SwapBackOnDrop {
+            restore_to: global_switchable_allocator
+                .replace_registered_coroutine_local_allocator(coroutine_local_allocator.take()),
+            swapped_out_to: coroutine_local_allocator,
+            global_switchable_allocator,
+        };
+
+        let result = global_switchable_allocator.callback_with_different_current_allocator(
+            CurrentAllocatorInUse::CoroutineLocal,
+            callback,
+        );
+        drop(swap_back);
+        result
+    }
+}
+
+/// Swaps the previous coroutine local allocator back in when dropped, including when unwinding from a panic.
+struct SwapBackOnDrop<'a, GSA: 'static + GlobalSwitchableAllocator> {
+    restore_to: Option<RegisteredCoroutineLocalAllocator<GSA>>,
+    swapped_out_to: &'a mut Option<RegisteredCoroutineLocalAllocator<GSA>>,
+    global_switchable_allocator: &'static GSA,
+}
+
+impl<'a, GSA: 'static + GlobalSwitchableAllocator> Drop for SwapBackOnDrop<'a, GSA> {
+    #[inline(always)]
+    fn drop(&mut self) {
+        *self.swapped_out_to = self
+            .global_switchable_allocator
+            .replace_registered_coroutine_local_allocator(self.restore_to.take());
+    }
+}
@@ -0,0 +1,17 @@
+use std::fmt::Debug;
+
+/// A lock used to serialize access to an allocator shared between threads.
+///
+/// Implementations are used inside allocation paths, so they must never allocate memory themselves.
+pub trait AllocatorLock: Debug + Sized {
+    /// An unlocked instance; usable in `const` and `static` contexts.
+    const UNLOCKED: Self;
+
+    /// Acquire the lock, spinning or blocking until it is available.
+    fn lock(&self);
+
+    /// Release the lock.
+    ///
+    /// Must only be called by the current holder of the lock.
+    fn unlock(&self);
+}
@@ -0,0 +1,122 @@
+use crate::allocators::locked::allocator_lock::AllocatorLock;
+use libc::{syscall, timespec, SYS_futex, FUTEX_PRIVATE_FLAG, FUTEX_WAIT, FUTEX_WAKE};
+use std::ptr::null;
+use std::sync::atomic::AtomicI32;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+
+/// A mutex which puts contending threads to sleep using the Linux `futex()` system call.
+///
+/// Suitable when the allocator lock may be held for a long time (eg when the wrapped allocator makes system calls), as waiting threads do not burn CPU time.
+///
+/// Only on Android and Linux.
+#[derive(Debug)]
+pub struct FutexMutex(AtomicI32);
+
+impl AllocatorLock for FutexMutex {
+    const UNLOCKED: Self = Self(AtomicI32::new(UNLOCKED_STATE));
+
+    #[inline(always)]
+    fn lock(&self) {
+        futex_lock(&self.0, FUTEX_PRIVATE_FLAG)
+    }
+
+    #[inline(always)]
+    fn unlock(&self) {
+        futex_unlock(&self.0, FUTEX_PRIVATE_FLAG)
+    }
+}
+
+/// A `FutexMutex` which can be shared between processes, by placing it in memory mapped by all of them (eg the header of a `SharedMemorySource`).
+///
+/// Slightly slower than a `FutexMutex`, as the kernel has to look up the futex by its physical page.
+///
+/// Unlocked when all zero bytes.
+///
+/// Only on Android and Linux.
+#[derive(Debug)]
+pub struct ProcessSharedFutexMutex(AtomicI32);
+
+impl AllocatorLock for ProcessSharedFutexMutex {
+    const UNLOCKED: Self = Self(AtomicI32::new(UNLOCKED_STATE));
+
+    #[inline(always)]
+    fn lock(&self) {
+        const PROCESS_SHARED: i32 = 0;
+
+        futex_lock(&self.0, PROCESS_SHARED)
+    }
+
+    #[inline(always)]
+    fn unlock(&self) {
+        const PROCESS_SHARED: i32 = 0;
+
+        futex_unlock(&self.0, PROCESS_SHARED)
+    }
+}
+
+const UNLOCKED_STATE: i32 = 0;
+
+const LOCKED_STATE: i32 = 1;
+
+const LOCKED_WITH_WAITERS_STATE: i32 = 2;
+
+#[inline(always)]
+fn futex_lock(futex: &AtomicI32, private_flag: i32) {
+    let state = match futex.compare_exchange(UNLOCKED_STATE, LOCKED_STATE, Acquire, Relaxed) {
+        Ok(_) => return,
+        Err(state) => state,
+    };
+
+    let mut state = if state == LOCKED_WITH_WAITERS_STATE {
+        state
+    } else {
+        futex.swap(LOCKED_WITH_WAITERS_STATE, Acquire)
+    };
+
+    while state != UNLOCKED_STATE {
+        futex_wait(futex, private_flag, LOCKED_WITH_WAITERS_STATE);
+        state = futex.swap(LOCKED_WITH_WAITERS_STATE, Acquire);
+    }
+}
+
+#[inline(always)]
+fn futex_unlock(futex: &AtomicI32, private_flag: i32) {
+    if unlikely!(futex.swap(UNLOCKED_STATE, Release) == LOCKED_WITH_WAITERS_STATE) {
+        futex_wake_one(futex, private_flag)
+    }
+}
+
+#[inline(always)]
+fn futex_wait(futex: &AtomicI32, private_flag: i32, expected_state: i32) {
+    const NO_TIMEOUT: *const timespec = null();
+
+    // Spurious wake ups and `EAGAIN` / `EINTR` are handled by the caller re-checking the state.
+    unsafe {
+        syscall(
+            SYS_futex,
+            futex_address(futex),
+            FUTEX_WAIT | private_flag,
+            expected_state,
+            NO_TIMEOUT,
+        )
+    };
+}
+
+#[inline(always)]
+fn futex_wake_one(futex: &AtomicI32, private_flag: i32) {
+    const ONE_WAITER: i32 = 1;
+
+    unsafe {
+        syscall(
+            SYS_futex,
+            futex_address(futex),
+            FUTEX_WAKE | private_flag,
+            ONE_WAITER,
+        )
+    };
+}
+
+#[inline(always)]
+fn futex_address(futex: &AtomicI32) -> *const i32 {
+    futex as *const AtomicI32 as *const i32
+}
@@ -0,0 +1,217 @@
+use crate::allocators::allocator::Allocator;
+use crate::allocators::global::local_allocator::LocalAllocator;
+use crate::allocators::global::memory_range::MemoryRange;
+use crate::allocators::locked::allocator_lock::AllocatorLock;
+use crate::allocators::locked::spin_lock::SpinLock;
+use crate::memory_address::MemoryAddress;
+use std::alloc::{AllocError, AllocRef, GlobalAlloc, Layout};
+use std::borrow::Borrow;
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::marker::PhantomData;
+use std::num::NonZeroUsize;
+use std::ptr::NonNull;
+
+/// Makes any allocator thread-safe by serializing all access to it with a lock of type `L`.
+///
+/// This allows, say, a `MultipleBinarySearchTreeAllocator` to be used as the `$GlobalAllocator` of `switchable_allocator!` or directly as a `#[global_allocator]`.
+///
+/// As most allocators can not be created in a `const` context, use `LockedAllocator::lazy()` in a `static` to defer creation until the first allocation.
+/// The initializer is called with the lock held, so it must not itself allocate from this allocator.
+///
+/// The wrapped allocator must be `Send`, as it is used by whichever thread holds the lock.
+///
+/// The lock is usually owned, but can be borrowed, `B`, using `with_lock()`; this is how an allocator whose memory is shared between processes is locked with a lock held in that shared memory (see `ProcessSharedLockedAllocator`).
+pub struct LockedAllocator<A: Allocator, L: AllocatorLock = SpinLock, B: Borrow<L> = L> {
+    lock: B,
+    allocator: UnsafeCell<Option<A>>,
+    initializer: Option<fn() -> Result<A, AllocError>>,
+    marker: PhantomData<L>,
+}
+
+/// The wrapped allocator is only moved between threads with this instance.
+unsafe impl<A: Allocator + Send, L: AllocatorLock, B: Borrow<L> + Send> Send
+    for LockedAllocator<A, L, B>
+{
+}
+
+/// The wrapped allocator is only ever used by one thread at a time, whilst holding the lock, so need not itself be `Sync`; it must, however, be `Send`.
+unsafe impl<A: Allocator + Send, L: AllocatorLock, B: Borrow<L> + Sync> Sync
+    for LockedAllocator<A, L, B>
+{
+}
+
+impl<A: Allocator, L: AllocatorLock, B: Borrow<L>> Debug for LockedAllocator<A, L, B> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "LockedAllocator")
+    }
+}
+
+unsafe impl<A: Allocator, L: AllocatorLock, B: Borrow<L>> GlobalAlloc for LockedAllocator<A, L, B> {
+    crate::global_alloc!();
+}
+
+unsafe impl<A: Allocator, L: AllocatorLock, B: Borrow<L>> AllocRef for LockedAllocator<A, L, B> {
+    crate::alloc_ref!();
+}
+
+impl<A: Allocator, L: AllocatorLock, B: Borrow<L>> Allocator for LockedAllocator<A, L, B> {
+    #[inline(always)]
+    fn allocate(
+        &self,
+        non_zero_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+    ) -> Result<MemoryAddress, AllocError> {
+        self.locked(|allocator| allocator.allocate(non_zero_size, non_zero_power_of_two_alignment))
+    }
+
+    #[inline(always)]
+    fn deallocate(
+        &self,
+        non_zero_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) {
+        let _ = self.locked(|allocator| {
+            allocator.deallocate(
+                non_zero_size,
+                non_zero_power_of_two_alignment,
+                current_memory,
+            );
+            Ok(())
+        });
+    }
+
+    #[inline(always)]
+    fn growing_reallocate(
+        &self,
+        non_zero_new_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+        non_zero_current_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> Result<MemoryAddress, AllocError> {
+        self.locked(|allocator| {
+            allocator.growing_reallocate(
+                non_zero_new_size,
+                non_zero_power_of_two_alignment,
+                non_zero_current_size,
+                current_memory,
+            )
+        })
+    }
+
+    #[inline(always)]
+    fn shrinking_reallocate(
+        &self,
+        non_zero_new_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+        non_zero_current_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> Result<MemoryAddress, AllocError> {
+        self.locked(|allocator| {
+            allocator.shrinking_reallocate(
+                non_zero_new_size,
+                non_zero_power_of_two_alignment,
+                non_zero_current_size,
+                current_memory,
+            )
+        })
+    }
+}
+
+impl<A: LocalAllocator, L: AllocatorLock, B: Borrow<L>> LocalAllocator
+    for LockedAllocator<A, L, B>
+{
+    /// An empty memory range is returned if a lazily created allocator has not yet been created.
+    #[inline(always)]
+    fn memory_range(&self) -> MemoryRange {
+        let lock = self.lock.borrow();
+        lock.lock();
+        let _guard = UnlockOnDrop(lock);
+
+        match unsafe { &*self.allocator.get() } {
+            Some(ref allocator) => allocator.memory_range(),
+            None => MemoryRange::new(Self::ZERO_SIZED_ALLOCATION, Self::ZERO_SIZED_ALLOCATION),
+        }
+    }
+}
+
+impl<A: Allocator, L: AllocatorLock> LockedAllocator<A, L> {
+    /// Create a new instance wrapping an already created allocator.
+    #[inline(always)]
+    pub const fn new(allocator: A) -> Self {
+        Self {
+            lock: L::UNLOCKED,
+            allocator: UnsafeCell::new(Some(allocator)),
+            initializer: None,
+            marker: PhantomData,
+        }
+    }
+
+    /// Create a new instance which creates the wrapped allocator using `initializer` when first used.
+    ///
+    /// If `initializer` fails, then allocations fail with `AllocError`; it will be retried on the next allocation.
+    #[inline(always)]
+    pub const fn lazy(initializer: fn() -> Result<A, AllocError>) -> Self {
+        Self {
+            lock: L::UNLOCKED,
+            allocator: UnsafeCell::new(None),
+            initializer: Some(initializer),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<A: Allocator, L: AllocatorLock, B: Borrow<L>> LockedAllocator<A, L, B> {
+    /// Create a new instance wrapping an already created allocator, serializing access to it with `lock`, which may be borrowed.
+    #[inline(always)]
+    pub fn with_lock(lock: B, allocator: A) -> Self {
+        Self {
+            lock,
+            allocator: UnsafeCell::new(Some(allocator)),
+            initializer: None,
+            marker: PhantomData,
+        }
+    }
+
+    /// Consumes this instance, returning the wrapped allocator, if it has been created.
+    #[inline(always)]
+    pub fn into_inner(self) -> Option<A> {
+        self.allocator.into_inner()
+    }
+
+    #[inline(always)]
+    fn locked<R>(
+        &self,
+        callback: impl FnOnce(&A) -> Result<R, AllocError>,
+    ) -> Result<R, AllocError> {
+        let lock = self.lock.borrow();
+        lock.lock();
+        let _guard = UnlockOnDrop(lock);
+
+        let allocator = unsafe { &mut *self.allocator.get() };
+        if unlikely!(allocator.is_none()) {
+            match self.initializer {
+                None => return Err(AllocError),
+                Some(initializer) => *allocator = Some(initializer()?),
+            }
+        }
+
+        match allocator {
+            Some(ref allocator) => callback(allocator),
+            None => unreachable!(),
+        }
+    }
+}
+
+/// Releases the lock even if the wrapped allocator panics.
+pub(crate) struct UnlockOnDrop<'a, L: AllocatorLock>(pub(crate) &'a L);
+
+impl<'a, L: AllocatorLock> Drop for UnlockOnDrop<'a, L> {
+    #[inline(always)]
+    fn drop(&mut self) {
+        self.0.unlock()
+    }
+}
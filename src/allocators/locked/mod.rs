@@ -0,0 +1,17 @@
+pub mod allocator_lock;
+#[cfg(any(target_os = "android", target_os = "linux"))]
+pub mod futex_mutex;
+pub mod locked_allocator;
+pub mod process_shared_locked_allocator;
+pub mod spin_lock;
+pub mod ticket_lock;
+
+pub mod prelude {
+    pub use super::allocator_lock::*;
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub use super::futex_mutex::*;
+    pub use super::locked_allocator::*;
+    pub use super::process_shared_locked_allocator::*;
+    pub use super::spin_lock::*;
+    pub use super::ticket_lock::*;
+}
@@ -0,0 +1,7 @@
+use crate::allocators::locked::locked_allocator::LockedAllocator;
+
+/// Makes an allocator whose memory is shared between processes (eg a `BitSetAllocator` using a `SharedMemorySource`) safe to use from all of them by serializing all access to it with a lock held in that shared memory.
+///
+/// Each process has its own instance of the allocator, referring to the same memory; the lock, `L`, is borrowed, typically from `SharedMemorySource::lock()`, and passed to `LockedAllocator::with_lock()`.
+/// Use a lock which works between processes, such as a `ProcessSharedFutexMutex` or a `SpinLock`.
+pub type ProcessSharedLockedAllocator<'lock, A, L> = LockedAllocator<A, L, &'lock L>;
@@ -0,0 +1,31 @@
+use crate::allocators::locked::allocator_lock::AllocatorLock;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use std::sync::atomic::{spin_loop_hint, AtomicBool};
+
+/// A simple test-and-test-and-set spin lock.
+///
+/// Very fast when uncontended, but wastes CPU time and is unfair when heavily contended.
+#[derive(Debug)]
+pub struct SpinLock(AtomicBool);
+
+impl AllocatorLock for SpinLock {
+    const UNLOCKED: Self = Self(AtomicBool::new(false));
+
+    #[inline(always)]
+    fn lock(&self) {
+        while unlikely!(self
+            .0
+            .compare_exchange_weak(false, true, Acquire, Relaxed)
+            .is_err())
+        {
+            while self.0.load(Relaxed) {
+                spin_loop_hint()
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn unlock(&self) {
+        self.0.store(false, Release)
+    }
+}
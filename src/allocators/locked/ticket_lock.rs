@@ -0,0 +1,34 @@
+use crate::allocators::locked::allocator_lock::AllocatorLock;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use std::sync::atomic::{spin_loop_hint, AtomicUsize};
+
+/// A first-in, first-out (FIFO) spin lock.
+///
+/// Slightly slower than a `SpinLock` when uncontended, but fair: threads acquire the lock in the order they asked for it.
+#[derive(Debug)]
+pub struct TicketLock {
+    next_ticket: AtomicUsize,
+    now_serving: AtomicUsize,
+}
+
+impl AllocatorLock for TicketLock {
+    const UNLOCKED: Self = Self {
+        next_ticket: AtomicUsize::new(0),
+        now_serving: AtomicUsize::new(0),
+    };
+
+    #[inline(always)]
+    fn lock(&self) {
+        let ticket = self.next_ticket.fetch_add(1, Relaxed);
+
+        while unlikely!(self.now_serving.load(Acquire) != ticket) {
+            spin_loop_hint()
+        }
+    }
+
+    #[inline(always)]
+    fn unlock(&self) {
+        let now_serving = self.now_serving.load(Relaxed);
+        self.now_serving.store(now_serving.wrapping_add(1), Release)
+    }
+}
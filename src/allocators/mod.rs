@@ -7,21 +7,56 @@ pub mod bit_set;
 #[macro_use]
 pub mod global;
 
+/// Thread-safe, lock-based wrappers for allocators.
+pub mod locked;
+
+/// Allocators sharded by the CPU the current thread is running on.
+///
+/// Only on Android and Linux.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+pub mod per_cpu;
+
+/// A thread cache front-end to a shared allocator.
+///
+/// Only on Unix.
+#[cfg(unix)]
+pub mod thread_cache;
+
 pub mod allocator;
+pub mod arena_pool;
+pub mod atomic_bump_allocator;
 pub mod bump_allocator;
 pub mod context_allocator;
+pub mod failing_allocator;
+pub mod fallback_allocator;
 pub mod memory_map_allocator;
 pub mod multiple_binary_search_tree_allocator;
+pub mod segregator;
 
 #[macro_use]
 pub mod prelude {
     pub use super::binary_search_trees::*;
     pub use super::bit_set::*;
     pub use super::global::*;
+    pub use super::locked::prelude::*;
+    pub use super::locked::*;
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub use super::per_cpu::prelude::*;
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub use super::per_cpu::*;
+    #[cfg(unix)]
+    pub use super::thread_cache::prelude::*;
+    #[cfg(unix)]
+    pub use super::thread_cache::*;
 
     pub use super::allocator::*;
+    pub use super::arena_pool::*;
+    pub use super::atomic_bump_allocator::*;
     pub use super::bump_allocator::*;
     pub use super::context_allocator::*;
+    pub use super::failing_allocator::*;
+    pub use super::fallback_allocator::*;
     pub use super::memory_map_allocator::*;
     pub use super::multiple_binary_search_tree_allocator::*;
+    pub use super::segregator::*;
 }
@@ -31,14 +31,20 @@ use crate::allocators::allocator::Allocator;
 ///
 /// This allocator NEVER grows or shrinks its memory region.
 ///
+/// If created with `new_with_decommit_threshold()`, the pages of free blocks at least as large as the threshold are decommitted (see `MemorySource::decommit()`), returning their memory to the operating system, say; they are recommitted when the block is next allocated.
+///
 /// This allocator is not thread-safe.
 pub struct MultipleBinarySearchTreeAllocator<MS: MemorySource> {
     inner: BinarySearchTreesWithCachedKnowledgeOfFirstChild,
     memory_source: MS,
     allocations_start_from: MemoryAddress,
     memory_source_size: NonZeroUsize,
+    decommit_threshold: NonZeroUsize,
 }
 
+/// The binary search trees of free blocks live in memory owned by this allocator alone, so it may be moved to another thread.
+unsafe impl<MS: MemorySource + Send> Send for MultipleBinarySearchTreeAllocator<MS> {}
+
 impl<MS: MemorySource> Drop for MultipleBinarySearchTreeAllocator<MS> {
     #[inline(always)]
     fn drop(&mut self) {
@@ -62,11 +68,12 @@ impl<MS: MemorySource> Allocator for MultipleBinarySearchTreeAllocator<MS> {
         non_zero_power_of_two_alignment: NonZeroUsize,
     ) -> Result<MemoryAddress, AllocError> {
         macro_rules! try_to_allocate_exact_size_block {
-            ($node_pointer: ident, $is_cached_first_child: expr, $non_zero_power_of_two_alignment: ident, $binary_search_tree: ident, $_block_size: ident, $_exact_block_size: ident, $_self: ident) => {{
+            ($node_pointer: ident, $is_cached_first_child: expr, $non_zero_power_of_two_alignment: ident, $binary_search_tree: ident, $block_size: ident, $_exact_block_size: ident, $self: ident) => {{
                 let memory_address = $node_pointer.value();
 
                 if likely!(memory_address.is_aligned_to($non_zero_power_of_two_alignment)) {
                     $binary_search_tree.remove($node_pointer, $is_cached_first_child);
+                    $self.recommit_block(memory_address, $block_size)?;
 
                     return Ok(memory_address);
                 }
@@ -83,6 +90,7 @@ impl<MS: MemorySource> Allocator for MultipleBinarySearchTreeAllocator<MS> {
                         memory_address.is_aligned_to($floored_non_zero_power_of_two_alignment)
                     ) {
                         $binary_search_tree.remove($node_pointer, $is_cached_first_child);
+                        $self.recommit_block(start_memory_address, $block_size)?;
 
                         // Block(s) at front.
                         $self.split_up_block(start_memory_address, memory_address);
@@ -151,19 +159,18 @@ impl<MS: MemorySource> Allocator for MultipleBinarySearchTreeAllocator<MS> {
         }
 
         // (1) Try to satisfy allocation from a binary search tree of blocks of the same size.
+        let exact_block_size = Self::block_size(non_zero_size);
         let binary_search_tree_index_for_blocks_of_exact_size =
             BinarySearchTreesWithCachedKnowledgeOfFirstChild::binary_search_tree_index(
-                Self::block_size(non_zero_size),
+                exact_block_size,
             );
-        #[allow(dead_code)]
-        const UNUSED: () = ();
         try_to_satisfy_allocation!(
             try_to_allocate_exact_size_block,
             binary_search_tree_index_for_blocks_of_exact_size,
             non_zero_power_of_two_alignment,
-            Unused,
-            Unused,
-            Unused
+            exact_block_size,
+            exact_block_size,
+            self
         );
 
         // (2) Try to satisfy allocation from binary search trees of blocks of larger size (either because of exhaustion or a large alignment).
@@ -171,7 +178,6 @@ impl<MS: MemorySource> Allocator for MultipleBinarySearchTreeAllocator<MS> {
             BinarySearchTreesWithCachedKnowledgeOfFirstChild::floor_alignment_to_minimum(
                 non_zero_power_of_two_alignment,
             );
-        let exact_block_size = BinarySearchTreesWithCachedKnowledgeOfFirstChild::binary_search_tree_index_to_block_size(binary_search_tree_index_for_blocks_of_exact_size);
         for binary_search_tree_index_of_larger_size_block in
             (binary_search_tree_index_for_blocks_of_exact_size + 1)
                 ..BinarySearchTreesWithCachedKnowledgeOfFirstChild::NUMBER_OF_BINARY_SEARCH_TREES
@@ -208,8 +214,14 @@ impl<MS: MemorySource> Allocator for MultipleBinarySearchTreeAllocator<MS> {
         let has_blocks = binary_search_tree.has_blocks();
         let inserted_node_pointer = binary_search_tree.insert_memory_address(current_memory);
         if likely!(has_blocks) {
-            self.coalesce(inserted_node_pointer, block_size, binary_search_tree_index);
+            let coalesced =
+                self.coalesce(inserted_node_pointer, block_size, binary_search_tree_index);
+            if coalesced {
+                // The coalesced blocks have been deallocated, and so decommitted, in turn.
+                return;
+            }
         }
+        self.decommit_block(current_memory, block_size)
     }
 
     #[inline(always)]
@@ -246,6 +258,7 @@ impl<MS: MemorySource> Allocator for MultipleBinarySearchTreeAllocator<MS> {
                 let is_first_child =
                     contiguous_block_node_pointer == binary_search_tree.cached_first_child();
                 binary_search_tree.remove(contiguous_block_node_pointer, is_first_child);
+                self.recommit_block(current_memory.add_non_zero(old_block_size), old_block_size)?;
 
                 return Ok(current_memory);
             }
@@ -312,6 +325,22 @@ impl<MS: MemorySource> MultipleBinarySearchTreeAllocator<MS> {
     ///
     /// The memory must be aligned to `BinarySearchTreesWithCachedKnowledgeOfFirstChild::MinimumAlignment`, which is the same as the size of a `Node`.
     pub fn new(memory_source: MS, memory_source_size: NonZeroUsize) -> Result<Self, AllocError> {
+        Self::new_with_decommit_threshold(memory_source, memory_source_size, Self::NEVER_DECOMMIT)
+    }
+
+    /// As `new()`, but free blocks of at least `decommit_threshold` bytes are decommitted.
+    ///
+    /// Only whole pages after the node at the start of a free block are decommitted, so a block smaller than two pages is never decommitted, whatever `decommit_threshold`; a smaller threshold is harmless, but wastes calls to the memory source, so should be at least twice the page size.
+    pub fn new_with_decommit_threshold(
+        memory_source: MS,
+        memory_source_size: NonZeroUsize,
+        decommit_threshold: NonZeroUsize,
+    ) -> Result<Self, AllocError> {
+        debug_assert!(
+            decommit_threshold > Self::NODE_SIZE,
+            "decommit_threshold `{}` must exceed the size of a node",
+            decommit_threshold
+        );
         debug_assert_ne!(
             BinarySearchTreesWithCachedKnowledgeOfFirstChild::NUMBER_OF_BINARY_SEARCH_TREES,
             0,
@@ -332,6 +361,7 @@ impl<MS: MemorySource> MultipleBinarySearchTreeAllocator<MS> {
             memory_source,
             allocations_start_from,
             memory_source_size,
+            decommit_threshold,
         };
 
         let mut size = memory_source_size.get();
@@ -367,6 +397,48 @@ impl<MS: MemorySource> MultipleBinarySearchTreeAllocator<MS> {
         Ok(this)
     }
 
+    /// The memory source.
+    #[inline(always)]
+    pub fn memory_source(&self) -> &MS {
+        &self.memory_source
+    }
+
+    const NEVER_DECOMMIT: NonZeroUsize = unsafe { NonZeroUsize::new_unchecked(usize::MAX) };
+
+    /// The start of a free block holds its node in a binary search tree, so is never decommitted.
+    const NODE_SIZE: NonZeroUsize =
+        BinarySearchTreesWithCachedKnowledgeOfFirstChild::MINIMUM_ALIGNMENT;
+
+    #[inline(always)]
+    fn decommit_block(&self, block: MemoryAddress, block_size: NonZeroUsize) {
+        if unlikely!(block_size >= self.decommit_threshold) {
+            self.memory_source.decommit(
+                (block_size.get() - Self::NODE_SIZE.get()).non_zero(),
+                block.add_non_zero(Self::NODE_SIZE),
+            )
+        }
+    }
+
+    #[inline(always)]
+    fn recommit_block(
+        &self,
+        block: MemoryAddress,
+        block_size: NonZeroUsize,
+    ) -> Result<(), AllocError> {
+        if unlikely!(block_size >= self.decommit_threshold) {
+            let recommitted = self.memory_source.recommit(
+                (block_size.get() - Self::NODE_SIZE.get()).non_zero(),
+                block.add_non_zero(Self::NODE_SIZE),
+            );
+            if unlikely!(recommitted.is_err()) {
+                self.binary_search_tree_for_block_size(block_size)
+                    .insert_memory_address(block);
+                return Err(AllocError);
+            }
+        }
+        Ok(())
+    }
+
     #[inline(always)]
     fn split_up_block(&self, mut from: MemoryAddress, to: MemoryAddress) {
         let mut difference = to.difference(from);
@@ -387,12 +459,13 @@ impl<MS: MemorySource> MultipleBinarySearchTreeAllocator<MS> {
         }
     }
 
+    /// Returns `true` if blocks were coalesced.
     fn coalesce(
         &self,
         inserted_node_pointer: NodePointer,
         block_size: NonZeroUsize,
         binary_search_tree_index: usize,
-    ) {
+    ) -> bool {
         let furthest_back_contiguous_with_inserted_node_pointer_memory_address =
             inserted_node_pointer.furthest_back_contiguous_with(block_size);
 
@@ -405,7 +478,7 @@ impl<MS: MemorySource> MultipleBinarySearchTreeAllocator<MS> {
         let nothing_to_coalesce = difference == 0;
 
         if likely!(nothing_to_coalesce) {
-            return;
+            return false;
         }
 
         let first_block_memory_address = {
@@ -452,6 +525,8 @@ impl<MS: MemorySource> MultipleBinarySearchTreeAllocator<MS> {
             difference -= smallest_power_of_two_difference.get();
             likely!(difference != 0)
         } {}
+
+        true
     }
 
     #[inline(always)]
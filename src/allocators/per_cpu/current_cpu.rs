@@ -0,0 +1,15 @@
+use libc::sched_getcpu;
+
+/// The zero-based index of the CPU the current thread is running on.
+///
+/// Uses `sched_getcpu()`, which recent versions of glibc implement by reading a restartable sequence (`rseq`) area that the C library itself registers for each thread, and older versions using the vDSO; either is far cheaper than a system call.
+/// No restartable sequence area is registered by this crate, as only one may be registered per thread and it would have to be unregistered before the thread's memory is freed.
+///
+/// The thread may be migrated to another CPU at any time, so the result is only a hint; `PerCpuAllocator` always takes the lock of the shard it uses.
+#[inline(always)]
+pub fn current_cpu() -> usize {
+    match unsafe { sched_getcpu() } {
+        cpu if likely!(cpu >= 0) => cpu as usize,
+        _ => 0,
+    }
+}
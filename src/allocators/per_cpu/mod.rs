@@ -0,0 +1,7 @@
+pub mod current_cpu;
+pub mod per_cpu_allocator;
+
+pub mod prelude {
+    pub use super::current_cpu::*;
+    pub use super::per_cpu_allocator::*;
+}
@@ -0,0 +1,419 @@
+use crate::allocators::allocator::Allocator;
+use crate::allocators::global::local_allocator::LocalAllocator;
+use crate::allocators::global::memory_range::MemoryRange;
+use crate::allocators::locked::allocator_lock::AllocatorLock;
+use crate::allocators::locked::locked_allocator::UnlockOnDrop;
+use crate::allocators::locked::spin_lock::SpinLock;
+use crate::allocators::per_cpu::current_cpu::current_cpu;
+use crate::extensions::prelude::*;
+use crate::memory_address::MemoryAddress;
+use crate::memory_sources::memory_source::MemorySource;
+use crate::memory_sources::mmap::numa::prelude::*;
+use crate::memory_sources::mmap::prelude::*;
+use libc::{c_char, closedir, opendir, readdir, sysconf, _SC_NPROCESSORS_CONF};
+use std::alloc::{AllocError, AllocRef, GlobalAlloc, Layout};
+use std::cell::UnsafeCell;
+use std::ffi::CStr;
+use std::fmt;
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::mem::size_of;
+use std::num::NonZeroUsize;
+use std::ptr::{drop_in_place, null_mut, write, NonNull};
+use std::slice::from_raw_parts;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use std::sync::atomic::{AtomicPtr, AtomicUsize};
+
+/// Shards allocations across one allocator per CPU, rather than one per thread, so memory use is bounded by the number of CPUs however many threads there are.
+///
+/// Allocations are made from the shard of the CPU the current thread is running on (see `current_cpu()`).
+/// As a thread can be migrated to another CPU at any time, each shard is guarded by a lock of type `L`; this is almost never contended.
+/// Should a shard be exhausted, allocations are made from the other shards.
+/// Deallocations and reallocations are made by the shard owning the memory, whichever CPU the thread is on; memory not owned by any shard is ignored when deallocated and fails to reallocate.
+///
+/// Each shard is created by `shard_factory`, which is passed the zero-based index of the CPU and, if binding to NUMA nodes, `NumaSettings` binding memory to that CPU's NUMA node; these can be passed to `MemoryMapSource::new()`.
+/// `shard_factory` must not allocate from this allocator, and the memory range of each shard must not change after creation.
+///
+/// Usable as a `#[global_allocator]` or as the `$GlobalAllocator` of `switchable_allocator!` if created with `new_lazy()`.
+///
+/// Only on Android and Linux.
+pub struct PerCpuAllocator<A: LocalAllocator, L: AllocatorLock = SpinLock> {
+    /// Null until the shards have been created.
+    shards: AtomicPtr<Shard<A, L>>,
+    number_of_shards: AtomicUsize,
+    initialization_lock: SpinLock,
+
+    bind_to_numa_nodes: bool,
+    shard_factory: fn(usize, Option<NumaSettings>) -> Result<A, AllocError>,
+}
+
+/// The shards are only moved between threads with this instance.
+unsafe impl<A: LocalAllocator + Send, L: AllocatorLock + Send> Send for PerCpuAllocator<A, L> {}
+
+/// Each shard is used by one thread at a time, whilst holding its lock, but by any thread, so must be `Send`.
+unsafe impl<A: LocalAllocator + Send, L: AllocatorLock + Sync> Sync for PerCpuAllocator<A, L> {}
+
+impl<A: LocalAllocator, L: AllocatorLock> Drop for PerCpuAllocator<A, L> {
+    #[inline(always)]
+    fn drop(&mut self) {
+        let shards = *self.shards.get_mut();
+        if likely!(!shards.is_null()) {
+            let number_of_shards = *self.number_of_shards.get_mut();
+            unsafe { Self::destroy_shards(shards, number_of_shards, number_of_shards) }
+        }
+    }
+}
+
+impl<A: LocalAllocator, L: AllocatorLock> Debug for PerCpuAllocator<A, L> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "PerCpuAllocator")
+    }
+}
+
+unsafe impl<A: LocalAllocator, L: AllocatorLock> GlobalAlloc for PerCpuAllocator<A, L> {
+    crate::global_alloc!();
+}
+
+unsafe impl<A: LocalAllocator, L: AllocatorLock> AllocRef for PerCpuAllocator<A, L> {
+    crate::alloc_ref!();
+}
+
+impl<A: LocalAllocator, L: AllocatorLock> Allocator for PerCpuAllocator<A, L> {
+    #[inline(always)]
+    fn allocate(
+        &self,
+        non_zero_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+    ) -> Result<MemoryAddress, AllocError> {
+        let shards = self.shards()?;
+        let home = current_cpu() % shards.len();
+
+        let allocate =
+            |allocator: &A| allocator.allocate(non_zero_size, non_zero_power_of_two_alignment);
+
+        let result = shards[home].locked(allocate);
+        if likely!(result.is_ok()) {
+            return result;
+        }
+
+        for shard in shards[(home + 1)..].iter().chain(shards[..home].iter()) {
+            let result = shard.locked(allocate);
+            if result.is_ok() {
+                return result;
+            }
+        }
+        Err(AllocError)
+    }
+
+    #[inline(always)]
+    fn deallocate(
+        &self,
+        non_zero_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) {
+        if let Some(owning_shard) = self.owning_shard(current_memory) {
+            owning_shard.locked(|allocator| {
+                allocator.deallocate(
+                    non_zero_size,
+                    non_zero_power_of_two_alignment,
+                    current_memory,
+                )
+            })
+        }
+    }
+
+    #[inline(always)]
+    fn growing_reallocate(
+        &self,
+        non_zero_new_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+        non_zero_current_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> Result<MemoryAddress, AllocError> {
+        let owning_shard = self.owning_shard(current_memory).ok_or(AllocError)?;
+
+        let result = owning_shard.locked(|allocator| {
+            allocator.growing_reallocate(
+                non_zero_new_size,
+                non_zero_power_of_two_alignment,
+                non_zero_current_size,
+                current_memory,
+            )
+        });
+        if likely!(result.is_ok()) {
+            return result;
+        }
+
+        // The owning shard is exhausted; move the memory to another shard.
+        let new_memory = self.allocate(non_zero_new_size, non_zero_power_of_two_alignment)?;
+        unsafe {
+            new_memory
+                .as_ptr()
+                .copy_from_nonoverlapping(current_memory.as_ptr(), non_zero_current_size.get())
+        };
+        owning_shard.locked(|allocator| {
+            allocator.deallocate(
+                non_zero_current_size,
+                non_zero_power_of_two_alignment,
+                current_memory,
+            )
+        });
+        Ok(new_memory)
+    }
+
+    #[inline(always)]
+    fn shrinking_reallocate(
+        &self,
+        non_zero_new_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+        non_zero_current_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> Result<MemoryAddress, AllocError> {
+        self.owning_shard(current_memory)
+            .ok_or(AllocError)?
+            .locked(|allocator| {
+                allocator.shrinking_reallocate(
+                    non_zero_new_size,
+                    non_zero_power_of_two_alignment,
+                    non_zero_current_size,
+                    current_memory,
+                )
+            })
+    }
+}
+
+impl<A: LocalAllocator, L: AllocatorLock> PerCpuAllocator<A, L> {
+    /// The array of shards is not allocated from the global allocator, as this allocator may be it.
+    const SHARDS_MEMORY_SOURCE: MemoryMapSource =
+        MemoryMapSource::new(false, false, false, false, HugePageSize::None, None);
+
+    /// New instance with one shard per configured CPU, created immediately.
+    ///
+    /// * `bind_to_numa_nodes`: Should each shard's `shard_factory` be passed `NumaSettings` binding memory to the NUMA node of its CPU?
+    /// * `shard_factory`: Creates the allocator for a CPU.
+    #[inline(always)]
+    pub fn new(
+        bind_to_numa_nodes: bool,
+        shard_factory: fn(usize, Option<NumaSettings>) -> Result<A, AllocError>,
+    ) -> Result<Self, AllocError> {
+        let this = Self::new_lazy(bind_to_numa_nodes, shard_factory);
+        this.shards()?;
+        Ok(this)
+    }
+
+    /// New instance which creates its shards on first allocation.
+    ///
+    /// Usable in `const` and `static` contexts, eg for a `#[global_allocator]`.
+    #[inline(always)]
+    pub const fn new_lazy(
+        bind_to_numa_nodes: bool,
+        shard_factory: fn(usize, Option<NumaSettings>) -> Result<A, AllocError>,
+    ) -> Self {
+        Self {
+            shards: AtomicPtr::new(null_mut()),
+            number_of_shards: AtomicUsize::new(0),
+            initialization_lock: SpinLock::UNLOCKED,
+
+            bind_to_numa_nodes,
+            shard_factory,
+        }
+    }
+
+    #[inline(always)]
+    fn shards(&self) -> Result<&[Shard<A, L>], AllocError> {
+        let shards = self.shards.load(Acquire);
+        if likely!(!shards.is_null()) {
+            return Ok(unsafe { from_raw_parts(shards, self.number_of_shards.load(Relaxed)) });
+        }
+
+        self.create_shards()
+    }
+
+    /// `None` if `current_memory` was not allocated by this instance, including if no shards have been created.
+    #[inline(always)]
+    fn owning_shard(&self, current_memory: MemoryAddress) -> Option<&Shard<A, L>> {
+        let shards = self.shards.load(Acquire);
+        if unlikely!(shards.is_null()) {
+            return None;
+        }
+        let shards = unsafe { from_raw_parts(shards, self.number_of_shards.load(Relaxed)) };
+
+        let home = &shards[current_cpu() % shards.len()];
+        if likely!(home.memory_range.contains(current_memory)) {
+            return Some(home);
+        }
+
+        shards
+            .iter()
+            .find(|shard| shard.memory_range.contains(current_memory))
+    }
+
+    #[cold]
+    fn create_shards(&self) -> Result<&[Shard<A, L>], AllocError> {
+        self.initialization_lock.lock();
+        let _guard = UnlockOnDrop(&self.initialization_lock);
+
+        // Another thread may have created the shards whilst this one waited for the lock.
+        let shards = self.shards.load(Acquire);
+        if !shards.is_null() {
+            return Ok(unsafe { from_raw_parts(shards, self.number_of_shards.load(Relaxed)) });
+        }
+
+        let number_of_shards = Self::number_of_cpus();
+        let shards = Self::SHARDS_MEMORY_SOURCE
+            .obtain(Self::shards_size(number_of_shards))?
+            .as_ptr() as *mut Shard<A, L>;
+
+        for cpu in 0..number_of_shards {
+            let numa_settings = if self.bind_to_numa_nodes {
+                Self::numa_settings_for_cpu(cpu)
+            } else {
+                None
+            };
+
+            match (self.shard_factory)(cpu, numa_settings) {
+                Ok(allocator) => unsafe { write(shards.add(cpu), Shard::new(allocator)) },
+
+                Err(AllocError) => {
+                    unsafe { Self::destroy_shards(shards, cpu, number_of_shards) };
+                    return Err(AllocError);
+                }
+            }
+        }
+
+        self.number_of_shards.store(number_of_shards, Relaxed);
+        self.shards.store(shards, Release);
+        Ok(unsafe { from_raw_parts(shards, number_of_shards) })
+    }
+
+    /// Drops the first `number_of_created_shards` and releases the memory holding them.
+    #[inline(always)]
+    unsafe fn destroy_shards(
+        shards: *mut Shard<A, L>,
+        number_of_created_shards: usize,
+        number_of_shards: usize,
+    ) {
+        for index in 0..number_of_created_shards {
+            drop_in_place(shards.add(index))
+        }
+
+        Self::SHARDS_MEMORY_SOURCE.release(
+            Self::shards_size(number_of_shards),
+            NonNull::new_unchecked(shards as *mut u8),
+        )
+    }
+
+    #[inline(always)]
+    fn shards_size(number_of_shards: usize) -> NonZeroUsize {
+        (number_of_shards * size_of::<Shard<A, L>>()).non_zero()
+    }
+
+    #[inline(always)]
+    fn number_of_cpus() -> usize {
+        match unsafe { sysconf(_SC_NPROCESSORS_CONF) } {
+            number_of_cpus if likely!(number_of_cpus > 0) => number_of_cpus as usize,
+            _ => 1,
+        }
+    }
+
+    #[inline(always)]
+    fn numa_settings_for_cpu(cpu: usize) -> Option<NumaSettings> {
+        Self::numa_node_of_cpu(cpu).map(|numa_node| {
+            let mut numa_node_bit_set = NumaNodeBitSet::default();
+            numa_node_bit_set.insert_numa_node(numa_node);
+            NumaSettings::new(NumaAllocationPolicy::Bind(numa_node_bit_set), false)
+        })
+    }
+
+    /// Finds the `nodeN` entry in `/sys/devices/system/cpu/cpuC`.
+    ///
+    /// Does not allocate from the global allocator, as this may be called whilst handling the first allocation.
+    fn numa_node_of_cpu(cpu: usize) -> Option<u8> {
+        const PREFIX: &[u8] = b"/sys/devices/system/cpu/cpu";
+
+        let mut path = [0u8; PREFIX.len() + 21];
+        path[..PREFIX.len()].copy_from_slice(PREFIX);
+        let mut digits = [0u8; 20];
+        let mut number_of_digits = 0;
+        let mut remaining = cpu;
+        loop {
+            digits[number_of_digits] = b'0' + (remaining % 10) as u8;
+            number_of_digits += 1;
+            remaining /= 10;
+            if remaining == 0 {
+                break;
+            }
+        }
+        for index in 0..number_of_digits {
+            path[PREFIX.len() + index] = digits[number_of_digits - 1 - index];
+        }
+
+        let directory = unsafe { opendir(path.as_ptr() as *const c_char) };
+        if unlikely!(directory.is_null()) {
+            return None;
+        }
+
+        let mut numa_node = None;
+        loop {
+            let entry = unsafe { readdir(directory) };
+            if entry.is_null() {
+                break;
+            }
+
+            let name = unsafe { CStr::from_ptr((*entry).d_name.as_ptr()) }.to_bytes();
+            if name.starts_with(b"node") {
+                numa_node = Self::parse_numa_node(&name[4..]);
+                if numa_node.is_some() {
+                    break;
+                }
+            }
+        }
+
+        unsafe { closedir(directory) };
+        numa_node
+    }
+
+    #[inline(always)]
+    fn parse_numa_node(digits: &[u8]) -> Option<u8> {
+        if digits.is_empty() {
+            return None;
+        }
+
+        let mut numa_node: u8 = 0;
+        for digit in digits {
+            if !digit.is_ascii_digit() {
+                return None;
+            }
+            numa_node = numa_node.checked_mul(10)?.checked_add(digit - b'0')?;
+        }
+        Some(numa_node)
+    }
+}
+
+/// Cache line aligned so that shards used by different CPUs do not share cache lines.
+#[repr(align(64))]
+struct Shard<A: LocalAllocator, L: AllocatorLock> {
+    lock: L,
+    allocator: UnsafeCell<A>,
+    memory_range: MemoryRange,
+}
+
+impl<A: LocalAllocator, L: AllocatorLock> Shard<A, L> {
+    #[inline(always)]
+    fn new(allocator: A) -> Self {
+        Self {
+            lock: L::UNLOCKED,
+            memory_range: allocator.memory_range(),
+            allocator: UnsafeCell::new(allocator),
+        }
+    }
+
+    #[inline(always)]
+    fn locked<R>(&self, callback: impl FnOnce(&A) -> R) -> R {
+        self.lock.lock();
+        let _guard = UnlockOnDrop(&self.lock);
+
+        callback(unsafe { &*self.allocator.get() })
+    }
+}
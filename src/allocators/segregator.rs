@@ -0,0 +1,191 @@
+use crate::allocators::allocator::Allocator;
+use crate::memory_address::MemoryAddress;
+use std::alloc::{AllocError, AllocRef, GlobalAlloc, Layout};
+use std::num::NonZeroUsize;
+use std::ptr::NonNull;
+
+/// An allocator which routes allocations of up to and including `THRESHOLD` bytes to a small allocator and larger allocations to a large allocator.
+///
+/// It:-
+///
+/// * Uses the size of an allocation to decide which allocator to use to deallocate and reallocate it, so needs no knowledge of either allocator's memory.
+/// * Copies memory between the two allocators when reallocating across `THRESHOLD`.
+///
+/// A typical usage is a `BitSetAllocator` for small allocations with a `MemoryMapAllocator` for large ones.
+///
+/// Segregators can be nested to route to more than two allocators.
+#[derive(Debug)]
+pub struct Segregator<const THRESHOLD: usize, Small: Allocator, Large: Allocator> {
+    small: Small,
+    large: Large,
+}
+
+unsafe impl<const THRESHOLD: usize, Small: Allocator, Large: Allocator> GlobalAlloc
+    for Segregator<THRESHOLD, Small, Large>
+{
+    crate::global_alloc!();
+}
+
+unsafe impl<const THRESHOLD: usize, Small: Allocator, Large: Allocator> AllocRef
+    for Segregator<THRESHOLD, Small, Large>
+{
+    crate::alloc_ref!();
+}
+
+impl<const THRESHOLD: usize, Small: Allocator, Large: Allocator> Allocator
+    for Segregator<THRESHOLD, Small, Large>
+{
+    #[inline(always)]
+    fn allocate(
+        &self,
+        non_zero_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+    ) -> Result<MemoryAddress, AllocError> {
+        if Self::is_small(non_zero_size) {
+            self.small
+                .allocate(non_zero_size, non_zero_power_of_two_alignment)
+        } else {
+            self.large
+                .allocate(non_zero_size, non_zero_power_of_two_alignment)
+        }
+    }
+
+    #[inline(always)]
+    fn deallocate(
+        &self,
+        non_zero_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) {
+        if Self::is_small(non_zero_size) {
+            self.small.deallocate(
+                non_zero_size,
+                non_zero_power_of_two_alignment,
+                current_memory,
+            )
+        } else {
+            self.large.deallocate(
+                non_zero_size,
+                non_zero_power_of_two_alignment,
+                current_memory,
+            )
+        }
+    }
+
+    #[inline(always)]
+    fn growing_reallocate(
+        &self,
+        non_zero_new_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+        non_zero_current_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> Result<MemoryAddress, AllocError> {
+        match (
+            Self::is_small(non_zero_current_size),
+            Self::is_small(non_zero_new_size),
+        ) {
+            (true, true) => self.small.growing_reallocate(
+                non_zero_new_size,
+                non_zero_power_of_two_alignment,
+                non_zero_current_size,
+                current_memory,
+            ),
+
+            (false, _) => self.large.growing_reallocate(
+                non_zero_new_size,
+                non_zero_power_of_two_alignment,
+                non_zero_current_size,
+                current_memory,
+            ),
+
+            (true, false) => {
+                let new_memory = self
+                    .large
+                    .allocate(non_zero_new_size, non_zero_power_of_two_alignment)?;
+                Self::copy(current_memory, new_memory, non_zero_current_size);
+                self.small.deallocate(
+                    non_zero_current_size,
+                    non_zero_power_of_two_alignment,
+                    current_memory,
+                );
+                Ok(new_memory)
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn shrinking_reallocate(
+        &self,
+        non_zero_new_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+        non_zero_current_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> Result<MemoryAddress, AllocError> {
+        match (
+            Self::is_small(non_zero_current_size),
+            Self::is_small(non_zero_new_size),
+        ) {
+            (true, _) => self.small.shrinking_reallocate(
+                non_zero_new_size,
+                non_zero_power_of_two_alignment,
+                non_zero_current_size,
+                current_memory,
+            ),
+
+            (false, false) => self.large.shrinking_reallocate(
+                non_zero_new_size,
+                non_zero_power_of_two_alignment,
+                non_zero_current_size,
+                current_memory,
+            ),
+
+            (false, true) => {
+                let new_memory = self
+                    .small
+                    .allocate(non_zero_new_size, non_zero_power_of_two_alignment)?;
+                Self::copy(current_memory, new_memory, non_zero_new_size);
+                self.large.deallocate(
+                    non_zero_current_size,
+                    non_zero_power_of_two_alignment,
+                    current_memory,
+                );
+                Ok(new_memory)
+            }
+        }
+    }
+}
+
+impl<const THRESHOLD: usize, Small: Allocator, Large: Allocator>
+    Segregator<THRESHOLD, Small, Large>
+{
+    /// Creates a new instance.
+    #[inline(always)]
+    pub const fn new(small: Small, large: Large) -> Self {
+        Self { small, large }
+    }
+
+    /// The allocator used for allocations of up to and including `THRESHOLD` bytes.
+    #[inline(always)]
+    pub fn small(&self) -> &Small {
+        &self.small
+    }
+
+    /// The allocator used for allocations of more than `THRESHOLD` bytes.
+    #[inline(always)]
+    pub fn large(&self) -> &Large {
+        &self.large
+    }
+
+    #[inline(always)]
+    fn is_small(non_zero_size: NonZeroUsize) -> bool {
+        non_zero_size.get() <= THRESHOLD
+    }
+
+    #[inline(always)]
+    fn copy(from: MemoryAddress, to: MemoryAddress, non_zero_size: NonZeroUsize) {
+        unsafe {
+            to.as_ptr()
+                .copy_from_nonoverlapping(from.as_ptr(), non_zero_size.get())
+        }
+    }
+}
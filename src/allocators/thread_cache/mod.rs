@@ -0,0 +1,9 @@
+pub mod thread_cache;
+pub mod thread_cache_allocator;
+
+pub mod prelude {
+    pub use super::thread_cache::{
+        MAXIMUM_MAGAZINE_CAPACITY, MAXIMUM_SIZE_CLASS, MINIMUM_SIZE_CLASS,
+    };
+    pub use super::thread_cache_allocator::*;
+}
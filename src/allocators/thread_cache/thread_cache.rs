@@ -0,0 +1,176 @@
+use crate::allocators::global::thread_exit_hook::ThreadExitHook;
+use crate::extensions::prelude::*;
+use crate::memory_address::MemoryAddress;
+use std::ffi::c_void;
+
+/// The largest number of free blocks a thread can cache for each size class.
+pub const MAXIMUM_MAGAZINE_CAPACITY: usize = 64;
+
+/// The smallest size class; smaller allocations are rounded up to it.
+pub const MINIMUM_SIZE_CLASS: usize = 16;
+
+/// The largest size class; larger allocations are never cached.
+pub const MAXIMUM_SIZE_CLASS: usize = 4096;
+
+/// Size classes are the powers of two from `MINIMUM_SIZE_CLASS` to `MAXIMUM_SIZE_CLASS` inclusive.
+pub(crate) const NUMBER_OF_SIZE_CLASSES: usize = 9;
+
+/// A stack of free blocks of one size class.
+#[derive(Copy, Clone)]
+pub(crate) struct Magazine {
+    number_of_blocks: usize,
+
+    /// The fewest blocks held since the last scavenge; these blocks were not needed.
+    low_water_mark: usize,
+
+    blocks: [usize; MAXIMUM_MAGAZINE_CAPACITY],
+}
+
+impl Magazine {
+    const EMPTY: Self = Self {
+        number_of_blocks: 0,
+        low_water_mark: 0,
+        blocks: [0; MAXIMUM_MAGAZINE_CAPACITY],
+    };
+
+    #[inline(always)]
+    pub(crate) fn pop(&mut self) -> Option<MemoryAddress> {
+        if unlikely!(self.number_of_blocks == 0) {
+            return None;
+        }
+
+        self.number_of_blocks -= 1;
+        if self.number_of_blocks < self.low_water_mark {
+            self.low_water_mark = self.number_of_blocks
+        }
+        Some(MemoryAddress::from_usize(
+            self.blocks[self.number_of_blocks],
+        ))
+    }
+
+    /// Returns `false` if the magazine is full.
+    #[inline(always)]
+    pub(crate) fn push(&mut self, magazine_capacity: usize, block: MemoryAddress) -> bool {
+        if unlikely!(self.number_of_blocks >= magazine_capacity) {
+            return false;
+        }
+
+        self.blocks[self.number_of_blocks] = block.to_usize();
+        self.number_of_blocks += 1;
+        true
+    }
+
+    /// Removes up to `number_of_blocks` blocks, passing each to `callback`.
+    #[inline(always)]
+    pub(crate) fn release(
+        &mut self,
+        number_of_blocks: usize,
+        mut callback: impl FnMut(MemoryAddress),
+    ) {
+        let keep = self.number_of_blocks.saturating_sub(number_of_blocks);
+        while self.number_of_blocks > keep {
+            self.number_of_blocks -= 1;
+            callback(MemoryAddress::from_usize(
+                self.blocks[self.number_of_blocks],
+            ))
+        }
+        if self.number_of_blocks < self.low_water_mark {
+            self.low_water_mark = self.number_of_blocks
+        }
+    }
+
+    /// Removes the blocks which were not needed since the last scavenge.
+    #[inline(always)]
+    pub(crate) fn scavenge(&mut self, callback: impl FnMut(MemoryAddress)) {
+        self.release(self.low_water_mark, callback);
+        self.low_water_mark = self.number_of_blocks
+    }
+}
+
+/// The free blocks cached by the current thread for at most one `ThreadCacheAllocator`, its owner.
+///
+/// Owners are identified by a unique id rather than their address, so that an owner created at the address of a dropped owner never adopts its blocks.
+pub(crate) struct ThreadCache {
+    owner: usize,
+    owner_address: usize,
+    flush: Option<unsafe fn(usize, &mut ThreadCache)>,
+    pub(crate) operations_since_scavenge: usize,
+    pub(crate) magazines: [Magazine; NUMBER_OF_SIZE_CLASSES],
+}
+
+/// Each thread has one cache, as thread-local statics can not be generic.
+#[thread_local]
+static mut thread_cache: ThreadCache = ThreadCache {
+    owner: ThreadCache::UNCLAIMED,
+    owner_address: 0,
+    flush: None,
+    operations_since_scavenge: 0,
+    magazines: [Magazine::EMPTY; NUMBER_OF_SIZE_CLASSES],
+};
+
+static THREAD_EXIT_HOOK: ThreadExitHook = ThreadExitHook::new(ThreadCache::thread_exited);
+
+impl ThreadCache {
+    const UNCLAIMED: usize = 0;
+
+    /// Once the thread's cache has been flushed on thread exit, it is never used again.
+    const THREAD_EXITED: usize = ::std::usize::MAX;
+
+    /// The current thread's cache, if owned by `owner`.
+    ///
+    /// The first owner to ask claims the cache; `flush` is called with the `owner_address` when the thread exits.
+    /// Returns `None` if another owner has claimed the cache or the thread is exiting.
+    #[inline(always)]
+    pub(crate) fn for_owner(
+        owner: usize,
+        owner_address: usize,
+        flush: unsafe fn(usize, &mut ThreadCache),
+    ) -> Option<&'static mut ThreadCache> {
+        let this = unsafe { &mut thread_cache };
+        if likely!(this.owner == owner) {
+            return Some(this);
+        }
+
+        if unlikely!(this.owner != Self::UNCLAIMED) {
+            return None;
+        }
+
+        if unlikely!(!THREAD_EXIT_HOOK.register_current_thread()) {
+            return None;
+        }
+
+        this.owner = owner;
+        this.owner_address = owner_address;
+        this.flush = Some(flush);
+        Some(this)
+    }
+
+    /// The current thread's cache, if already claimed by `owner`.
+    #[inline(always)]
+    pub(crate) fn owned_by(owner: usize) -> Option<&'static mut ThreadCache> {
+        let this = unsafe { &mut thread_cache };
+        if this.owner == owner {
+            Some(this)
+        } else {
+            None
+        }
+    }
+
+    /// Stops `owner` using the current thread's cache, which must first have been emptied.
+    #[inline(always)]
+    pub(crate) fn disown(owner: usize) {
+        let this = unsafe { &mut thread_cache };
+        if this.owner == owner {
+            this.owner = Self::UNCLAIMED;
+            this.flush = None;
+        }
+    }
+
+    unsafe extern "C" fn thread_exited(_value: *mut c_void) {
+        let this = &mut thread_cache;
+        if let Some(flush) = this.flush.take() {
+            flush(this.owner_address, this)
+        }
+        this.owner = Self::THREAD_EXITED
+    }
+}
@@ -0,0 +1,399 @@
+use crate::allocators::allocator::Allocator;
+use crate::allocators::thread_cache::thread_cache::*;
+use crate::extensions::prelude::*;
+use crate::memory_address::MemoryAddress;
+use std::alloc::{AllocError, AllocRef, GlobalAlloc, Layout};
+use std::num::NonZeroUsize;
+use std::ptr::NonNull;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::Relaxed;
+
+/// Ids of `ThreadCacheAllocator`s; never `UNASSIGNED_ID`.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+/// A thread cache (tcache) front-end to a thread-safe backing allocator.
+///
+/// Each thread keeps a magazine (stack) of free blocks for each size class (the powers of two from `MINIMUM_SIZE_CLASS` to `MAXIMUM_SIZE_CLASS`); most allocations and deallocations of small blocks are then made without touching the backing allocator or any lock.
+/// This is a middle ground between fully thread local allocators and a shared global allocator.
+///
+/// * When a magazine is full, half of it is returned to the backing allocator.
+/// * Every `scavenge_interval` cached allocations and deallocations made by a thread, blocks its magazines have not needed since the last scavenge are returned to the backing allocator.
+/// * When a thread exits, all of its cached blocks are returned to the backing allocator.
+///
+/// The backing allocator must be thread-safe, eg a `LockedAllocator`, a `PerCpuAllocator` or `GlobalAllocToAllocatorAdaptor<System>`.
+///
+/// Intended to be used in a `static`, eg as the `$GlobalAllocator` of `switchable_allocator!` or as a `#[global_allocator]`.
+/// An instance must be `'static` in effect: once any thread has used it, it must be neither moved nor dropped whilst that thread is alive, as the thread's cached blocks are returned to it when the thread exits.
+/// Dropping it only returns the current thread's cached blocks; those of other threads are never handed to another instance, even one later created at the same address, as each instance has a unique id.
+/// Each thread only caches blocks for the first instance it uses; other instances pass through to their backing allocator.
+///
+/// Only on Unix.
+#[derive(Debug)]
+pub struct ThreadCacheAllocator<A: Allocator> {
+    backing_allocator: A,
+    magazine_capacity: usize,
+    maximum_cached_size: usize,
+    scavenge_interval: usize,
+
+    /// Assigned on first use, as `new()` is `const`; zero until then.
+    id: AtomicUsize,
+}
+
+impl<A: Allocator> Drop for ThreadCacheAllocator<A> {
+    #[inline(always)]
+    fn drop(&mut self) {
+        self.flush();
+        ThreadCache::disown(self.owner())
+    }
+}
+
+unsafe impl<A: Allocator> GlobalAlloc for ThreadCacheAllocator<A> {
+    crate::global_alloc!();
+}
+
+unsafe impl<A: Allocator> AllocRef for ThreadCacheAllocator<A> {
+    crate::alloc_ref!();
+}
+
+impl<A: Allocator> Allocator for ThreadCacheAllocator<A> {
+    #[inline(always)]
+    fn allocate(
+        &self,
+        non_zero_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+    ) -> Result<MemoryAddress, AllocError> {
+        let size_class_index =
+            match self.size_class_index(non_zero_size, non_zero_power_of_two_alignment) {
+                None => {
+                    return self
+                        .backing_allocator
+                        .allocate(non_zero_size, non_zero_power_of_two_alignment)
+                }
+                Some(size_class_index) => size_class_index,
+            };
+
+        if let Some(thread_cache) = self.thread_cache() {
+            let cached = thread_cache.magazines[size_class_index].pop();
+            self.scavenge_if_due(thread_cache);
+            if let Some(block) = cached {
+                return Ok(block);
+            }
+        }
+
+        let size_class = Self::size_class(size_class_index);
+        self.backing_allocator.allocate(size_class, size_class)
+    }
+
+    #[inline(always)]
+    fn deallocate(
+        &self,
+        non_zero_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) {
+        let size_class_index =
+            match self.size_class_index(non_zero_size, non_zero_power_of_two_alignment) {
+                None => {
+                    return self.backing_allocator.deallocate(
+                        non_zero_size,
+                        non_zero_power_of_two_alignment,
+                        current_memory,
+                    )
+                }
+                Some(size_class_index) => size_class_index,
+            };
+
+        let size_class = Self::size_class(size_class_index);
+        if let Some(thread_cache) = self.thread_cache() {
+            let magazine_capacity = self.magazine_capacity();
+            let magazine = &mut thread_cache.magazines[size_class_index];
+
+            let mut cached = magazine.push(magazine_capacity, current_memory);
+            if unlikely!(!cached) {
+                magazine.release(magazine_capacity / 2, |block| {
+                    self.backing_allocator
+                        .deallocate(size_class, size_class, block)
+                });
+                cached = magazine.push(magazine_capacity, current_memory);
+            }
+
+            self.scavenge_if_due(thread_cache);
+            if likely!(cached) {
+                return;
+            }
+        }
+
+        self.backing_allocator
+            .deallocate(size_class, size_class, current_memory)
+    }
+
+    #[inline(always)]
+    fn growing_reallocate(
+        &self,
+        non_zero_new_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+        non_zero_current_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> Result<MemoryAddress, AllocError> {
+        let current_size_class_index =
+            self.size_class_index(non_zero_current_size, non_zero_power_of_two_alignment);
+        let new_size_class_index =
+            self.size_class_index(non_zero_new_size, non_zero_power_of_two_alignment);
+
+        match (current_size_class_index, new_size_class_index) {
+            (None, None) => self.backing_allocator.growing_reallocate(
+                non_zero_new_size,
+                non_zero_power_of_two_alignment,
+                non_zero_current_size,
+                current_memory,
+            ),
+
+            (Some(current), Some(new)) if current == new => Ok(current_memory),
+
+            _ => self.move_between_size_classes(
+                non_zero_new_size,
+                non_zero_power_of_two_alignment,
+                non_zero_current_size,
+                current_memory,
+                non_zero_current_size,
+            ),
+        }
+    }
+
+    #[inline(always)]
+    fn shrinking_reallocate(
+        &self,
+        non_zero_new_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+        non_zero_current_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> Result<MemoryAddress, AllocError> {
+        let current_size_class_index =
+            self.size_class_index(non_zero_current_size, non_zero_power_of_two_alignment);
+        let new_size_class_index =
+            self.size_class_index(non_zero_new_size, non_zero_power_of_two_alignment);
+
+        match (current_size_class_index, new_size_class_index) {
+            (None, None) => self.backing_allocator.shrinking_reallocate(
+                non_zero_new_size,
+                non_zero_power_of_two_alignment,
+                non_zero_current_size,
+                current_memory,
+            ),
+
+            (Some(current), Some(new)) if current == new => Ok(current_memory),
+
+            _ => self.move_between_size_classes(
+                non_zero_new_size,
+                non_zero_power_of_two_alignment,
+                non_zero_current_size,
+                current_memory,
+                non_zero_new_size,
+            ),
+        }
+    }
+}
+
+impl<A: Allocator> ThreadCacheAllocator<A> {
+    /// The default number of blocks a thread caches for each size class.
+    pub const DEFAULT_MAGAZINE_CAPACITY: usize = 32;
+
+    /// The default number of cached allocations and deallocations a thread makes between scavenges.
+    pub const DEFAULT_SCAVENGE_INTERVAL: usize = 4096;
+
+    const SIZE_CLASS_SHIFT: u32 = MINIMUM_SIZE_CLASS.trailing_zeros();
+
+    const UNASSIGNED_ID: usize = 0;
+
+    /// Create a new instance.
+    ///
+    /// * `backing_allocator`: A thread-safe allocator to obtain blocks from and return blocks to.
+    /// * `magazine_capacity`: The number of blocks a thread caches for each size class; at most `MAXIMUM_MAGAZINE_CAPACITY`. Zero disables caching.
+    /// * `maximum_cached_size`: Allocations larger than this (after rounding up to a power of two) are passed to `backing_allocator`; at most `MAXIMUM_SIZE_CLASS`.
+    /// * `scavenge_interval`: The number of cached allocations and deallocations a thread makes between scavenges. Zero disables scavenging.
+    ///
+    /// Usable in `const` and `static` contexts.
+    #[inline(always)]
+    pub const fn new(
+        backing_allocator: A,
+        magazine_capacity: usize,
+        maximum_cached_size: usize,
+        scavenge_interval: usize,
+    ) -> Self {
+        Self {
+            backing_allocator,
+            magazine_capacity,
+            maximum_cached_size,
+            scavenge_interval,
+            id: AtomicUsize::new(Self::UNASSIGNED_ID),
+        }
+    }
+
+    /// Create a new instance with `DEFAULT_MAGAZINE_CAPACITY`, caching all size classes and with `DEFAULT_SCAVENGE_INTERVAL`.
+    ///
+    /// Usable in `const` and `static` contexts.
+    #[inline(always)]
+    pub const fn with_defaults(backing_allocator: A) -> Self {
+        Self::new(
+            backing_allocator,
+            Self::DEFAULT_MAGAZINE_CAPACITY,
+            MAXIMUM_SIZE_CLASS,
+            Self::DEFAULT_SCAVENGE_INTERVAL,
+        )
+    }
+
+    /// The backing allocator.
+    #[inline(always)]
+    pub fn backing_allocator(&self) -> &A {
+        &self.backing_allocator
+    }
+
+    /// Returns all blocks cached by the current thread to the backing allocator.
+    ///
+    /// Other threads' caches are unaffected.
+    #[inline(always)]
+    pub fn flush(&self) {
+        if let Some(thread_cache) = ThreadCache::owned_by(self.owner()) {
+            self.release_all(thread_cache)
+        }
+    }
+
+    /// Returns blocks cached by the current thread which have not been needed since the last scavenge to the backing allocator.
+    #[inline(always)]
+    pub fn scavenge(&self) {
+        if let Some(thread_cache) = ThreadCache::owned_by(self.owner()) {
+            self.scavenge_thread_cache(thread_cache)
+        }
+    }
+
+    #[inline(always)]
+    fn move_between_size_classes(
+        &self,
+        non_zero_new_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+        non_zero_current_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+        non_zero_copy_size: NonZeroUsize,
+    ) -> Result<MemoryAddress, AllocError> {
+        let new_memory = self.allocate(non_zero_new_size, non_zero_power_of_two_alignment)?;
+        unsafe {
+            new_memory
+                .as_ptr()
+                .copy_from_nonoverlapping(current_memory.as_ptr(), non_zero_copy_size.get())
+        };
+        self.deallocate(
+            non_zero_current_size,
+            non_zero_power_of_two_alignment,
+            current_memory,
+        );
+        Ok(new_memory)
+    }
+
+    #[inline(always)]
+    fn thread_cache(&self) -> Option<&'static mut ThreadCache> {
+        ThreadCache::for_owner(
+            self.owner(),
+            self as *const Self as usize,
+            Self::flush_on_thread_exit,
+        )
+    }
+
+    #[inline(always)]
+    fn owner(&self) -> usize {
+        let id = self.id.load(Relaxed);
+        if likely!(id != Self::UNASSIGNED_ID) {
+            id
+        } else {
+            self.assign_id()
+        }
+    }
+
+    #[cold]
+    fn assign_id(&self) -> usize {
+        let id = NEXT_ID.fetch_add(1, Relaxed);
+        match self
+            .id
+            .compare_exchange(Self::UNASSIGNED_ID, id, Relaxed, Relaxed)
+        {
+            Ok(_) => id,
+
+            Err(assigned_by_another_thread) => assigned_by_another_thread,
+        }
+    }
+
+    /// Called with the address of the thread cache's owner as the thread exits.
+    unsafe fn flush_on_thread_exit(owner_address: usize, thread_cache: &mut ThreadCache) {
+        let this = &*(owner_address as *const Self);
+        this.release_all(thread_cache)
+    }
+
+    #[inline(always)]
+    fn release_all(&self, thread_cache: &mut ThreadCache) {
+        for (size_class_index, magazine) in thread_cache.magazines.iter_mut().enumerate() {
+            let size_class = Self::size_class(size_class_index);
+            magazine.release(MAXIMUM_MAGAZINE_CAPACITY, |block| {
+                self.backing_allocator
+                    .deallocate(size_class, size_class, block)
+            })
+        }
+    }
+
+    #[inline(always)]
+    fn scavenge_if_due(&self, thread_cache: &mut ThreadCache) {
+        if unlikely!(self.scavenge_interval == 0) {
+            return;
+        }
+
+        thread_cache.operations_since_scavenge += 1;
+        if unlikely!(thread_cache.operations_since_scavenge >= self.scavenge_interval) {
+            self.scavenge_thread_cache(thread_cache)
+        }
+    }
+
+    #[cold]
+    fn scavenge_thread_cache(&self, thread_cache: &mut ThreadCache) {
+        thread_cache.operations_since_scavenge = 0;
+        for (size_class_index, magazine) in thread_cache.magazines.iter_mut().enumerate() {
+            let size_class = Self::size_class(size_class_index);
+            magazine.scavenge(|block| {
+                self.backing_allocator
+                    .deallocate(size_class, size_class, block)
+            })
+        }
+    }
+
+    #[inline(always)]
+    fn magazine_capacity(&self) -> usize {
+        self.magazine_capacity.min(MAXIMUM_MAGAZINE_CAPACITY)
+    }
+
+    /// Blocks are allocated from the backing allocator with an alignment equal to their size class, so a block can satisfy any alignment up to its size class.
+    #[inline(always)]
+    fn size_class_index(
+        &self,
+        non_zero_size: NonZeroUsize,
+        non_zero_power_of_two_alignment: NonZeroUsize,
+    ) -> Option<usize> {
+        let size = non_zero_size
+            .get()
+            .max(non_zero_power_of_two_alignment.get())
+            .max(MINIMUM_SIZE_CLASS);
+
+        let maximum_cached_size = self.maximum_cached_size.min(MAXIMUM_SIZE_CLASS);
+        if unlikely!(size > maximum_cached_size) {
+            return None;
+        }
+
+        let size_class = size.next_power_of_two();
+        if unlikely!(size_class > maximum_cached_size) {
+            return None;
+        }
+
+        Some((size_class.trailing_zeros() - Self::SIZE_CLASS_SHIFT) as usize)
+    }
+
+    #[inline(always)]
+    fn size_class(size_class_index: usize) -> NonZeroUsize {
+        (MINIMUM_SIZE_CLASS << size_class_index).non_zero()
+    }
+}
@@ -6,6 +6,8 @@
 #![feature(const_fn)]
 #![feature(llvm_asm)]
 #![feature(nonnull_slice_from_raw_parts)]
+#![feature(const_in_array_repeat_expressions)]
+#![feature(min_const_generics)]
 
 /// Path prediction macros for likely/unlikely intrinsics
 #[macro_use]
@@ -0,0 +1,89 @@
+use crate::allocators::global::global_switchable_allocator::GlobalSwitchableAllocator;
+use crate::allocators::global::memory_range::MemoryRange;
+use crate::memory_address::MemoryAddress;
+use crate::memory_sources::memory_source::MemorySource;
+use std::alloc::AllocError;
+use std::num::NonZeroUsize;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// Represents an Atomically Reference-counted (ARC) memory source.
+///
+/// The thread-safe counterpart of `RcMemorySource`: useful when passing in one thread-safe memory source, such as a `MemoryMapSource`, a `ConcurrentArenaMemorySource` or a `LockedMemorySource`, to the allocators of many threads.
+#[derive(Debug)]
+pub struct ArcMemorySource<MS: MemorySource + Sync>(Arc<MS>);
+
+impl<MS: MemorySource + Sync> Clone for ArcMemorySource<MS> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<MS: MemorySource + Sync> Deref for ArcMemorySource<MS> {
+    type Target = MS;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<MS: MemorySource + Sync> MemorySource for ArcMemorySource<MS> {
+    #[inline(always)]
+    fn obtain(&self, non_zero_size: NonZeroUsize) -> Result<MemoryAddress, AllocError> {
+        self.0.obtain(non_zero_size)
+    }
+
+    #[inline(always)]
+    fn release(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        self.0.release(non_zero_size, current_memory)
+    }
+
+    #[inline(always)]
+    fn decommit(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        self.0.decommit(non_zero_size, current_memory)
+    }
+
+    #[inline(always)]
+    fn recommit(
+        &self,
+        non_zero_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> Result<(), AllocError> {
+        self.0.recommit(non_zero_size, current_memory)
+    }
+
+    #[inline(always)]
+    fn extend(
+        &self,
+        current_memory: MemoryAddress,
+        non_zero_current_size: NonZeroUsize,
+        non_zero_new_size: NonZeroUsize,
+    ) -> Result<(), AllocError> {
+        self.0
+            .extend(current_memory, non_zero_current_size, non_zero_new_size)
+    }
+
+    #[inline(always)]
+    fn reservation(&self) -> Option<MemoryRange> {
+        self.0.reservation()
+    }
+}
+
+impl<MS: MemorySource + Sync> ArcMemorySource<MS> {
+    /// Creates a new instance, allocating from the current allocator in use.
+    #[inline(always)]
+    pub fn new(underlying_memory_source: MS) -> Self {
+        Self(Arc::new(underlying_memory_source))
+    }
+
+    /// Creates a new instance, allocating from the global allocator, so that it can be shared by, and outlive, the thread local and coroutine local allocators of any thread.
+    #[inline(always)]
+    pub fn new_global<GTACSA: GlobalSwitchableAllocator>(
+        global_allocator: &GTACSA,
+        underlying_memory_source: MS,
+    ) -> Self {
+        Self(global_allocator.callback_with_global_allocator(|| Arc::new(underlying_memory_source)))
+    }
+}
@@ -0,0 +1,142 @@
+use crate::extensions::non_null_u8_ext::NonNullU8Ext;
+use crate::extensions::non_zero_usize_ext::NonZeroUsizeExt;
+use crate::extensions::usize_ext::UsizeExt;
+use crate::memory_address::MemoryAddress;
+use crate::memory_sources::arena_memory_source::slot_index::SlotIndex;
+use crate::memory_sources::arena_memory_source::unallocated_block::UnallocatedBlock;
+use crate::memory_sources::memory_source::MemorySource;
+use std::alloc::AllocError;
+use std::num::NonZeroUsize;
+
+/// The fixed-size blocks of an arena, and the arithmetic between them and their slot indices, shared by `ArenaMemorySource` and `ConcurrentArenaMemorySource`.
+///
+/// When created, each block holds the slot index of the next, so the blocks form a singly-linked list of free blocks starting at slot index 0; how its head is kept is left to the arena.
+///
+/// The memory is released when dropped.
+#[derive(Debug)]
+pub(crate) struct ArenaBlocks<MS: MemorySource> {
+    block_size: NonZeroUsize,
+    #[cfg(debug_assertions)]
+    number_of_blocks: NonZeroUsize,
+
+    memory_source: MS,
+    allocations_start_from: MemoryAddress,
+    memory_source_size: NonZeroUsize,
+}
+
+impl<MS: MemorySource> Drop for ArenaBlocks<MS> {
+    #[inline(always)]
+    fn drop(&mut self) {
+        self.memory_source
+            .release(self.memory_source_size, self.allocations_start_from)
+    }
+}
+
+impl<MS: MemorySource> ArenaBlocks<MS> {
+    /// Number of blocks of `block_size` needed to cover `memory_source_size`.
+    #[inline(always)]
+    pub(crate) fn number_of_blocks_for_amount(
+        block_size: NonZeroUsize,
+        memory_source_size: NonZeroUsize,
+    ) -> NonZeroUsize {
+        ((memory_source_size.get() + (block_size.get() - 1)) / block_size.get()).non_zero()
+    }
+
+    /// Obtains the memory for the blocks and links them.
+    #[inline(always)]
+    pub(crate) fn new(
+        memory_source: MS,
+        block_size: NonZeroUsize,
+        number_of_blocks: NonZeroUsize,
+        block_initializer: impl Fn(MemoryAddress, NonZeroUsize),
+    ) -> Result<Self, AllocError> {
+        let memory_source_size = block_size.multiply(number_of_blocks);
+
+        let allocations_start_from = memory_source.obtain(memory_source_size)?;
+
+        Self::initialize_blocks_so_they_are_a_singly_linked_list(
+            block_size,
+            block_initializer,
+            memory_source_size,
+            allocations_start_from,
+        );
+
+        Ok(Self {
+            block_size,
+            #[cfg(debug_assertions)]
+            number_of_blocks,
+
+            memory_source,
+            allocations_start_from,
+            memory_source_size,
+        })
+    }
+
+    #[inline(always)]
+    pub(crate) fn block_size(&self) -> NonZeroUsize {
+        self.block_size
+    }
+
+    #[inline(always)]
+    pub(crate) fn memory_source(&self) -> &MS {
+        &self.memory_source
+    }
+
+    #[inline(always)]
+    pub(crate) fn unallocated_block(&self, slot_index: SlotIndex) -> &UnallocatedBlock {
+        UnallocatedBlock::from_memory_address(self.block_from_slot_index(slot_index))
+    }
+
+    #[inline(always)]
+    pub(crate) fn slot_index_from_block(&self, unallocated_block: &UnallocatedBlock) -> SlotIndex {
+        SlotIndex(
+            unallocated_block
+                .to_memory_address()
+                .difference(self.allocations_start_from)
+                / self.block_size.get(),
+        )
+    }
+
+    #[inline(always)]
+    fn block_from_slot_index(&self, slot_index: SlotIndex) -> MemoryAddress {
+        debug_assert_ne!(
+            slot_index,
+            SlotIndex::IS_FULLY_ALLOCATED_NEXT_AVAILABLE_SLOT_INDEX_SENTINEL,
+            "Should never get IsFullyAllocatedNextAvailableSlotIndexSentinel for `slot_index`"
+        );
+        #[cfg(debug_assertions)]
+        debug_assert!(
+            slot_index.0 < self.number_of_blocks.get(),
+            "slot_index `{:?}` is out of range",
+            slot_index
+        );
+
+        self.allocations_start_from
+            .add(self.block_size.get() * slot_index.0)
+    }
+
+    #[inline(always)]
+    fn initialize_blocks_so_they_are_a_singly_linked_list(
+        block_size: NonZeroUsize,
+        block_initializer: impl Fn(MemoryAddress, NonZeroUsize),
+        memory_source_size: NonZeroUsize,
+        allocations_start_from: MemoryAddress,
+    ) {
+        let mut slot_index = SlotIndex(1);
+        let mut block_memory_address = allocations_start_from;
+        let allocations_end_at = allocations_start_from.add_non_zero(memory_source_size);
+        let allocations_end_at_less_one_block = allocations_end_at.subtract_non_zero(block_size);
+        while block_memory_address != allocations_end_at_less_one_block {
+            let unallocated_block = UnallocatedBlock::from_memory_address(block_memory_address);
+            unallocated_block.initialize(block_size, &block_initializer, slot_index);
+
+            slot_index.increment();
+            block_memory_address.add_assign_non_zero(block_size)
+        }
+        UnallocatedBlock::from_memory_address(allocations_end_at_less_one_block).initialize(
+            block_size,
+            &block_initializer,
+            SlotIndex::IS_FULLY_ALLOCATED_NEXT_AVAILABLE_SLOT_INDEX_SENTINEL,
+        );
+    }
+}
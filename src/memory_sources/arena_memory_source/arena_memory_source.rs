@@ -1,7 +1,5 @@
-use crate::extensions::non_null_u8_ext::NonNullU8Ext;
-use crate::extensions::non_zero_usize_ext::NonZeroUsizeExt;
-use crate::extensions::usize_ext::UsizeExt;
 use crate::memory_address::MemoryAddress;
+use crate::memory_sources::arena_memory_source::arena_blocks::ArenaBlocks;
 use crate::memory_sources::arena_memory_source::slot_index::SlotIndex;
 use crate::memory_sources::arena_memory_source::unallocated_block::UnallocatedBlock;
 use crate::memory_sources::memory_source::MemorySource;
@@ -14,27 +12,13 @@ use std::num::NonZeroUsize;
 pub struct ArenaMemorySource<MS: MemorySource> {
     next_available_slot_index: Cell<SlotIndex>,
 
-    block_size: NonZeroUsize,
-    #[cfg(debug_assertions)]
-    number_of_blocks: NonZeroUsize,
-
-    memory_source: MS,
-    allocations_start_from: MemoryAddress,
-    memory_source_size: NonZeroUsize,
-}
-
-impl<MS: MemorySource> Drop for ArenaMemorySource<MS> {
-    #[inline(always)]
-    fn drop(&mut self) {
-        self.memory_source
-            .release(self.memory_source_size, self.allocations_start_from)
-    }
+    blocks: ArenaBlocks<MS>,
 }
 
 impl<MS: MemorySource> MemorySource for ArenaMemorySource<MS> {
     #[inline(always)]
     fn obtain(&self, non_zero_size: NonZeroUsize) -> Result<MemoryAddress, AllocError> {
-        debug_assert!(non_zero_size <= self.block_size);
+        debug_assert!(non_zero_size <= self.blocks.block_size());
 
         let next_available_slot_index = self.next_available_slot_index.get();
 
@@ -42,7 +26,7 @@ impl<MS: MemorySource> MemorySource for ArenaMemorySource<MS> {
             return Err(AllocError);
         }
 
-        let unallocated_block = self.unallocated_block(next_available_slot_index);
+        let unallocated_block = self.blocks.unallocated_block(next_available_slot_index);
         self.next_available_slot_index
             .set(unallocated_block.next_available_slot_index());
 
@@ -51,14 +35,32 @@ impl<MS: MemorySource> MemorySource for ArenaMemorySource<MS> {
 
     #[inline(always)]
     fn release(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
-        debug_assert!(non_zero_size <= self.block_size);
+        debug_assert!(non_zero_size <= self.blocks.block_size());
 
         let unallocated_block = UnallocatedBlock::from_memory_address(current_memory);
         unallocated_block
             .set_unoccupied_next_available_slot_index(self.next_available_slot_index.get());
 
         self.next_available_slot_index
-            .set(self.slot_index_from_block(unallocated_block));
+            .set(self.blocks.slot_index_from_block(unallocated_block));
+    }
+
+    #[inline(always)]
+    fn decommit(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        self.blocks
+            .memory_source()
+            .decommit(non_zero_size, current_memory)
+    }
+
+    #[inline(always)]
+    fn recommit(
+        &self,
+        non_zero_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> Result<(), AllocError> {
+        self.blocks
+            .memory_source()
+            .recommit(non_zero_size, current_memory)
     }
 }
 
@@ -71,13 +73,10 @@ impl<MS: MemorySource> ArenaMemorySource<MS> {
         memory_source_size: NonZeroUsize,
         block_initializer: impl Fn(MemoryAddress, NonZeroUsize),
     ) -> Result<Self, AllocError> {
-        let number_of_blocks =
-            ((memory_source_size.get() + (block_size.get() - 1)) / block_size.get()).non_zero();
-
         Self::new(
             memory_source,
             block_size,
-            number_of_blocks,
+            ArenaBlocks::<MS>::number_of_blocks_for_amount(block_size, memory_source_size),
             block_initializer,
         )
     }
@@ -93,79 +92,15 @@ impl<MS: MemorySource> ArenaMemorySource<MS> {
         number_of_blocks: NonZeroUsize,
         block_initializer: impl Fn(MemoryAddress, NonZeroUsize),
     ) -> Result<Self, AllocError> {
-        let memory_source_size = block_size.multiply(number_of_blocks);
-
-        let allocations_start_from = memory_source.obtain(memory_source_size)?;
-
-        Self::initialize_blocks_so_they_are_a_singly_linked_list(
-            block_size,
-            block_initializer,
-            memory_source_size,
-            allocations_start_from,
-        );
-
         Ok(Self {
             next_available_slot_index: Cell::default(),
 
-            block_size,
-            #[cfg(debug_assertions)]
-            number_of_blocks,
-
-            memory_source,
-            allocations_start_from,
-            memory_source_size,
+            blocks: ArenaBlocks::new(
+                memory_source,
+                block_size,
+                number_of_blocks,
+                block_initializer,
+            )?,
         })
     }
-
-    #[inline(always)]
-    fn initialize_blocks_so_they_are_a_singly_linked_list(
-        block_size: NonZeroUsize,
-        block_initializer: impl Fn(MemoryAddress, NonZeroUsize),
-        memory_source_size: NonZeroUsize,
-        allocations_start_from: MemoryAddress,
-    ) {
-        let mut slot_index = SlotIndex(1);
-        let mut block_memory_address = allocations_start_from;
-        let allocations_end_at = allocations_start_from.add_non_zero(memory_source_size);
-        let allocations_end_at_less_one_block = allocations_end_at.subtract_non_zero(block_size);
-        while block_memory_address != allocations_end_at_less_one_block {
-            let unallocated_block = UnallocatedBlock::from_memory_address(block_memory_address);
-            unallocated_block.initialize(block_size, &block_initializer, slot_index);
-
-            slot_index.increment();
-            block_memory_address.add_assign_non_zero(block_size)
-        }
-        UnallocatedBlock::from_memory_address(allocations_end_at_less_one_block).initialize(
-            block_size,
-            &block_initializer,
-            SlotIndex::IS_FULLY_ALLOCATED_NEXT_AVAILABLE_SLOT_INDEX_SENTINEL,
-        );
-    }
-
-    #[inline(always)]
-    fn unallocated_block(&self, slot_index: SlotIndex) -> &UnallocatedBlock {
-        UnallocatedBlock::from_memory_address(self.block_from_slot_index(slot_index))
-    }
-
-    #[inline(always)]
-    fn block_from_slot_index(&self, slot_index: SlotIndex) -> MemoryAddress {
-        debug_assert_ne!(
-            slot_index,
-            SlotIndex::IS_FULLY_ALLOCATED_NEXT_AVAILABLE_SLOT_INDEX_SENTINEL,
-            "Should never get IsFullyAllocatedNextAvailableSlotIndexSentinel for `slot_index`"
-        );
-
-        self.allocations_start_from
-            .add(self.block_size.get() * slot_index.0)
-    }
-
-    #[inline(always)]
-    fn slot_index_from_block(&self, unallocated_block: &UnallocatedBlock) -> SlotIndex {
-        SlotIndex(
-            unallocated_block
-                .to_memory_address()
-                .difference(self.allocations_start_from)
-                / self.block_size.get(),
-        )
-    }
 }
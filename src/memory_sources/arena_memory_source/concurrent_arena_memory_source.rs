@@ -0,0 +1,154 @@
+use crate::memory_address::MemoryAddress;
+use crate::memory_sources::arena_memory_source::arena_blocks::ArenaBlocks;
+use crate::memory_sources::arena_memory_source::slot_index::SlotIndex;
+use crate::memory_sources::arena_memory_source::tagged_slot_index::TaggedSlotIndex;
+use crate::memory_sources::arena_memory_source::unallocated_block::UnallocatedBlock;
+use crate::memory_sources::memory_source::MemorySource;
+use std::alloc::AllocError;
+use std::num::NonZeroUsize;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+
+/// A thread-safe arena memory source.
+///
+/// Behaves like `ArenaMemorySource`, but the singly-linked list of free blocks has a lock-free, tagged head, so that many threads can obtain and release fixed-size blocks from one arena; eg to pool coroutine arenas across the worker threads of an executor.
+///
+/// The number of blocks is limited to `TaggedSlotIndex::MAXIMUM_NUMBER_OF_SLOTS`.
+#[derive(Debug)]
+pub struct ConcurrentArenaMemorySource<MS: MemorySource> {
+    next_available_slot_index: AtomicU64,
+
+    blocks: ArenaBlocks<MS>,
+}
+
+unsafe impl<MS: MemorySource + Send> Send for ConcurrentArenaMemorySource<MS> {}
+
+unsafe impl<MS: MemorySource + Sync> Sync for ConcurrentArenaMemorySource<MS> {}
+
+impl<MS: MemorySource> MemorySource for ConcurrentArenaMemorySource<MS> {
+    #[inline(always)]
+    fn obtain(&self, non_zero_size: NonZeroUsize) -> Result<MemoryAddress, AllocError> {
+        debug_assert!(non_zero_size <= self.blocks.block_size());
+
+        let mut next_available_slot_index =
+            TaggedSlotIndex(self.next_available_slot_index.load(Acquire));
+        loop {
+            let slot_index = next_available_slot_index.slot_index();
+            if unlikely!(slot_index.is_fully_allocated()) {
+                return Err(AllocError);
+            }
+
+            // If another thread obtains this block before us, this value may be garbage; the compare-and-swap below will then fail because the tag has changed.
+            let unallocated_block = self.blocks.unallocated_block(slot_index);
+            let following_slot_index = SlotIndex(
+                unallocated_block
+                    .atomic_next_available_slot_index()
+                    .load(Relaxed),
+            );
+
+            match self.next_available_slot_index.compare_exchange_weak(
+                next_available_slot_index.0,
+                next_available_slot_index.replace(following_slot_index).0,
+                Acquire,
+                Acquire,
+            ) {
+                Ok(_) => return Ok(unallocated_block.to_memory_address()),
+
+                Err(was) => next_available_slot_index = TaggedSlotIndex(was),
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn release(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        debug_assert!(non_zero_size <= self.blocks.block_size());
+
+        let unallocated_block = UnallocatedBlock::from_memory_address(current_memory);
+        let slot_index = self.blocks.slot_index_from_block(unallocated_block);
+
+        let mut next_available_slot_index =
+            TaggedSlotIndex(self.next_available_slot_index.load(Relaxed));
+        loop {
+            unallocated_block
+                .atomic_next_available_slot_index()
+                .store(next_available_slot_index.slot_index().0, Relaxed);
+
+            match self.next_available_slot_index.compare_exchange_weak(
+                next_available_slot_index.0,
+                next_available_slot_index.replace(slot_index).0,
+                Release,
+                Relaxed,
+            ) {
+                Ok(_) => return,
+
+                Err(was) => next_available_slot_index = TaggedSlotIndex(was),
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn decommit(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        self.blocks
+            .memory_source()
+            .decommit(non_zero_size, current_memory)
+    }
+
+    #[inline(always)]
+    fn recommit(
+        &self,
+        non_zero_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> Result<(), AllocError> {
+        self.blocks
+            .memory_source()
+            .recommit(non_zero_size, current_memory)
+    }
+}
+
+impl<MS: MemorySource> ConcurrentArenaMemorySource<MS> {
+    /// Create a new instance by memory size and block size.
+    #[inline(always)]
+    pub fn new_by_amount(
+        memory_source: MS,
+        block_size: NonZeroUsize,
+        memory_source_size: NonZeroUsize,
+        block_initializer: impl Fn(MemoryAddress, NonZeroUsize),
+    ) -> Result<Self, AllocError> {
+        Self::new(
+            memory_source,
+            block_size,
+            ArenaBlocks::<MS>::number_of_blocks_for_amount(block_size, memory_source_size),
+            block_initializer,
+        )
+    }
+
+    /// Creates a new instance.
+    ///
+    /// `block_size` must be at least 8 to be useful.
+    /// `number_of_blocks` must be less than `TaggedSlotIndex::MAXIMUM_NUMBER_OF_SLOTS`.
+    /// `block_initializer` takes the address of a block and the size of a block; after it is called, the block will have the first 8 bytes (4 bytes on 32-bit platforms) overwritten with a slot index pointer.
+    #[inline(always)]
+    pub fn new(
+        memory_source: MS,
+        block_size: NonZeroUsize,
+        number_of_blocks: NonZeroUsize,
+        block_initializer: impl Fn(MemoryAddress, NonZeroUsize),
+    ) -> Result<Self, AllocError> {
+        if unlikely!(number_of_blocks.get() >= TaggedSlotIndex::MAXIMUM_NUMBER_OF_SLOTS) {
+            return Err(AllocError);
+        }
+
+        Ok(Self {
+            next_available_slot_index: AtomicU64::new(
+                TaggedSlotIndex::default().replace(SlotIndex::default()).0,
+            ),
+
+            blocks: ArenaBlocks::new(
+                memory_source,
+                block_size,
+                number_of_blocks,
+                block_initializer,
+            )?,
+        })
+    }
+}
@@ -0,0 +1,45 @@
+use crate::memory_sources::arena_memory_source::slot_index::SlotIndex;
+
+/// A slot index combined with a tag which is changed on every update, so that a compare-and-swap can detect the ABA problem.
+///
+/// The slot index is held in the lower 32 bits and the tag in the upper 32 bits.
+#[derive(Default, Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub struct TaggedSlotIndex(pub u64);
+
+impl TaggedSlotIndex {
+    /// The maximum number of slots that can be represented.
+    pub const MAXIMUM_NUMBER_OF_SLOTS: usize = Self::IS_FULLY_ALLOCATED as usize;
+
+    const IS_FULLY_ALLOCATED: u32 = ::std::u32::MAX;
+
+    const TAG_SHIFT: u64 = 32;
+
+    /// The slot index.
+    #[inline(always)]
+    pub fn slot_index(self) -> SlotIndex {
+        let slot_index = self.0 as u32;
+        if unlikely!(slot_index == Self::IS_FULLY_ALLOCATED) {
+            SlotIndex::IS_FULLY_ALLOCATED_NEXT_AVAILABLE_SLOT_INDEX_SENTINEL
+        } else {
+            SlotIndex(slot_index as usize)
+        }
+    }
+
+    /// Replaces the slot index and changes the tag.
+    #[inline(always)]
+    pub fn replace(self, slot_index: SlotIndex) -> Self {
+        let slot_index = if unlikely!(slot_index.is_fully_allocated()) {
+            Self::IS_FULLY_ALLOCATED
+        } else {
+            debug_assert!(
+                slot_index.0 < Self::MAXIMUM_NUMBER_OF_SLOTS,
+                "slot_index `{:?}` can not be tagged",
+                slot_index
+            );
+            slot_index.0 as u32
+        };
+
+        let tag = ((self.0 >> Self::TAG_SHIFT) as u32).wrapping_add(1);
+        Self(((tag as u64) << Self::TAG_SHIFT) | (slot_index as u64))
+    }
+}
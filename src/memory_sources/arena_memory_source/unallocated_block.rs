@@ -4,6 +4,7 @@ use crate::memory_sources::arena_memory_source::slot_index::SlotIndex;
 use crate::memory_sources::arena_memory_source::unsized_block::Unsized;
 use std::cell::Cell;
 use std::num::NonZeroUsize;
+use std::sync::atomic::AtomicUsize;
 
 #[repr(C)]
 pub struct UnallocatedBlock {
@@ -33,6 +34,12 @@ impl UnallocatedBlock {
         self.next_available_slot_index.set(slot_index)
     }
 
+    /// Used when the block is shared between threads; overlays the same memory as `next_available_slot_index`.
+    #[inline(always)]
+    pub(crate) fn atomic_next_available_slot_index(&self) -> &AtomicUsize {
+        unsafe { &*(self as *const Self as *const AtomicUsize) }
+    }
+
     #[inline(always)]
     pub(crate) fn from_memory_address<'a>(memory_address: MemoryAddress) -> &'a Self {
         unsafe { &*(memory_address.as_ptr() as *const Self) }
@@ -0,0 +1,318 @@
+use crate::extensions::prelude::*;
+use crate::memory_address::MemoryAddress;
+use crate::memory_sources::memory_source::MemorySource;
+use std::alloc::AllocError;
+use std::cell::{Cell, UnsafeCell};
+use std::fmt;
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::mem::{align_of, size_of};
+use std::num::NonZeroUsize;
+use std::ptr::NonNull;
+use std::time::{Duration, Instant};
+
+/// What happens to memory retained by a `CachingMemorySource` before it is obtained again.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum CachedMemoryReuse {
+    /// Memory is obtained again as it was released, with its previous contents.
+    AsIs,
+
+    /// Memory is zeroed when it is obtained again.
+    Zeroed,
+
+    /// Memory is decommitted when it is retained (see `MemorySource::decommit()`), so that its whole pages use no physical memory whilst retained, and recommitted when it is obtained again.
+    ///
+    /// The contents of memory obtained again are undefined.
+    Decommitted,
+}
+
+impl Default for CachedMemoryReuse {
+    #[inline(always)]
+    fn default() -> Self {
+        CachedMemoryReuse::AsIs
+    }
+}
+
+/// A memory source which retains memory released to it, rather than releasing it to an underlying memory source, and obtains it again for an obtain of the same size; eg to avoid `munmap()` followed by `mmap()` (and page faults) every time a coroutine's allocator is dropped and another created.
+///
+/// Retained memory is kept in free lists bucketed by size (powers of two), held in the memory itself, up to a budget of `maximum_cached_bytes`; memory released beyond the budget, or too small to hold a free list node, is released to the underlying memory source.
+///
+/// Retained memory is released to the underlying memory source with `trim()`, `trim_older_than()`, automatically when it has been retained for longer than `maximum_age` (checked on each obtain and release), and when dropped.
+///
+/// This memory source is not thread-safe.
+pub struct CachingMemorySource<MS: MemorySource> {
+    buckets: UnsafeCell<[Option<NonNull<CachedRegion>>; NUMBER_OF_BUCKETS]>,
+    cached_bytes: Cell<usize>,
+    maximum_cached_bytes: usize,
+    reuse: CachedMemoryReuse,
+    maximum_age: Option<Duration>,
+    memory_source: MS,
+}
+
+impl<MS: MemorySource> Drop for CachingMemorySource<MS> {
+    #[inline(always)]
+    fn drop(&mut self) {
+        self.trim()
+    }
+}
+
+impl<MS: MemorySource> Debug for CachingMemorySource<MS> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "CachingMemorySource({:?}, {}, {}, {:?}, {:?})",
+            self.memory_source,
+            self.cached_bytes.get(),
+            self.maximum_cached_bytes,
+            self.reuse,
+            self.maximum_age
+        )
+    }
+}
+
+impl<MS: MemorySource> MemorySource for CachingMemorySource<MS> {
+    #[inline(always)]
+    fn obtain(&self, non_zero_size: NonZeroUsize) -> Result<MemoryAddress, AllocError> {
+        self.trim_if_too_old();
+
+        match self.take_cached(non_zero_size) {
+            None => self.memory_source.obtain(non_zero_size),
+
+            Some(memory) => self.reuse(non_zero_size, memory),
+        }
+    }
+
+    #[inline(always)]
+    fn release(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        self.trim_if_too_old();
+
+        let can_be_cached = non_zero_size.get() > Self::HEADER_SIZE
+            && current_memory.is_aligned_to(align_of::<CachedRegion>().non_zero())
+            && self.cached_bytes.get() + non_zero_size.get() <= self.maximum_cached_bytes;
+        if unlikely!(!can_be_cached) {
+            return self.memory_source.release(non_zero_size, current_memory);
+        }
+
+        if self.reuse == CachedMemoryReuse::Decommitted {
+            self.memory_source.decommit(
+                (non_zero_size.get() - Self::HEADER_SIZE).non_zero(),
+                current_memory.add(Self::HEADER_SIZE),
+            )
+        }
+        self.put_cached(non_zero_size, current_memory)
+    }
+
+    #[inline(always)]
+    fn decommit(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        self.memory_source.decommit(non_zero_size, current_memory)
+    }
+
+    #[inline(always)]
+    fn recommit(
+        &self,
+        non_zero_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> Result<(), AllocError> {
+        self.memory_source.recommit(non_zero_size, current_memory)
+    }
+}
+
+impl<MS: MemorySource> CachingMemorySource<MS> {
+    const HEADER_SIZE: usize = size_of::<CachedRegion>();
+
+    /// Creates a new instance.
+    ///
+    /// * `memory_source`: Underlying memory source.
+    /// * `maximum_cached_bytes`: Budget of memory retained.
+    /// * `reuse`: What happens to retained memory before it is obtained again.
+    /// * `maximum_age`: Memory retained for longer than this is released to the underlying memory source; `None` to retain memory until trimmed.
+    #[inline(always)]
+    pub fn new(
+        memory_source: MS,
+        maximum_cached_bytes: usize,
+        reuse: CachedMemoryReuse,
+        maximum_age: Option<Duration>,
+    ) -> Self {
+        Self {
+            buckets: UnsafeCell::new([None; NUMBER_OF_BUCKETS]),
+            cached_bytes: Cell::new(0),
+            maximum_cached_bytes,
+            reuse,
+            maximum_age,
+            memory_source,
+        }
+    }
+
+    /// Number of bytes retained.
+    #[inline(always)]
+    pub fn cached_bytes(&self) -> usize {
+        self.cached_bytes.get()
+    }
+
+    /// Releases all retained memory to the underlying memory source.
+    #[inline(always)]
+    pub fn trim(&self) {
+        self.trim_retaining(|_| false)
+    }
+
+    /// Releases memory retained for longer than `age` to the underlying memory source.
+    #[inline(always)]
+    pub fn trim_older_than(&self, age: Duration) {
+        let now = Instant::now();
+        self.trim_retaining(|cached_at| now.duration_since(cached_at) <= age)
+    }
+
+    /// The underlying memory source.
+    #[inline(always)]
+    pub fn memory_source(&self) -> &MS {
+        &self.memory_source
+    }
+
+    #[inline(always)]
+    fn trim_if_too_old(&self) {
+        if let Some(maximum_age) = self.maximum_age {
+            if self.cached_bytes.get() != 0 {
+                self.trim_older_than(maximum_age)
+            }
+        }
+    }
+
+    fn trim_retaining(&self, retain: impl Fn(Instant) -> bool) {
+        for bucket_index in 0..NUMBER_OF_BUCKETS {
+            let mut previous: Option<NonNull<CachedRegion>> = None;
+            let mut next = *self.bucket(bucket_index);
+            while let Some(cached_region) = next {
+                let (size, cached_at, following) = {
+                    let cached_region = unsafe { cached_region.as_ref() };
+                    (
+                        cached_region.size,
+                        cached_region.cached_at,
+                        cached_region.next,
+                    )
+                };
+
+                if retain(cached_at) {
+                    previous = Some(cached_region);
+                } else {
+                    self.unlink(bucket_index, previous, following);
+                    self.release_to_memory_source(size, cached_region.cast::<u8>());
+                }
+                next = following;
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn take_cached(&self, non_zero_size: NonZeroUsize) -> Option<MemoryAddress> {
+        let bucket_index = Self::bucket_index(non_zero_size);
+
+        let mut previous: Option<NonNull<CachedRegion>> = None;
+        let mut next = *self.bucket(bucket_index);
+        while let Some(cached_region) = next {
+            let (size, following) = {
+                let cached_region = unsafe { cached_region.as_ref() };
+                (cached_region.size, cached_region.next)
+            };
+
+            if size == non_zero_size {
+                self.unlink(bucket_index, previous, following);
+                self.cached_bytes.set(self.cached_bytes.get() - size.get());
+                return Some(cached_region.cast::<u8>());
+            }
+
+            previous = Some(cached_region);
+            next = following;
+        }
+        None
+    }
+
+    #[inline(always)]
+    fn put_cached(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        let bucket_index = Self::bucket_index(non_zero_size);
+        let bucket = self.bucket(bucket_index);
+
+        let cached_region = current_memory.cast::<CachedRegion>();
+        unsafe {
+            cached_region.as_ptr().write(CachedRegion {
+                next: *bucket,
+                size: non_zero_size,
+                cached_at: Instant::now(),
+            })
+        };
+        *bucket = Some(cached_region);
+        self.cached_bytes
+            .set(self.cached_bytes.get() + non_zero_size.get());
+    }
+
+    #[inline(always)]
+    fn reuse(
+        &self,
+        non_zero_size: NonZeroUsize,
+        memory: MemoryAddress,
+    ) -> Result<MemoryAddress, AllocError> {
+        match self.reuse {
+            CachedMemoryReuse::AsIs => (),
+
+            CachedMemoryReuse::Zeroed => unsafe {
+                memory.as_ptr().write_bytes(0x00, non_zero_size.get())
+            },
+
+            CachedMemoryReuse::Decommitted => {
+                let rest_of_memory = memory.add(Self::HEADER_SIZE);
+                let rest_of_size = (non_zero_size.get() - Self::HEADER_SIZE).non_zero();
+                if unlikely!(self
+                    .memory_source
+                    .recommit(rest_of_size, rest_of_memory)
+                    .is_err())
+                {
+                    self.memory_source.release(non_zero_size, memory);
+                    return self.memory_source.obtain(non_zero_size);
+                }
+            }
+        }
+        Ok(memory)
+    }
+
+    #[inline(always)]
+    fn unlink(
+        &self,
+        bucket_index: usize,
+        previous: Option<NonNull<CachedRegion>>,
+        following: Option<NonNull<CachedRegion>>,
+    ) {
+        match previous {
+            None => *self.bucket(bucket_index) = following,
+
+            Some(mut previous) => unsafe { previous.as_mut() }.next = following,
+        }
+    }
+
+    #[inline(always)]
+    fn release_to_memory_source(&self, non_zero_size: NonZeroUsize, memory: MemoryAddress) {
+        self.cached_bytes
+            .set(self.cached_bytes.get() - non_zero_size.get());
+        self.memory_source.release(non_zero_size, memory)
+    }
+
+    #[inline(always)]
+    fn bucket(&self, bucket_index: usize) -> &mut Option<NonNull<CachedRegion>> {
+        debug_assert!(bucket_index < NUMBER_OF_BUCKETS);
+
+        unsafe { (&mut *self.buckets.get()).get_unchecked_mut(bucket_index) }
+    }
+
+    /// Sizes from `2^n` to `2^(n + 1) - 1` are in bucket `n`.
+    #[inline(always)]
+    fn bucket_index(non_zero_size: NonZeroUsize) -> usize {
+        NUMBER_OF_BUCKETS - 1 - (non_zero_size.get().leading_zeros() as usize)
+    }
+}
+
+const NUMBER_OF_BUCKETS: usize = size_of::<usize>() * 8;
+
+/// Held at the start of retained memory.
+struct CachedRegion {
+    next: Option<NonNull<CachedRegion>>,
+    size: NonZeroUsize,
+    cached_at: Instant,
+}
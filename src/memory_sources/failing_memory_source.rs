@@ -0,0 +1,225 @@
+use crate::memory_address::MemoryAddress;
+use crate::memory_sources::memory_source::MemorySource;
+use std::alloc::AllocError;
+use std::cell::Cell;
+use std::num::NonZeroUsize;
+
+/// When a `FailureInjector` fails a call.
+#[derive(Debug, Copy, Clone)]
+pub enum FailurePolicy {
+    /// Never fail.
+    Never,
+
+    /// Fail every Nth call (the Nth, the 2Nth, and so on).
+    EveryNthCall(NonZeroUsize),
+
+    /// Fail any call which would take the total bytes of calls not failed beyond this many bytes.
+    AfterBytes(usize),
+
+    /// Fail any call for which this predicate of the size returns `true`.
+    WhenSize(fn(NonZeroUsize) -> bool),
+
+    /// Fail, on average, one call in `one_in`, pseudo-randomly; the same `seed` always fails the same calls.
+    Randomly {
+        /// Seed.
+        seed: u64,
+
+        /// Average number of calls per failure.
+        one_in: NonZeroUsize,
+    },
+}
+
+/// Decides, deterministically, which calls to fail according to a `FailurePolicy`, and records which were failed.
+///
+/// Used by `FailingMemorySource` and `FailingAllocator`.
+///
+/// Calls are numbered from 1.
+///
+/// This is not thread-safe.
+#[derive(Debug)]
+pub struct FailureInjector {
+    policy: FailurePolicy,
+    calls: Cell<usize>,
+    bytes: Cell<usize>,
+    failures: Cell<usize>,
+    first_failed_call: Cell<Option<NonZeroUsize>>,
+    last_failed_call: Cell<Option<NonZeroUsize>>,
+    random_state: Cell<u64>,
+}
+
+impl FailureInjector {
+    /// Creates a new instance.
+    #[inline(always)]
+    pub fn new(policy: FailurePolicy) -> Self {
+        let random_state = match policy {
+            // xorshift can not use a state of zero.
+            FailurePolicy::Randomly { seed: 0, .. } => 0x9E37_79B9_7F4A_7C15,
+
+            FailurePolicy::Randomly { seed, .. } => seed,
+
+            _ => 0,
+        };
+
+        Self {
+            policy,
+            calls: Cell::new(0),
+            bytes: Cell::new(0),
+            failures: Cell::new(0),
+            first_failed_call: Cell::new(None),
+            last_failed_call: Cell::new(None),
+            random_state: Cell::new(random_state),
+        }
+    }
+
+    /// Counts a call of `non_zero_size` bytes, and returns `Err` if it should fail.
+    #[inline(always)]
+    pub fn call(&self, non_zero_size: NonZeroUsize) -> Result<(), AllocError> {
+        let call = self.calls.get() + 1;
+        self.calls.set(call);
+
+        if unlikely!(self.should_fail(call, non_zero_size)) {
+            let call = unsafe { NonZeroUsize::new_unchecked(call) };
+            self.failures.set(self.failures.get() + 1);
+            if self.first_failed_call.get().is_none() {
+                self.first_failed_call.set(Some(call))
+            }
+            self.last_failed_call.set(Some(call));
+            Err(AllocError)
+        } else {
+            self.bytes
+                .set(self.bytes.get().saturating_add(non_zero_size.get()));
+            Ok(())
+        }
+    }
+
+    /// The policy.
+    #[inline(always)]
+    pub fn policy(&self) -> FailurePolicy {
+        self.policy
+    }
+
+    /// Number of calls, failed or not.
+    #[inline(always)]
+    pub fn calls(&self) -> usize {
+        self.calls.get()
+    }
+
+    /// Total bytes of calls not failed.
+    #[inline(always)]
+    pub fn bytes(&self) -> usize {
+        self.bytes.get()
+    }
+
+    /// Number of calls failed.
+    #[inline(always)]
+    pub fn failures(&self) -> usize {
+        self.failures.get()
+    }
+
+    /// Number of the first call failed, if any.
+    #[inline(always)]
+    pub fn first_failed_call(&self) -> Option<NonZeroUsize> {
+        self.first_failed_call.get()
+    }
+
+    /// Number of the most recent call failed, if any.
+    #[inline(always)]
+    pub fn last_failed_call(&self) -> Option<NonZeroUsize> {
+        self.last_failed_call.get()
+    }
+
+    #[inline(always)]
+    fn should_fail(&self, call: usize, non_zero_size: NonZeroUsize) -> bool {
+        match self.policy {
+            FailurePolicy::Never => false,
+
+            FailurePolicy::EveryNthCall(n) => call % n.get() == 0,
+
+            FailurePolicy::AfterBytes(limit) => {
+                match self.bytes.get().checked_add(non_zero_size.get()) {
+                    Some(bytes) => bytes > limit,
+
+                    None => true,
+                }
+            }
+
+            FailurePolicy::WhenSize(predicate) => predicate(non_zero_size),
+
+            FailurePolicy::Randomly { one_in, .. } => {
+                self.next_random() % (one_in.get() as u64) == 0
+            }
+        }
+    }
+
+    /// xorshift64*.
+    #[inline(always)]
+    fn next_random(&self) -> u64 {
+        let mut state = self.random_state.get();
+        state ^= state >> 12;
+        state ^= state << 25;
+        state ^= state >> 27;
+        self.random_state.set(state);
+        state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+/// A memory source which fails obtains according to a `FailurePolicy`, and otherwise obtains from an underlying memory source; for testing behaviour when memory can not be obtained, which a `MemoryMapSource` almost never does.
+///
+/// Only obtains are failed; releases, decommits and recommits are always passed to the underlying memory source.
+///
+/// This memory source is not thread-safe.
+#[derive(Debug)]
+pub struct FailingMemorySource<MS: MemorySource> {
+    failure_injector: FailureInjector,
+    memory_source: MS,
+}
+
+impl<MS: MemorySource> MemorySource for FailingMemorySource<MS> {
+    #[inline(always)]
+    fn obtain(&self, non_zero_size: NonZeroUsize) -> Result<MemoryAddress, AllocError> {
+        self.failure_injector.call(non_zero_size)?;
+        self.memory_source.obtain(non_zero_size)
+    }
+
+    #[inline(always)]
+    fn release(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        self.memory_source.release(non_zero_size, current_memory)
+    }
+
+    #[inline(always)]
+    fn decommit(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        self.memory_source.decommit(non_zero_size, current_memory)
+    }
+
+    #[inline(always)]
+    fn recommit(
+        &self,
+        non_zero_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> Result<(), AllocError> {
+        self.memory_source.recommit(non_zero_size, current_memory)
+    }
+}
+
+impl<MS: MemorySource> FailingMemorySource<MS> {
+    /// Creates a new instance.
+    #[inline(always)]
+    pub fn new(memory_source: MS, policy: FailurePolicy) -> Self {
+        Self {
+            failure_injector: FailureInjector::new(policy),
+            memory_source,
+        }
+    }
+
+    /// Counts of obtains and which were failed.
+    #[inline(always)]
+    pub fn failure_injector(&self) -> &FailureInjector {
+        &self.failure_injector
+    }
+
+    /// The underlying memory source.
+    #[inline(always)]
+    pub fn memory_source(&self) -> &MS {
+        &self.memory_source
+    }
+}
@@ -0,0 +1,156 @@
+use crate::extensions::prelude::*;
+use crate::memory_address::MemoryAddress;
+use crate::memory_sources::memory_source::MemorySource;
+use libc::*;
+use std::alloc::AllocError;
+use std::cell::Cell;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::num::NonZeroUsize;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::ptr::null_mut;
+
+/// A memory source which memory maps a file (`MAP_SHARED`), so that memory obtained is persisted to it; eg a persistent scratch heap for large on-disk indexes.
+///
+/// Each obtain maps the next, page-aligned, region of the file, starting at the beginning of the file, growing the file as needed.
+/// The file is grown sparsely using `ftruncate()`, or, if `preallocate` is true on Android and Linux, by allocating disk blocks using `fallocate()` (so that running out of disk space fails the obtain rather than raising `SIGBUS` later).
+/// Obtaining the same sequence of sizes from a new instance for the same file maps the same regions, with their previous contents.
+///
+/// Releasing memory unmaps it, first writing it to the file with `msync()` if `synchronize_on_release` is true; the region of the file is not reused.
+///
+/// It is slow and uses system calls.
+///
+/// This memory source is not thread-safe.
+///
+/// Only on Unix.
+#[derive(Debug)]
+pub struct FileMemorySource {
+    file: File,
+    file_size: Cell<u64>,
+    next_obtain_at_offset: Cell<u64>,
+    page_size: NonZeroUsize,
+    preallocate: bool,
+    synchronize_on_release: bool,
+}
+
+impl MemorySource for FileMemorySource {
+    #[inline(always)]
+    fn obtain(&self, non_zero_size: NonZeroUsize) -> Result<MemoryAddress, AllocError> {
+        let size = non_zero_size.round_up_to_power_of_two(self.page_size).get() as u64;
+        let offset = self.next_obtain_at_offset.get();
+        let ends_at_offset = offset.checked_add(size).ok_or(AllocError)?;
+
+        self.grow_file(offset, ends_at_offset)?;
+
+        let result = unsafe {
+            mmap(
+                null_mut(),
+                non_zero_size.get(),
+                PROT_READ | PROT_WRITE,
+                MAP_SHARED,
+                self.file.as_raw_fd(),
+                offset as off_t,
+            )
+        };
+        if unlikely!(result == MAP_FAILED) {
+            return Err(AllocError);
+        }
+
+        self.next_obtain_at_offset.set(ends_at_offset);
+        Ok(result.cast::<u8>().non_null())
+    }
+
+    #[inline(always)]
+    fn release(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        let address = current_memory.as_ptr() as *mut c_void;
+        if self.synchronize_on_release {
+            unsafe { msync(address, non_zero_size.get(), MS_SYNC) };
+        }
+        unsafe { munmap(address, non_zero_size.get()) };
+    }
+}
+
+impl FileMemorySource {
+    /// Opens (creating if necessary) the file at `path` for reading and writing, then creates a new instance using it.
+    ///
+    /// See `new()` for `preallocate` and `synchronize_on_release`.
+    #[inline(always)]
+    pub fn open(
+        path: impl AsRef<Path>,
+        preallocate: bool,
+        synchronize_on_release: bool,
+    ) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(path)?;
+        Self::new(file, preallocate, synchronize_on_release)
+    }
+
+    /// Creates a new instance using `file`, which must be open for reading and writing.
+    ///
+    /// * `preallocate`: Allocate disk blocks when growing the file, rather than growing it sparsely. Only on Android and Linux; elsewhere, the file is always grown sparsely.
+    /// * `synchronize_on_release`: Write memory to the file with `msync()` when it is released, rather than leaving it to the operating system.
+    #[inline(always)]
+    pub fn new(file: File, preallocate: bool, synchronize_on_release: bool) -> io::Result<Self> {
+        let file_size = file.metadata()?.len();
+        let page_size = unsafe { sysconf(_SC_PAGESIZE) };
+        if unlikely!(page_size <= 0) {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self {
+            file,
+            file_size: Cell::new(file_size),
+            next_obtain_at_offset: Cell::new(0),
+            page_size: (page_size as usize).non_zero(),
+            preallocate,
+            synchronize_on_release,
+        })
+    }
+
+    /// The file.
+    #[inline(always)]
+    pub fn file(&self) -> &File {
+        &self.file
+    }
+
+    #[allow(unused_variables)]
+    #[inline(always)]
+    fn grow_file(&self, offset: u64, ends_at_offset: u64) -> Result<(), AllocError> {
+        if likely!(ends_at_offset <= self.file_size.get()) {
+            return Ok(());
+        }
+
+        #[cfg(any(target_os = "android", target_os = "linux"))]
+        {
+            if self.preallocate {
+                const ALLOCATE_AND_GROW: c_int = 0;
+
+                let result = unsafe {
+                    fallocate(
+                        self.file.as_raw_fd(),
+                        ALLOCATE_AND_GROW,
+                        offset as off_t,
+                        (ends_at_offset - offset) as off_t,
+                    )
+                };
+                if unlikely!(result != 0) {
+                    return Err(AllocError);
+                }
+                self.file_size.set(ends_at_offset);
+                return Ok(());
+            }
+        }
+
+        let result = unsafe { ftruncate(self.file.as_raw_fd(), ends_at_offset as off_t) };
+        if unlikely!(result != 0) {
+            return Err(AllocError);
+        }
+        self.file_size.set(ends_at_offset);
+        Ok(())
+    }
+}
@@ -0,0 +1,228 @@
+use crate::extensions::prelude::*;
+use crate::memory_address::MemoryAddress;
+use crate::memory_sources::memory_source::MemorySource;
+use std::alloc::AllocError;
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::marker::PhantomData;
+use std::num::NonZeroUsize;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::{AcqRel, Acquire, Relaxed};
+
+/// Memory obtained from a fixed buffer is aligned to this (a cache line).
+pub const FIXED_BUFFER_ALIGNMENT: usize = 64;
+
+/// A memory source which obtains memory from a caller-supplied buffer, such as a stack buffer or a region handed over by another library, rather than the operating system.
+///
+/// Memory is obtained by bumping a pointer; released memory is only reclaimed if it was the most recently obtained, or when `reset()` is called.
+///
+/// Useful in environments without `mmap` and for deterministic tests.
+///
+/// This memory source is thread-safe.
+pub struct FixedBufferMemorySource<'buffer> {
+    allocations_start_from: usize,
+    buffer_size: usize,
+    next_obtain_at_offset: AtomicUsize,
+    marker: PhantomData<&'buffer mut [u8]>,
+}
+
+impl<'buffer> Debug for FixedBufferMemorySource<'buffer> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "FixedBufferMemorySource({:#x}, {}, {})",
+            self.allocations_start_from,
+            self.buffer_size,
+            self.next_obtain_at_offset.load(Relaxed)
+        )
+    }
+}
+
+impl<'buffer> MemorySource for FixedBufferMemorySource<'buffer> {
+    #[inline(always)]
+    fn obtain(&self, non_zero_size: NonZeroUsize) -> Result<MemoryAddress, AllocError> {
+        obtain_by_bumping(
+            &self.next_obtain_at_offset,
+            self.allocations_start_from,
+            self.buffer_size,
+            non_zero_size,
+        )
+    }
+
+    #[inline(always)]
+    fn release(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        release_if_most_recent(
+            &self.next_obtain_at_offset,
+            self.allocations_start_from,
+            non_zero_size,
+            current_memory,
+        )
+    }
+}
+
+impl<'buffer> FixedBufferMemorySource<'buffer> {
+    /// Creates a new instance which obtains memory from `buffer`.
+    #[inline(always)]
+    pub fn new(buffer: &'buffer mut [u8]) -> Self {
+        unsafe { Self::from_raw_range(buffer.as_mut_ptr() as usize, buffer.len()) }
+    }
+
+    /// Creates a new instance which obtains memory from the `buffer_size` bytes starting at `buffer_starts_at`.
+    ///
+    /// Usable in `const` contexts.
+    ///
+    /// # Safety
+    ///
+    /// The memory must be valid for reads and writes, and not used for anything else, for `'buffer`.
+    #[inline(always)]
+    pub const unsafe fn from_raw_range(buffer_starts_at: usize, buffer_size: usize) -> Self {
+        Self {
+            allocations_start_from: buffer_starts_at,
+            buffer_size,
+            next_obtain_at_offset: AtomicUsize::new(0),
+            marker: PhantomData,
+        }
+    }
+
+    /// Number of bytes not yet obtained (ignoring alignment).
+    #[inline(always)]
+    pub fn remaining(&self) -> usize {
+        self.buffer_size - self.next_obtain_at_offset.load(Relaxed)
+    }
+
+    /// Makes all of the buffer available again.
+    ///
+    /// All memory previously obtained becomes invalid.
+    #[inline(always)]
+    pub fn reset(&mut self) {
+        *self.next_obtain_at_offset.get_mut() = 0
+    }
+}
+
+/// A memory source which obtains memory from a buffer of `SIZE` bytes held inline, so that it can be a `static` (eg to run an allocator without `mmap`).
+///
+/// Usable in `const` and `static` contexts; a reference to it (eg `&'static`) is also a memory source.
+///
+/// Memory is obtained by bumping a pointer; released memory is only reclaimed if it was the most recently obtained.
+///
+/// This memory source is thread-safe.
+#[repr(C, align(64))]
+pub struct StaticFixedBufferMemorySource<const SIZE: usize> {
+    buffer: UnsafeCell<[u8; SIZE]>,
+    next_obtain_at_offset: AtomicUsize,
+}
+
+unsafe impl<const SIZE: usize> Sync for StaticFixedBufferMemorySource<SIZE> {}
+
+impl<const SIZE: usize> Debug for StaticFixedBufferMemorySource<SIZE> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "StaticFixedBufferMemorySource({}, {})",
+            SIZE,
+            self.next_obtain_at_offset.load(Relaxed)
+        )
+    }
+}
+
+impl<const SIZE: usize> MemorySource for StaticFixedBufferMemorySource<SIZE> {
+    #[inline(always)]
+    fn obtain(&self, non_zero_size: NonZeroUsize) -> Result<MemoryAddress, AllocError> {
+        obtain_by_bumping(
+            &self.next_obtain_at_offset,
+            self.allocations_start_from(),
+            SIZE,
+            non_zero_size,
+        )
+    }
+
+    #[inline(always)]
+    fn release(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        release_if_most_recent(
+            &self.next_obtain_at_offset,
+            self.allocations_start_from(),
+            non_zero_size,
+            current_memory,
+        )
+    }
+}
+
+impl<'a, const SIZE: usize> MemorySource for &'a StaticFixedBufferMemorySource<SIZE> {
+    #[inline(always)]
+    fn obtain(&self, non_zero_size: NonZeroUsize) -> Result<MemoryAddress, AllocError> {
+        (*self).obtain(non_zero_size)
+    }
+
+    #[inline(always)]
+    fn release(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        (*self).release(non_zero_size, current_memory)
+    }
+}
+
+impl<const SIZE: usize> StaticFixedBufferMemorySource<SIZE> {
+    /// Creates a new instance.
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self {
+            buffer: UnsafeCell::new([0; SIZE]),
+            next_obtain_at_offset: AtomicUsize::new(0),
+        }
+    }
+
+    /// Number of bytes not yet obtained (ignoring alignment).
+    #[inline(always)]
+    pub fn remaining(&self) -> usize {
+        SIZE - self.next_obtain_at_offset.load(Relaxed)
+    }
+
+    #[inline(always)]
+    fn allocations_start_from(&self) -> usize {
+        self.buffer.get() as usize
+    }
+}
+
+#[inline(always)]
+fn obtain_by_bumping(
+    next_obtain_at_offset: &AtomicUsize,
+    allocations_start_from: usize,
+    buffer_size: usize,
+    non_zero_size: NonZeroUsize,
+) -> Result<MemoryAddress, AllocError> {
+    let mut offset = next_obtain_at_offset.load(Acquire);
+    loop {
+        let obtain_at = (allocations_start_from + offset)
+            .round_up_to_power_of_two(FIXED_BUFFER_ALIGNMENT.non_zero());
+        let obtained_offset = obtain_at - allocations_start_from;
+        let ends_at_offset = match obtained_offset.checked_add(non_zero_size.get()) {
+            Some(ends_at_offset) if ends_at_offset <= buffer_size => ends_at_offset,
+
+            _ => return Err(AllocError),
+        };
+
+        match next_obtain_at_offset.compare_exchange_weak(offset, ends_at_offset, AcqRel, Acquire) {
+            Ok(_) => return Ok(MemoryAddress::from_usize(obtain_at)),
+
+            Err(was) => offset = was,
+        }
+    }
+}
+
+#[inline(always)]
+fn release_if_most_recent(
+    next_obtain_at_offset: &AtomicUsize,
+    allocations_start_from: usize,
+    non_zero_size: NonZeroUsize,
+    current_memory: MemoryAddress,
+) {
+    let obtained_offset = current_memory.to_usize() - allocations_start_from;
+
+    // Only succeeds if this was the most recently obtained memory.
+    let _ = next_obtain_at_offset.compare_exchange(
+        obtained_offset + non_zero_size.get(),
+        obtained_offset,
+        AcqRel,
+        Acquire,
+    );
+}
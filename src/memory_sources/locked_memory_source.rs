@@ -0,0 +1,97 @@
+use crate::allocators::global::memory_range::MemoryRange;
+use crate::allocators::locked::allocator_lock::AllocatorLock;
+use crate::allocators::locked::locked_allocator::UnlockOnDrop;
+use crate::allocators::locked::spin_lock::SpinLock;
+use crate::memory_address::MemoryAddress;
+use crate::memory_sources::memory_source::MemorySource;
+use std::alloc::AllocError;
+use std::fmt;
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::num::NonZeroUsize;
+
+/// Makes any memory source thread-safe by serializing all access to it with a lock of type `L`; eg so that a `ReservedMemorySource` or `CachingMemorySource` can be shared by the allocators of many threads using an `ArcMemorySource`.
+///
+/// Memory sources which are already thread-safe, such as `MemoryMapSource`, do not need this.
+pub struct LockedMemorySource<MS: MemorySource, L: AllocatorLock = SpinLock> {
+    lock: L,
+    memory_source: MS,
+}
+
+unsafe impl<MS: MemorySource + Send, L: AllocatorLock + Send> Send for LockedMemorySource<MS, L> {}
+
+unsafe impl<MS: MemorySource + Send, L: AllocatorLock + Sync> Sync for LockedMemorySource<MS, L> {}
+
+impl<MS: MemorySource, L: AllocatorLock> Debug for LockedMemorySource<MS, L> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "LockedMemorySource")
+    }
+}
+
+impl<MS: MemorySource, L: AllocatorLock> MemorySource for LockedMemorySource<MS, L> {
+    #[inline(always)]
+    fn obtain(&self, non_zero_size: NonZeroUsize) -> Result<MemoryAddress, AllocError> {
+        self.locked(|memory_source| memory_source.obtain(non_zero_size))
+    }
+
+    #[inline(always)]
+    fn release(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        self.locked(|memory_source| memory_source.release(non_zero_size, current_memory))
+    }
+
+    #[inline(always)]
+    fn decommit(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        self.locked(|memory_source| memory_source.decommit(non_zero_size, current_memory))
+    }
+
+    #[inline(always)]
+    fn recommit(
+        &self,
+        non_zero_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> Result<(), AllocError> {
+        self.locked(|memory_source| memory_source.recommit(non_zero_size, current_memory))
+    }
+
+    #[inline(always)]
+    fn extend(
+        &self,
+        current_memory: MemoryAddress,
+        non_zero_current_size: NonZeroUsize,
+        non_zero_new_size: NonZeroUsize,
+    ) -> Result<(), AllocError> {
+        self.locked(|memory_source| {
+            memory_source.extend(current_memory, non_zero_current_size, non_zero_new_size)
+        })
+    }
+
+    #[inline(always)]
+    fn reservation(&self) -> Option<MemoryRange> {
+        self.locked(|memory_source| memory_source.reservation())
+    }
+}
+
+impl<MS: MemorySource, L: AllocatorLock> LockedMemorySource<MS, L> {
+    /// Create a new instance wrapping `memory_source`.
+    #[inline(always)]
+    pub const fn new(memory_source: MS) -> Self {
+        Self {
+            lock: L::UNLOCKED,
+            memory_source,
+        }
+    }
+
+    /// Consumes this instance, returning the wrapped memory source.
+    #[inline(always)]
+    pub fn into_inner(self) -> MS {
+        self.memory_source
+    }
+
+    #[inline(always)]
+    fn locked<R>(&self, callback: impl FnOnce(&MS) -> R) -> R {
+        self.lock.lock();
+        let _guard = UnlockOnDrop(&self.lock);
+
+        callback(&self.memory_source)
+    }
+}
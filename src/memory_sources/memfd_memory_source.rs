@@ -0,0 +1,248 @@
+use crate::extensions::prelude::*;
+use crate::memory_address::MemoryAddress;
+use crate::memory_sources::memory_source::MemorySource;
+use crate::memory_sources::mmap::huge_page_size::HugePageSize;
+use libc::*;
+use std::alloc::AllocError;
+use std::cell::Cell;
+use std::ffi::CStr;
+use std::io;
+use std::num::NonZeroUsize;
+use std::os::unix::io::RawFd;
+use std::ptr::null_mut;
+
+/// A memory source which maps an anonymous, memory-backed file created with `memfd_create()`, so that memory obtained can be shared with child processes or other processes by passing the file descriptor.
+///
+/// A range of virtual addresses of `maximum_size` is reserved when created; each obtain maps the next region of the file into the next part of it, so the file offset of any memory obtained is known (`offset_of()`).
+/// The file is grown with `ftruncate()` as needed; once sealed with `F_SEAL_GROW`, only memory within its current size can be obtained.
+///
+/// Releasing memory unmaps it and frees its region of the file by punching a hole; the region is not reused.
+///
+/// Huge pages (`MFD_HUGETLB`) can be used; obtained memory is then rounded up to the huge page size.
+///
+/// When dropped, the whole reserved range, including all memory obtained within it, is unmapped and the file descriptor closed; so it must outlive all memory obtained from it.
+/// Other processes which mapped the file keep their mappings.
+///
+/// This memory source is not thread-safe.
+///
+/// Only on Android and Linux.
+#[derive(Debug)]
+pub struct MemfdMemorySource {
+    file_descriptor: RawFd,
+    file_size: Cell<u64>,
+    next_obtain_at_offset: Cell<u64>,
+    page_size: NonZeroUsize,
+
+    reservation_starts_at: MemoryAddress,
+    reservation_size: NonZeroUsize,
+    allocations_start_from: MemoryAddress,
+    maximum_size: NonZeroUsize,
+}
+
+impl Drop for MemfdMemorySource {
+    #[inline(always)]
+    fn drop(&mut self) {
+        unsafe {
+            munmap(
+                self.reservation_starts_at.as_ptr() as *mut c_void,
+                self.reservation_size.get(),
+            );
+            close(self.file_descriptor);
+        }
+    }
+}
+
+impl MemorySource for MemfdMemorySource {
+    #[inline(always)]
+    fn obtain(&self, non_zero_size: NonZeroUsize) -> Result<MemoryAddress, AllocError> {
+        let size = non_zero_size.round_up_to_power_of_two(self.page_size).get();
+        let offset = self.next_obtain_at_offset.get();
+        let ends_at_offset = offset + size as u64;
+        if unlikely!(ends_at_offset > self.maximum_size.get() as u64) {
+            return Err(AllocError);
+        }
+
+        if ends_at_offset > self.file_size.get() {
+            if unlikely!(unsafe { ftruncate(self.file_descriptor, ends_at_offset as off_t) } != 0) {
+                return Err(AllocError);
+            }
+            self.file_size.set(ends_at_offset);
+        }
+
+        let memory = self.allocations_start_from.add(offset as usize);
+        let result = unsafe {
+            mmap(
+                memory.as_ptr() as *mut c_void,
+                size,
+                PROT_READ | PROT_WRITE,
+                MAP_SHARED | MAP_FIXED,
+                self.file_descriptor,
+                offset as off_t,
+            )
+        };
+        if unlikely!(result == MAP_FAILED) {
+            return Err(AllocError);
+        }
+
+        self.next_obtain_at_offset.set(ends_at_offset);
+        Ok(memory)
+    }
+
+    #[inline(always)]
+    fn release(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        let size = non_zero_size.round_up_to_power_of_two(self.page_size).get();
+        let offset = current_memory.difference(self.allocations_start_from);
+
+        unsafe {
+            // Keeps the range reserved.
+            mmap(
+                current_memory.as_ptr() as *mut c_void,
+                size,
+                PROT_NONE,
+                Self::RESERVATION_MAP_FLAGS | MAP_FIXED,
+                Self::UNUSED_FILE_DESCRIPTOR,
+                0,
+            );
+            fallocate(
+                self.file_descriptor,
+                FALLOC_FL_PUNCH_HOLE | FALLOC_FL_KEEP_SIZE,
+                offset as off_t,
+                size as off_t,
+            );
+        }
+    }
+}
+
+impl MemfdMemorySource {
+    const UNUSED_FILE_DESCRIPTOR: RawFd = -1;
+
+    const RESERVATION_MAP_FLAGS: c_int = MAP_PRIVATE | MAP_ANONYMOUS | MAP_NORESERVE;
+
+    /// Creates a new instance.
+    ///
+    /// * `name`: Name of the file, for debugging; appears in `/proc/self/fd`.
+    /// * `maximum_size`: Maximum size of all memory obtained; this much virtual address space is reserved.
+    /// * `huge_page_size`: Huge page size to create the file with (`MFD_HUGETLB`).
+    /// * `allow_sealing`: Allow seals to be added with `add_seals()` (`MFD_ALLOW_SEALING`).
+    #[inline(always)]
+    pub fn new(
+        name: &CStr,
+        maximum_size: NonZeroUsize,
+        huge_page_size: HugePageSize,
+        allow_sealing: bool,
+    ) -> io::Result<Self> {
+        let page_size = Self::page_size(huge_page_size)?;
+        let maximum_size = maximum_size.round_up_to_power_of_two(page_size);
+
+        let flags = MFD_CLOEXEC
+            | if allow_sealing { MFD_ALLOW_SEALING } else { 0 }
+            | Self::memfd_huge_page_flags(huge_page_size);
+        let file_descriptor = unsafe { memfd_create(name.as_ptr(), flags) };
+        if unlikely!(file_descriptor == -1) {
+            return Err(io::Error::last_os_error());
+        }
+
+        // Extra so that the start can be aligned to the page size.
+        let reservation_size = maximum_size.add_non_zero(page_size);
+        let reservation_starts_at = unsafe {
+            mmap(
+                null_mut(),
+                reservation_size.get(),
+                PROT_NONE,
+                Self::RESERVATION_MAP_FLAGS,
+                Self::UNUSED_FILE_DESCRIPTOR,
+                0,
+            )
+        };
+        if unlikely!(reservation_starts_at == MAP_FAILED) {
+            let error = io::Error::last_os_error();
+            unsafe { close(file_descriptor) };
+            return Err(error);
+        }
+        let reservation_starts_at = reservation_starts_at.cast::<u8>().non_null();
+
+        Ok(Self {
+            file_descriptor,
+            file_size: Cell::new(0),
+            next_obtain_at_offset: Cell::new(0),
+            page_size,
+
+            reservation_starts_at,
+            reservation_size,
+            allocations_start_from: reservation_starts_at.round_up_to_power_of_two(page_size),
+            maximum_size,
+        })
+    }
+
+    /// The file descriptor, eg to pass to another process (using `SCM_RIGHTS`) or to a child process.
+    ///
+    /// It is closed when this memory source is dropped; duplicate it with `dup()` to keep it open.
+    #[inline(always)]
+    pub fn file_descriptor(&self) -> RawFd {
+        self.file_descriptor
+    }
+
+    /// The offset in the file of memory obtained from this memory source, eg so that another process can map it.
+    #[inline(always)]
+    pub fn offset_of(&self, memory: MemoryAddress) -> Option<u64> {
+        if memory >= self.allocations_start_from {
+            let offset = memory.difference(self.allocations_start_from) as u64;
+            if offset < self.next_obtain_at_offset.get() {
+                return Some(offset);
+            }
+        }
+        None
+    }
+
+    /// Current size of the file.
+    #[inline(always)]
+    pub fn file_size(&self) -> u64 {
+        self.file_size.get()
+    }
+
+    /// Adds seals (`F_SEAL_SEAL`, `F_SEAL_SHRINK`, `F_SEAL_GROW`, `F_SEAL_WRITE` or `F_SEAL_FUTURE_WRITE`), eg before passing the file descriptor to a less trusted process.
+    ///
+    /// Fails unless created with `allow_sealing`.
+    #[inline(always)]
+    pub fn add_seals(&self, seals: c_int) -> io::Result<()> {
+        if unlikely!(unsafe { fcntl(self.file_descriptor, F_ADD_SEALS, seals) } == -1) {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Seals added.
+    #[inline(always)]
+    pub fn seals(&self) -> io::Result<c_int> {
+        let seals = unsafe { fcntl(self.file_descriptor, F_GET_SEALS) };
+        if unlikely!(seals == -1) {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(seals)
+        }
+    }
+
+    #[inline(always)]
+    fn page_size(huge_page_size: HugePageSize) -> io::Result<NonZeroUsize> {
+        if let Some(huge_page_size) = huge_page_size.page_size() {
+            return Ok(huge_page_size);
+        }
+
+        let page_size = unsafe { sysconf(_SC_PAGESIZE) };
+        if unlikely!(page_size <= 0) {
+            return Err(io::Error::last_os_error());
+        }
+        Ok((page_size as usize).non_zero())
+    }
+
+    /// `MFD_HUGE_*` use the same bits as `MAP_HUGE_*`.
+    #[inline(always)]
+    fn memfd_huge_page_flags(huge_page_size: HugePageSize) -> c_uint {
+        if huge_page_size == HugePageSize::None {
+            0
+        } else {
+            MFD_HUGETLB | ((huge_page_size as i32 & !MAP_HUGETLB) as c_uint)
+        }
+    }
+}
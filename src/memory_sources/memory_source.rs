@@ -1,3 +1,4 @@
+use crate::allocators::global::memory_range::MemoryRange;
 use crate::memory_address::MemoryAddress;
 use std::alloc::AllocError;
 use std::fmt::Debug;
@@ -16,4 +17,47 @@ pub trait MemorySource: Debug {
     ///
     /// Alignment will be whatever is appropriate, but is likely to be quite large.
     fn release(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress);
+
+    /// Decommit memory previously obtained, returning the physical memory backing it to the operating system, say, whilst keeping it obtained.
+    ///
+    /// Only whole pages within the memory are decommitted; the contents of decommitted memory are lost.
+    ///
+    /// By default, does nothing.
+    #[inline(always)]
+    fn decommit(&self, _non_zero_size: NonZeroUsize, _current_memory: MemoryAddress) {}
+
+    /// Recommit memory previously decommitted, so that it can be used again.
+    ///
+    /// All pages overlapping the memory are recommitted.
+    ///
+    /// By default, does nothing.
+    #[inline(always)]
+    fn recommit(
+        &self,
+        _non_zero_size: NonZeroUsize,
+        _current_memory: MemoryAddress,
+    ) -> Result<(), AllocError> {
+        Ok(())
+    }
+
+    /// Extends memory previously obtained, `current_memory`, from `non_zero_current_size` to `non_zero_new_size` bytes in place, without moving it.
+    ///
+    /// By default, fails.
+    #[inline(always)]
+    fn extend(
+        &self,
+        _current_memory: MemoryAddress,
+        _non_zero_current_size: NonZeroUsize,
+        _non_zero_new_size: NonZeroUsize,
+    ) -> Result<(), AllocError> {
+        Err(AllocError)
+    }
+
+    /// The range of addresses all memory obtained from this memory source lies within, if fixed up front.
+    ///
+    /// By default, `None`.
+    #[inline(always)]
+    fn reservation(&self) -> Option<MemoryRange> {
+        None
+    }
 }
@@ -1,5 +1,7 @@
+use crate::extensions::prelude::*;
 #[cfg(any(target_os = "android", target_os = "linux"))]
 use libc::MAP_HUGETLB;
+use std::num::NonZeroUsize;
 
 /// Request that an allocation uses huge pages.
 ///
@@ -103,4 +105,33 @@ impl Default for HugePageSize {
 impl HugePageSize {
     #[cfg(any(target_os = "android", target_os = "linux"))]
     const MAP_HUGE_SHIFT: i32 = 26;
+
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    const MAP_HUGE_MASK: i32 = 0x3F;
+
+    /// Size of a huge page, or `None` for `HugePageSize::None`.
+    ///
+    /// The size of `HugePageSize::Default` depends on the system's configuration; it is assumed to be `2Mb`.
+    #[inline(always)]
+    pub fn page_size(self) -> Option<NonZeroUsize> {
+        #[cfg(any(target_os = "android", target_os = "linux"))]
+        {
+            const ASSUMED_DEFAULT_HUGE_PAGE_SIZE: usize = 2 * 1024 * 1024;
+
+            if self == HugePageSize::None {
+                return None;
+            }
+
+            match ((self as i32) >> Self::MAP_HUGE_SHIFT) & Self::MAP_HUGE_MASK {
+                0 => Some(ASSUMED_DEFAULT_HUGE_PAGE_SIZE.non_zero()),
+
+                logarithm_base2 => Some((1usize << logarithm_base2).non_zero()),
+            }
+        }
+
+        #[cfg(not(any(target_os = "android", target_os = "linux")))]
+        {
+            None
+        }
+    }
 }
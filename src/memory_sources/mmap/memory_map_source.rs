@@ -18,6 +18,10 @@ use std::ptr::null_mut;
 /// When dropped, any memory obtained with this allocator is ***NOT*** freed.
 ///
 /// However, it is appropriate as a 'backing store' for other memory sources.
+///
+/// This memory source is thread-safe (`Send` and `Sync`), so one instance can be shared by the allocators of many threads using an `ArcMemorySource`.
+///
+/// Memory is decommitted using `madvise(MADV_DONTNEED)`, or, if `lazily_decommit()` was used on Android and Linux, `madvise(MADV_FREE)`; it does not need to be recommitted, as it is faulted back in when next accessed.
 #[derive(Debug, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
 pub struct MemoryMapSource {
     map_flags: i32,
@@ -30,6 +34,8 @@ pub struct MemoryMapSource {
 
     #[cfg(any(target_os = "android", target_os = "linux"))]
     numa_settings: Option<NumaSettings>,
+
+    decommit_advice: i32,
 }
 
 impl Default for MemoryMapSource {
@@ -49,6 +55,20 @@ impl MemorySource for MemoryMapSource {
     fn release(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
         Self::munmap_memory(current_memory, non_zero_size.get())
     }
+
+    #[inline(always)]
+    fn decommit(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        let page_size = Self::page_size();
+        let from = current_memory
+            .to_usize()
+            .round_up_to_power_of_two(page_size);
+        let to =
+            (current_memory.to_usize() + non_zero_size.get()).round_down_to_power_of_two(page_size);
+        if likely!(from < to) {
+            // Failure (eg for locked memory) just leaves the memory committed.
+            unsafe { madvise(from as *mut c_void, to - from, self.decommit_advice) };
+        }
+    }
 }
 
 impl MemoryMapSource {
@@ -60,9 +80,11 @@ impl MemoryMapSource {
     /// * `allocate_within_first_32_gb`: Useful for stacks and creating executable code. Only on Android, FreeBSD and Linux on 64-bit CPUs.
     /// * `huge_page_size`: Huge page size to use with Transparent Huge Pages (THP). On operating systems other than Android and Linux, specifying a huge page size has no effect.
     /// * `numa_settings`: NUMA policy settings for optimizing memory allocations to the nearest node. On operating systems other than Android and Linux, specifying a value has no effect.
+    ///
+    /// Usable in `const` and `static` contexts.
     #[allow(unused_variables)]
     #[inline(always)]
-    pub fn new(
+    pub const fn new(
         lock: bool,
         prefault: bool,
         do_not_reserve_swap_space: bool,
@@ -84,9 +106,25 @@ impl MemoryMapSource {
             madvise_flags: Self::madvise_flags(huge_page_size),
             #[cfg(any(target_os = "android", target_os = "linux"))]
             numa_settings,
+            decommit_advice: MADV_DONTNEED,
         }
     }
 
+    /// Decommit memory using `madvise(MADV_FREE)` rather than `madvise(MADV_DONTNEED)`.
+    ///
+    /// This is cheaper, but the operating system only reclaims the memory when under memory pressure, so the resident set size (RSS) does not fall immediately.
+    ///
+    /// Only on Android and Linux; elsewhere, this has no effect.
+    #[allow(unused_mut)]
+    #[inline(always)]
+    pub fn lazily_decommit(mut self) -> Self {
+        #[cfg(any(target_os = "android", target_os = "linux"))]
+        {
+            self.decommit_advice = MADV_FREE;
+        }
+        self
+    }
+
     /// Configure with NUMA settings passed down
     #[inline(always)]
     pub fn with_numa_settings(ns: NumaSettings) -> Self {
@@ -223,6 +261,17 @@ impl MemoryMapSource {
         unsafe { munmap(memory_address.as_ptr() as *mut _, size) };
     }
 
+    #[inline(always)]
+    fn page_size() -> NonZeroUsize {
+        const ASSUMED_PAGE_SIZE: usize = 4096;
+
+        match unsafe { sysconf(_SC_PAGESIZE) } {
+            page_size if likely!(page_size > 0) => (page_size as usize).non_zero(),
+
+            _ => ASSUMED_PAGE_SIZE.non_zero(),
+        }
+    }
+
     #[inline(always)]
     fn cast_address(address: *mut c_void) -> MemoryAddress {
         address.cast::<u8>().non_null()
@@ -230,7 +279,7 @@ impl MemoryMapSource {
 
     #[allow(unused_variables)]
     #[inline(always)]
-    fn map_flags(
+    const fn map_flags(
         lock: bool,
         prefault: bool,
         do_not_reserve_swap_space: bool,
@@ -318,10 +367,10 @@ impl MemoryMapSource {
 
     #[cfg(any(target_os = "android", target_os = "linux"))]
     #[inline(always)]
-    fn madvise_flags(huge_page_size: HugePageSize) -> i32 {
+    const fn madvise_flags(huge_page_size: HugePageSize) -> i32 {
         const MADVISE_FLAGS: i32 = MADV_DONTDUMP;
 
-        if huge_page_size != HugePageSize::None {
+        if huge_page_size as i32 != HugePageSize::None as i32 {
             MADVISE_FLAGS | MADV_HUGEPAGE
         } else {
             MADVISE_FLAGS
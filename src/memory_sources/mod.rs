@@ -5,7 +5,29 @@ pub mod arena_memory_source;
 #[cfg(unix)]
 pub mod mmap;
 
+/// A memory map (mmap) based memory source backed by a file.
+#[cfg(unix)]
+pub mod file_memory_source;
+
+/// A memory source backed by a `memfd_create()` file, which can be shared with other processes.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+pub mod memfd_memory_source;
+
+/// A memory source which reserves virtual memory up front and commits it on demand.
+#[cfg(unix)]
+pub mod reserved_memory_source;
+
+/// A memory source backed by a named POSIX shared memory segment, which can be shared with other processes.
+#[cfg(unix)]
+pub mod shared_memory_source;
+
+pub mod arc_memory_source;
+pub mod caching_memory_source;
+pub mod failing_memory_source;
+pub mod fixed_buffer_memory_source;
+pub mod locked_memory_source;
 pub mod memory_source;
+pub mod quota_memory_source;
 pub mod rc_memory_source;
 
 pub mod prelude {
@@ -19,6 +41,20 @@ pub mod prelude {
     #[cfg(unix)]
     pub use super::mmap::*;
 
+    pub use super::arc_memory_source::*;
+    pub use super::caching_memory_source::*;
+    pub use super::failing_memory_source::*;
+    #[cfg(unix)]
+    pub use super::file_memory_source::*;
+    pub use super::fixed_buffer_memory_source::*;
+    pub use super::locked_memory_source::*;
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub use super::memfd_memory_source::*;
     pub use super::memory_source::*;
+    pub use super::quota_memory_source::*;
     pub use super::rc_memory_source::*;
+    #[cfg(unix)]
+    pub use super::reserved_memory_source::*;
+    #[cfg(unix)]
+    pub use super::shared_memory_source::*;
 }
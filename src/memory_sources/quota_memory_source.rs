@@ -0,0 +1,190 @@
+use crate::memory_address::MemoryAddress;
+use crate::memory_sources::memory_source::MemorySource;
+use std::alloc::AllocError;
+use std::fmt;
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::num::NonZeroUsize;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::{AcqRel, Acquire, Relaxed, Release};
+
+/// What to do when an obtain would exceed a `QuotaMemorySource`'s limit.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum OutOfQuotaAction {
+    /// Fail the obtain with `AllocError`.
+    Fail,
+
+    /// Memory has been freed (eg by trimming a cache); try the obtain again.
+    Retry,
+}
+
+/// Called when an obtain would exceed a `QuotaMemorySource`'s limit with the size requested, the bytes in use and the limit.
+pub type OutOfQuotaCallback =
+    Box<dyn Fn(NonZeroUsize, usize, usize) -> OutOfQuotaAction + Send + Sync>;
+
+/// A memory source which enforces a hard limit on the bytes obtained through it from an underlying memory source; eg a per-tenant memory limit.
+///
+/// An obtain which would exceed the limit fails with `AllocError`, unless an out-of-memory callback frees memory and asks for a retry.
+///
+/// Quotas are hierarchical by nesting: a child quota is a `QuotaMemorySource` whose underlying memory source is its parent, so memory obtained through the child counts against both.
+/// To give a parent more than one child, share it using a `RcMemorySource`.
+///
+/// This memory source is thread-safe if the underlying memory source is.
+pub struct QuotaMemorySource<MS: MemorySource> {
+    limit: AtomicUsize,
+    used: AtomicUsize,
+    out_of_quota: Option<OutOfQuotaCallback>,
+    memory_source: MS,
+}
+
+impl<MS: MemorySource> Debug for QuotaMemorySource<MS> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "QuotaMemorySource({:?}, {}, {})",
+            self.memory_source,
+            self.used.load(Relaxed),
+            self.limit.load(Relaxed)
+        )
+    }
+}
+
+impl<MS: MemorySource> MemorySource for QuotaMemorySource<MS> {
+    #[inline(always)]
+    fn obtain(&self, non_zero_size: NonZeroUsize) -> Result<MemoryAddress, AllocError> {
+        self.take_from_quota(non_zero_size)?;
+
+        let result = self.memory_source.obtain(non_zero_size);
+        if unlikely!(result.is_err()) {
+            self.return_to_quota(non_zero_size)
+        }
+        result
+    }
+
+    #[inline(always)]
+    fn release(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        self.memory_source.release(non_zero_size, current_memory);
+        self.return_to_quota(non_zero_size)
+    }
+
+    #[inline(always)]
+    fn decommit(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        self.memory_source.decommit(non_zero_size, current_memory)
+    }
+
+    #[inline(always)]
+    fn recommit(
+        &self,
+        non_zero_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> Result<(), AllocError> {
+        self.memory_source.recommit(non_zero_size, current_memory)
+    }
+}
+
+impl<MS: MemorySource> QuotaMemorySource<MS> {
+    /// Number of times the out-of-quota callback is called for one obtain before it fails.
+    pub const MAXIMUM_RETRIES: usize = 8;
+
+    /// Creates a new instance which allows at most `limit` bytes to be obtained from `memory_source` at once.
+    #[inline(always)]
+    pub fn new(memory_source: MS, limit: usize) -> Self {
+        Self {
+            limit: AtomicUsize::new(limit),
+            used: AtomicUsize::new(0),
+            out_of_quota: None,
+            memory_source,
+        }
+    }
+
+    /// Creates a new instance which calls `out_of_quota` when an obtain would exceed `limit`.
+    ///
+    /// `out_of_quota` may free memory obtained from this instance (eg by trimming a cache) and return `OutOfQuotaAction::Retry`; it is called again, up to `MAXIMUM_RETRIES` times, if the obtain would still exceed the limit.
+    /// It is called whilst obtaining memory, so it must not itself obtain memory from this instance.
+    #[inline(always)]
+    pub fn with_out_of_quota_callback(
+        memory_source: MS,
+        limit: usize,
+        out_of_quota: OutOfQuotaCallback,
+    ) -> Self {
+        Self {
+            out_of_quota: Some(out_of_quota),
+            ..Self::new(memory_source, limit)
+        }
+    }
+
+    /// Bytes obtained and not yet released.
+    #[inline(always)]
+    pub fn used(&self) -> usize {
+        self.used.load(Relaxed)
+    }
+
+    /// The limit.
+    #[inline(always)]
+    pub fn limit(&self) -> usize {
+        self.limit.load(Relaxed)
+    }
+
+    /// Changes the limit.
+    ///
+    /// Lowering the limit below the bytes in use does not release any memory; subsequent obtains fail until enough is released.
+    #[inline(always)]
+    pub fn set_limit(&self, limit: usize) {
+        self.limit.store(limit, Relaxed)
+    }
+
+    /// Bytes which can be obtained before the limit is reached.
+    #[inline(always)]
+    pub fn remaining(&self) -> usize {
+        self.limit().saturating_sub(self.used())
+    }
+
+    /// The underlying memory source (eg the parent quota).
+    #[inline(always)]
+    pub fn memory_source(&self) -> &MS {
+        &self.memory_source
+    }
+
+    #[inline(always)]
+    fn take_from_quota(&self, non_zero_size: NonZeroUsize) -> Result<(), AllocError> {
+        let mut retries = 0;
+        let mut used = self.used.load(Acquire);
+        loop {
+            let limit = self.limit.load(Relaxed);
+            match used.checked_add(non_zero_size.get()) {
+                Some(now_used) if likely!(now_used <= limit) => {
+                    match self
+                        .used
+                        .compare_exchange_weak(used, now_used, AcqRel, Acquire)
+                    {
+                        Ok(_) => return Ok(()),
+
+                        Err(was) => used = was,
+                    }
+                }
+
+                _ => {
+                    let out_of_quota = match self.out_of_quota {
+                        Some(ref out_of_quota) if retries < Self::MAXIMUM_RETRIES => out_of_quota,
+
+                        _ => return Err(AllocError),
+                    };
+
+                    match out_of_quota(non_zero_size, used, limit) {
+                        OutOfQuotaAction::Fail => return Err(AllocError),
+
+                        OutOfQuotaAction::Retry => {
+                            retries += 1;
+                            used = self.used.load(Acquire)
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn return_to_quota(&self, non_zero_size: NonZeroUsize) {
+        self.used.fetch_sub(non_zero_size.get(), Release);
+    }
+}
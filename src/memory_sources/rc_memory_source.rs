@@ -1,4 +1,5 @@
 use crate::allocators::global::global_switchable_allocator::GlobalSwitchableAllocator;
+use crate::allocators::global::memory_range::MemoryRange;
 use crate::memory_address::MemoryAddress;
 use crate::memory_sources::memory_source::MemorySource;
 use std::alloc::AllocError;
@@ -38,9 +39,44 @@ impl<MS: MemorySource> MemorySource for RcMemorySource<MS> {
     fn release(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
         self.0.release(non_zero_size, current_memory)
     }
+    #[inline(always)]
+    fn decommit(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        self.0.decommit(non_zero_size, current_memory)
+    }
+
+    #[inline(always)]
+    fn recommit(
+        &self,
+        non_zero_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> Result<(), AllocError> {
+        self.0.recommit(non_zero_size, current_memory)
+    }
+
+    #[inline(always)]
+    fn extend(
+        &self,
+        current_memory: MemoryAddress,
+        non_zero_current_size: NonZeroUsize,
+        non_zero_new_size: NonZeroUsize,
+    ) -> Result<(), AllocError> {
+        self.0
+            .extend(current_memory, non_zero_current_size, non_zero_new_size)
+    }
+
+    #[inline(always)]
+    fn reservation(&self) -> Option<MemoryRange> {
+        self.0.reservation()
+    }
 }
 
 impl<MS: MemorySource> RcMemorySource<MS> {
+    /// Creates a new instance, allocating from the current allocator in use.
+    #[inline(always)]
+    pub fn new(underlying_memory_source: MS) -> Self {
+        Self(Rc::new(underlying_memory_source))
+    }
+
     /// Creates a new thread-local instance.
     #[inline(always)]
     pub fn new_thread_local<GTACSA: GlobalSwitchableAllocator>(
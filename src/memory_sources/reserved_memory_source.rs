@@ -0,0 +1,229 @@
+use crate::allocators::global::memory_range::MemoryRange;
+use crate::extensions::prelude::*;
+use crate::memory_address::MemoryAddress;
+use crate::memory_sources::memory_source::MemorySource;
+use libc::*;
+use std::alloc::AllocError;
+use std::cell::Cell;
+use std::io;
+use std::num::NonZeroUsize;
+use std::ptr::null_mut;
+
+/// A memory source which reserves one large, contiguous range of virtual addresses up front (`PROT_NONE`, `MAP_NORESERVE`), and commits pages of it on demand with `mprotect()`.
+///
+/// Each obtain commits the next, page-aligned, region of the reservation, directly after the previous one, so the most recently obtained memory can be extended in place with `MemorySource::extend()` rather than moved.
+/// As the whole reservation is known from the start (`MemorySource::reservation()`), an allocator using it can use that as its `LocalAllocator::memory_range()`, which never changes; see `BumpAllocator::new_growable()`.
+///
+/// Releasing memory decommits it; its addresses are only reused if it was the most recently obtained.
+/// Decommitting memory (see `MemorySource::decommit()`) makes it inaccessible, so it must be recommitted before it is used again.
+///
+/// When dropped, the whole reservation is unmapped, including any memory obtained.
+///
+/// This memory source is not thread-safe.
+///
+/// Only on Unix.
+#[derive(Debug)]
+pub struct ReservedMemorySource {
+    reservation_starts_at: MemoryAddress,
+    reservation_size: NonZeroUsize,
+    page_size: NonZeroUsize,
+    next_obtain_at_offset: Cell<usize>,
+}
+
+impl Drop for ReservedMemorySource {
+    #[inline(always)]
+    fn drop(&mut self) {
+        unsafe {
+            munmap(
+                self.reservation_starts_at.as_ptr() as *mut c_void,
+                self.reservation_size.get(),
+            )
+        };
+    }
+}
+
+impl MemorySource for ReservedMemorySource {
+    #[inline(always)]
+    fn obtain(&self, non_zero_size: NonZeroUsize) -> Result<MemoryAddress, AllocError> {
+        let size = non_zero_size.round_up_to_power_of_two(self.page_size).get();
+        let offset = self.next_obtain_at_offset.get();
+        let ends_at_offset = self.ends_at_offset(offset, size)?;
+
+        let memory = self.reservation_starts_at.add(offset);
+        self.commit(memory, size)?;
+
+        self.next_obtain_at_offset.set(ends_at_offset);
+        Ok(memory)
+    }
+
+    #[inline(always)]
+    fn release(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        let size = non_zero_size.round_up_to_power_of_two(self.page_size).get();
+        self.decommit_pages(current_memory.to_usize(), current_memory.to_usize() + size);
+
+        let offset = current_memory.difference(self.reservation_starts_at);
+        if offset + size == self.next_obtain_at_offset.get() {
+            self.next_obtain_at_offset.set(offset)
+        }
+    }
+
+    #[inline(always)]
+    fn decommit(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        let from = current_memory
+            .to_usize()
+            .round_up_to_power_of_two(self.page_size);
+        let to = (current_memory.to_usize() + non_zero_size.get())
+            .round_down_to_power_of_two(self.page_size);
+        self.decommit_pages(from, to)
+    }
+
+    #[inline(always)]
+    fn recommit(
+        &self,
+        non_zero_size: NonZeroUsize,
+        current_memory: MemoryAddress,
+    ) -> Result<(), AllocError> {
+        let from = current_memory
+            .to_usize()
+            .round_down_to_power_of_two(self.page_size);
+        let to = (current_memory.to_usize() + non_zero_size.get())
+            .round_up_to_power_of_two(self.page_size);
+        self.commit(MemoryAddress::from_usize(from), to - from)
+    }
+
+    /// Commits the pages after `current_memory`.
+    ///
+    /// Fails if `current_memory` was not the most recently obtained memory, or if the reservation is exhausted.
+    #[inline(always)]
+    fn extend(
+        &self,
+        current_memory: MemoryAddress,
+        non_zero_current_size: NonZeroUsize,
+        non_zero_new_size: NonZeroUsize,
+    ) -> Result<(), AllocError> {
+        let current_size = non_zero_current_size
+            .round_up_to_power_of_two(self.page_size)
+            .get();
+        let new_size = non_zero_new_size
+            .round_up_to_power_of_two(self.page_size)
+            .get();
+
+        let offset = current_memory.difference(self.reservation_starts_at);
+        let current_ends_at_offset = offset + current_size;
+        if unlikely!(current_ends_at_offset != self.next_obtain_at_offset.get()) {
+            return Err(AllocError);
+        }
+        if new_size <= current_size {
+            return Ok(());
+        }
+
+        let ends_at_offset = self.ends_at_offset(offset, new_size)?;
+        self.commit(
+            self.reservation_starts_at.add(current_ends_at_offset),
+            new_size - current_size,
+        )?;
+
+        self.next_obtain_at_offset.set(ends_at_offset);
+        Ok(())
+    }
+
+    /// The whole reservation, whether committed or not.
+    #[inline(always)]
+    fn reservation(&self) -> Option<MemoryRange> {
+        Some(MemoryRange::new(
+            self.reservation_starts_at,
+            self.reservation_starts_at
+                .add_non_zero(self.reservation_size),
+        ))
+    }
+}
+
+impl ReservedMemorySource {
+    /// Creates a new instance, reserving `reservation_size` bytes (rounded up to the page size) of virtual addresses.
+    ///
+    /// No memory is committed until it is obtained.
+    #[inline(always)]
+    pub fn new(reservation_size: NonZeroUsize) -> io::Result<Self> {
+        const UNUSED_FILE_DESCRIPTOR: c_int = -1;
+        const NO_OFFSET: off_t = 0;
+
+        #[cfg(any(target_os = "android", target_os = "linux", target_os = "netbsd"))]
+        const DO_NOT_RESERVE_SWAP_SPACE: c_int = MAP_NORESERVE;
+        #[cfg(not(any(target_os = "android", target_os = "linux", target_os = "netbsd")))]
+        const DO_NOT_RESERVE_SWAP_SPACE: c_int = 0;
+
+        let page_size = unsafe { sysconf(_SC_PAGESIZE) };
+        if unlikely!(page_size <= 0) {
+            return Err(io::Error::last_os_error());
+        }
+        let page_size = (page_size as usize).non_zero();
+        let reservation_size = reservation_size.round_up_to_power_of_two(page_size);
+
+        let result = unsafe {
+            mmap(
+                null_mut(),
+                reservation_size.get(),
+                PROT_NONE,
+                MAP_PRIVATE | MAP_ANONYMOUS | DO_NOT_RESERVE_SWAP_SPACE,
+                UNUSED_FILE_DESCRIPTOR,
+                NO_OFFSET,
+            )
+        };
+        if unlikely!(result == MAP_FAILED) {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self {
+            reservation_starts_at: result.cast::<u8>().non_null(),
+            reservation_size,
+            page_size,
+            next_obtain_at_offset: Cell::new(0),
+        })
+    }
+
+    /// Number of bytes of the reservation obtained (including any since decommitted).
+    #[inline(always)]
+    pub fn obtained(&self) -> usize {
+        self.next_obtain_at_offset.get()
+    }
+
+    /// The page size; memory is committed and decommitted in multiples of it.
+    #[inline(always)]
+    pub fn page_size(&self) -> NonZeroUsize {
+        self.page_size
+    }
+
+    #[inline(always)]
+    fn ends_at_offset(&self, offset: usize, size: usize) -> Result<usize, AllocError> {
+        match offset.checked_add(size) {
+            Some(ends_at_offset) if likely!(ends_at_offset <= self.reservation_size.get()) => {
+                Ok(ends_at_offset)
+            }
+
+            _ => Err(AllocError),
+        }
+    }
+
+    #[inline(always)]
+    fn commit(&self, memory: MemoryAddress, size: usize) -> Result<(), AllocError> {
+        let result =
+            unsafe { mprotect(memory.as_ptr() as *mut c_void, size, PROT_READ | PROT_WRITE) };
+        if unlikely!(result != 0) {
+            Err(AllocError)
+        } else {
+            Ok(())
+        }
+    }
+
+    #[inline(always)]
+    fn decommit_pages(&self, from: usize, to: usize) {
+        if likely!(from < to) {
+            let address = from as *mut c_void;
+            let size = to - from;
+            unsafe {
+                madvise(address, size, MADV_DONTNEED);
+                mprotect(address, size, PROT_NONE);
+            }
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use crate::allocators::locked::allocator_lock::AllocatorLock;
+use crate::extensions::prelude::*;
+use crate::memory_address::MemoryAddress;
+use crate::memory_sources::memory_source::MemorySource;
+use libc::*;
+use std::alloc::AllocError;
+use std::cell::Cell;
+use std::ffi::CStr;
+use std::io;
+use std::mem::{align_of, size_of, MaybeUninit};
+use std::num::NonZeroUsize;
+use std::ptr::null_mut;
+
+/// A memory source which maps a named POSIX shared memory segment (`shm_open()`, `MAP_SHARED`), so that two or more processes can share one heap; eg one `BitSetAllocator`.
+///
+/// One process creates the segment with `create()`, and others map it with `open()`, optionally at a fixed address.
+/// Each obtain bumps through the segment, starting after its header, so obtaining the same sequence of sizes in each process obtains the same regions of the segment (eg one process uses `BitSetAllocator::new()` and others `BitSetAllocator::attach()`).
+/// Released memory is not reused.
+///
+/// The segment can be mapped at a different address in each process; store offsets (`offset_of()`), not addresses, in shared memory and convert them back with `address_of()`.
+///
+/// The segment's header holds a process-shared lock, `lock()`, to use with a `ProcessSharedLockedAllocator`; a reference to this memory source is also a memory source, so that the allocator can borrow both.
+///
+/// When dropped, the segment is unmapped; it persists until removed with `unlink()`.
+///
+/// This memory source is not thread-safe.
+///
+/// Only on Unix.
+#[derive(Debug)]
+pub struct SharedMemorySource {
+    mapped_at: MemoryAddress,
+    size: NonZeroUsize,
+    page_size: NonZeroUsize,
+    next_obtain_at_offset: Cell<usize>,
+}
+
+impl Drop for SharedMemorySource {
+    #[inline(always)]
+    fn drop(&mut self) {
+        unsafe { munmap(self.mapped_at.as_ptr() as *mut c_void, self.size.get()) };
+    }
+}
+
+impl MemorySource for SharedMemorySource {
+    #[inline(always)]
+    fn obtain(&self, non_zero_size: NonZeroUsize) -> Result<MemoryAddress, AllocError> {
+        let size = non_zero_size.round_up_to_power_of_two(self.page_size).get();
+        let offset = self.next_obtain_at_offset.get();
+        let ends_at_offset = offset.checked_add(size).ok_or(AllocError)?;
+        if unlikely!(ends_at_offset > self.size.get()) {
+            return Err(AllocError);
+        }
+
+        self.next_obtain_at_offset.set(ends_at_offset);
+        Ok(self.mapped_at.add(offset))
+    }
+
+    #[inline(always)]
+    fn release(&self, _non_zero_size: NonZeroUsize, _current_memory: MemoryAddress) {}
+}
+
+impl<'a> MemorySource for &'a SharedMemorySource {
+    #[inline(always)]
+    fn obtain(&self, non_zero_size: NonZeroUsize) -> Result<MemoryAddress, AllocError> {
+        (*self).obtain(non_zero_size)
+    }
+
+    #[inline(always)]
+    fn release(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+        (*self).release(non_zero_size, current_memory)
+    }
+}
+
+impl SharedMemorySource {
+    /// Creates a new shared memory segment named `name` (eg `/my-heap`) of `size` bytes, including a header of one page, and maps it.
+    ///
+    /// Fails if a segment named `name` already exists.
+    ///
+    /// * `mode`: Permissions of the segment, eg `0o600`.
+    /// * `fixed_address`: Address to map the segment at, or `None` to let the operating system choose one; fails if the segment can not be mapped there.
+    #[inline(always)]
+    pub fn create(
+        name: &CStr,
+        size: NonZeroUsize,
+        mode: mode_t,
+        fixed_address: Option<MemoryAddress>,
+    ) -> io::Result<Self> {
+        let page_size = Self::page_size()?;
+        let size = size.round_up_to_power_of_two(page_size);
+
+        let file_descriptor = Self::shm_open(name, O_RDWR | O_CREAT | O_EXCL, mode)?;
+        if unlikely!(unsafe { ftruncate(file_descriptor, size.get() as off_t) } != 0) {
+            let error = io::Error::last_os_error();
+            unsafe {
+                close(file_descriptor);
+                shm_unlink(name.as_ptr());
+            }
+            return Err(error);
+        }
+
+        Self::map(file_descriptor, size, page_size, fixed_address)
+    }
+
+    /// Maps an existing shared memory segment named `name`, created by `create()`, eg in another process.
+    ///
+    /// * `fixed_address`: Address to map the segment at, or `None` to let the operating system choose one; fails if the segment can not be mapped there.
+    #[inline(always)]
+    pub fn open(name: &CStr, fixed_address: Option<MemoryAddress>) -> io::Result<Self> {
+        let page_size = Self::page_size()?;
+
+        let file_descriptor = Self::shm_open(name, O_RDWR, 0)?;
+        let mut stat = MaybeUninit::<stat>::uninit();
+        if unlikely!(unsafe { fstat(file_descriptor, stat.as_mut_ptr()) } != 0) {
+            let error = io::Error::last_os_error();
+            unsafe { close(file_descriptor) };
+            return Err(error);
+        }
+        let size = unsafe { stat.assume_init() }.st_size as usize;
+        if unlikely!(size <= page_size.get()) {
+            unsafe { close(file_descriptor) };
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Shared memory segment is too small",
+            ));
+        }
+
+        Self::map(file_descriptor, size.non_zero(), page_size, fixed_address)
+    }
+
+    /// Removes the shared memory segment named `name`; it is freed once all processes have unmapped it.
+    #[inline(always)]
+    pub fn unlink(name: &CStr) -> io::Result<()> {
+        if unlikely!(unsafe { shm_unlink(name.as_ptr()) } != 0) {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The process-shared lock held in the segment's header.
+    ///
+    /// The header is all zero bytes when the segment is created; all locks in this crate, such as `ProcessSharedFutexMutex` and `SpinLock`, are unlocked when all zero bytes.
+    /// All processes must use the same type of lock.
+    #[inline(always)]
+    pub fn lock<L: AllocatorLock>(&self) -> &L {
+        assert!(
+            size_of::<L>() <= self.page_size.get() && align_of::<L>() <= self.page_size.get(),
+            "Lock does not fit in header"
+        );
+
+        unsafe { &*(self.mapped_at.as_ptr() as *const L) }
+    }
+
+    /// Address the segment is mapped at in this process.
+    #[inline(always)]
+    pub fn mapped_at(&self) -> MemoryAddress {
+        self.mapped_at
+    }
+
+    /// Size of the segment, including its header.
+    #[inline(always)]
+    pub fn size(&self) -> NonZeroUsize {
+        self.size
+    }
+
+    /// The offset of `memory` in the segment, which is the same in all processes; or `None` if `memory` is not in the segment.
+    #[inline(always)]
+    pub fn offset_of(&self, memory: MemoryAddress) -> Option<usize> {
+        if likely!(memory >= self.mapped_at) {
+            let offset = memory.difference(self.mapped_at);
+            if likely!(offset < self.size.get()) {
+                return Some(offset);
+            }
+        }
+        None
+    }
+
+    /// The address, in this process, of `offset` in the segment; or `None` if `offset` is not in the segment.
+    #[inline(always)]
+    pub fn address_of(&self, offset: usize) -> Option<MemoryAddress> {
+        if likely!(offset < self.size.get()) {
+            Some(self.mapped_at.add(offset))
+        } else {
+            None
+        }
+    }
+
+    #[inline(always)]
+    fn map(
+        file_descriptor: c_int,
+        size: NonZeroUsize,
+        page_size: NonZeroUsize,
+        fixed_address: Option<MemoryAddress>,
+    ) -> io::Result<Self> {
+        let address_hint = match fixed_address {
+            None => null_mut(),
+            Some(fixed_address) => fixed_address.as_ptr() as *mut c_void,
+        };
+
+        // `MAP_FIXED` is not used, as it would silently replace any existing mapping.
+        let result = unsafe {
+            mmap(
+                address_hint,
+                size.get(),
+                PROT_READ | PROT_WRITE,
+                MAP_SHARED,
+                file_descriptor,
+                0,
+            )
+        };
+        let error = io::Error::last_os_error();
+        unsafe { close(file_descriptor) };
+
+        if unlikely!(result == MAP_FAILED) {
+            return Err(error);
+        }
+        if unlikely!(fixed_address.is_some() && result != address_hint) {
+            unsafe { munmap(result, size.get()) };
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                "Shared memory segment could not be mapped at fixed address",
+            ));
+        }
+
+        Ok(Self {
+            mapped_at: result.cast::<u8>().non_null(),
+            size,
+            page_size,
+            next_obtain_at_offset: Cell::new(page_size.get()),
+        })
+    }
+
+    #[inline(always)]
+    fn shm_open(name: &CStr, open_flags: c_int, mode: mode_t) -> io::Result<c_int> {
+        let file_descriptor = unsafe { shm_open(name.as_ptr(), open_flags | O_CLOEXEC, mode) };
+        if unlikely!(file_descriptor == -1) {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(file_descriptor)
+        }
+    }
+
+    #[inline(always)]
+    fn page_size() -> io::Result<NonZeroUsize> {
+        let page_size = unsafe { sysconf(_SC_PAGESIZE) };
+        if unlikely!(page_size <= 0) {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok((page_size as usize).non_zero())
+        }
+    }
+}
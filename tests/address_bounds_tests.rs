@@ -0,0 +1,55 @@
+#![feature(allocator_api)]
+
+#[cfg(test)]
+mod address_bounds_tests {
+
+    use allocator_suite::allocators::global::prelude::*;
+    use allocator_suite::extensions::non_null_u8_ext::NonNullU8Ext;
+    use allocator_suite::memory_address::MemoryAddress;
+
+    fn memory_range(from: usize, to: usize) -> MemoryRange {
+        MemoryRange::new(
+            MemoryAddress::from_usize(from),
+            MemoryAddress::from_usize(to),
+        )
+    }
+
+    fn address(value: usize) -> MemoryAddress {
+        MemoryAddress::from_usize(value)
+    }
+
+    #[test]
+    pub fn empty_bounds_contain_nothing() {
+        let bounds = AddressBounds::new();
+
+        assert!(!bounds.may_contain(address(1)));
+        assert!(!bounds.may_contain(address(usize::MAX)));
+    }
+
+    #[test]
+    pub fn widening_covers_all_memory_ranges() {
+        let bounds = AddressBounds::new();
+        bounds.widen(memory_range(0x2000, 0x3000));
+        bounds.widen(memory_range(0x8000, 0x9000));
+
+        assert!(!bounds.may_contain(address(0x1fff)));
+        assert!(bounds.may_contain(address(0x2000)));
+        assert!(bounds.may_contain(address(0x5000)));
+        assert!(bounds.may_contain(address(0x8fff)));
+        assert!(!bounds.may_contain(address(0x9000)));
+    }
+
+    #[test]
+    pub fn narrowing_covers_only_remaining_memory_ranges() {
+        let bounds = AddressBounds::new();
+        bounds.widen(memory_range(0x2000, 0x3000));
+        bounds.widen(memory_range(0x8000, 0x9000));
+
+        bounds.narrow(vec![memory_range(0x8000, 0x9000)].into_iter());
+        assert!(!bounds.may_contain(address(0x2000)));
+        assert!(bounds.may_contain(address(0x8000)));
+
+        bounds.narrow(Vec::new().into_iter());
+        assert!(!bounds.may_contain(address(0x8000)));
+    }
+}
@@ -0,0 +1,77 @@
+#![feature(allocator_api)]
+
+#[cfg(test)]
+mod arc_memory_source_tests {
+
+    use allocator_suite::allocators::prelude::*;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+    use allocator_suite::memory_sources::prelude::*;
+    use std::thread;
+
+    const SIZE: usize = 64 * 1024;
+
+    fn assert_send_and_sync<T: Send + Sync>() {}
+
+    #[test]
+    pub fn thread_safe_memory_sources_are_send_and_sync() {
+        assert_send_and_sync::<MemoryMapSource>();
+        assert_send_and_sync::<ArcMemorySource<MemoryMapSource>>();
+        assert_send_and_sync::<LockedMemorySource<ReservedMemorySource>>();
+        assert_send_and_sync::<ArcMemorySource<LockedMemorySource<ReservedMemorySource>>>();
+    }
+
+    #[test]
+    pub fn allocators_of_many_threads_share_one_memory_map_source() {
+        let memory_source = ArcMemorySource::new(MemoryMapSource::default());
+
+        let threads = (0..4)
+            .map(|_| {
+                let memory_source = memory_source.clone();
+                thread::spawn(move || {
+                    let allocator = BumpAllocator::new(memory_source, SIZE.non_zero())
+                        .expect("Did not create allocator");
+                    let memory = allocator
+                        .allocate(64.non_zero(), 8.non_zero())
+                        .expect("Did not allocate");
+                    unsafe { memory.as_ptr().write_bytes(0xAA, 64) };
+                    allocator.deallocate(64.non_zero(), 8.non_zero(), memory);
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for thread in threads {
+            thread.join().expect("Thread panicked");
+        }
+    }
+
+    #[test]
+    pub fn locked_memory_source_serializes_obtains_from_many_threads() {
+        let memory_source = ArcMemorySource::new(LockedMemorySource::<_, SpinLock>::new(
+            ReservedMemorySource::new((64 * SIZE).non_zero()).expect("Did not reserve"),
+        ));
+
+        let threads = (0..4)
+            .map(|_| {
+                let memory_source = memory_source.clone();
+                thread::spawn(move || {
+                    (0..8)
+                        .map(|_| {
+                            memory_source
+                                .obtain(SIZE.non_zero())
+                                .expect("Did not obtain")
+                                .as_ptr() as usize
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut obtained = threads
+            .into_iter()
+            .flat_map(|thread| thread.join().expect("Thread panicked"))
+            .collect::<Vec<_>>();
+        obtained.sort_unstable();
+        obtained.dedup();
+        assert_eq!(obtained.len(), 32, "Regions were obtained more than once");
+    }
+}
@@ -0,0 +1,92 @@
+#![feature(allocator_api)]
+
+#[cfg(test)]
+mod arena_pool_tests {
+
+    use allocator_suite::allocators::global::local_allocator::LocalAllocator;
+    use allocator_suite::allocators::prelude::*;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+
+    use allocator_suite::memory_sources::mmap::memory_map_source::MemoryMapSource;
+
+    const ARENA_SIZE: usize = 64 * 1024;
+
+    const NUMBER_OF_ARENAS: usize = 4;
+
+    #[test]
+    pub fn checks_out_each_arena_once() {
+        let mut pool = new_pool::<BumpAllocator<_>>();
+
+        let checked_out = (0..NUMBER_OF_ARENAS)
+            .map(|_| pool.checkout().expect("Did not check out"))
+            .collect::<Vec<_>>();
+        assert!(pool.checkout().is_none());
+
+        for (index, allocator) in checked_out.iter().enumerate() {
+            let memory = allocator
+                .allocate(64.non_zero(), 8.non_zero())
+                .expect("Did not allocate");
+            assert!(checked_out
+                .iter()
+                .enumerate()
+                .all(|(other_index, other)| (other_index == index) == other.contains(memory)));
+        }
+
+        for allocator in checked_out {
+            pool.checkin(allocator);
+        }
+        assert_eq!(pool.available(), NUMBER_OF_ARENAS);
+    }
+
+    #[test]
+    pub fn checkin_resets_bump_allocator() {
+        let mut pool = new_pool::<BumpAllocator<_>>();
+
+        let allocator = pool.checkout().expect("Did not check out");
+        let first = allocator
+            .allocate(64.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        allocator
+            .allocate(64.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        pool.checkin(allocator);
+
+        let allocator = pool.checkout().expect("Did not check out");
+        let again = allocator
+            .allocate(64.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        assert_eq!(first, again);
+        pool.checkin(allocator);
+    }
+
+    #[test]
+    pub fn checkin_resets_bit_set_allocator() {
+        let mut pool = new_pool::<BitSetAllocator<_>>();
+
+        let allocator = pool.checkout().expect("Did not check out");
+        let first = allocator
+            .allocate(64.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        allocator
+            .allocate(64.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        pool.checkin(allocator);
+
+        let allocator = pool.checkout().expect("Did not check out");
+        let again = allocator
+            .allocate(64.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        assert_eq!(first, again);
+        pool.checkin(allocator);
+    }
+
+    fn new_pool<A: ArenaAllocator<PooledArenaMemorySource<MemoryMapSource>>>(
+    ) -> ArenaPool<A, MemoryMapSource> {
+        ArenaPool::new(
+            MemoryMapSource::default(),
+            ARENA_SIZE.non_zero(),
+            NUMBER_OF_ARENAS.non_zero(),
+        )
+        .expect("Did not create pool")
+    }
+}
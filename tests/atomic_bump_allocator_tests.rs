@@ -0,0 +1,105 @@
+#![feature(allocator_api)]
+
+#[cfg(test)]
+mod atomic_bump_allocator_tests {
+
+    use allocator_suite::allocators::prelude::*;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+
+    use allocator_suite::memory_sources::mmap::memory_map_source::MemoryMapSource;
+    use std::alloc::AllocError;
+    use std::sync::Arc;
+    use std::thread;
+
+    const NUMBER_OF_THREADS: usize = 4;
+
+    const ALLOCATIONS_PER_THREAD: usize = 256;
+
+    #[test]
+    pub fn allocations_from_many_threads_do_not_overlap() {
+        let allocator = Arc::new(new_allocator(
+            NUMBER_OF_THREADS * ALLOCATIONS_PER_THREAD * 16,
+        ));
+
+        let threads = (0..NUMBER_OF_THREADS)
+            .map(|_| {
+                let allocator = allocator.clone();
+                thread::spawn(move || {
+                    (0..ALLOCATIONS_PER_THREAD)
+                        .map(|_| {
+                            allocator
+                                .allocate(16.non_zero(), 16.non_zero())
+                                .expect("Did not allocate")
+                                .as_ptr() as usize
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut allocations = threads
+            .into_iter()
+            .flat_map(|thread| thread.join().expect("Thread panicked"))
+            .collect::<Vec<_>>();
+        allocations.sort();
+        allocations.dedup();
+        assert_eq!(
+            allocations.len(),
+            NUMBER_OF_THREADS * ALLOCATIONS_PER_THREAD,
+            "Allocations overlapped"
+        );
+
+        assert_eq!(
+            allocator.allocate(1.non_zero(), 1.non_zero()),
+            Err(AllocError),
+            "Allocator was not empty"
+        );
+    }
+
+    #[test]
+    pub fn grow_and_shrink_most_recent_allocation_in_place() {
+        let allocator = new_allocator(4096);
+
+        let allocation = allocator
+            .allocate(64.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+
+        let reallocation = allocator
+            .growing_reallocate(128.non_zero(), 8.non_zero(), 64.non_zero(), allocation)
+            .expect("Did not reallocate");
+        assert_eq!(allocation, reallocation, "Did not grow in place");
+
+        let reallocation = allocator
+            .shrinking_reallocate(32.non_zero(), 8.non_zero(), 128.non_zero(), allocation)
+            .expect("Did not reallocate");
+        assert_eq!(allocation, reallocation, "Did not shrink in place");
+
+        allocator.deallocate(32.non_zero(), 8.non_zero(), allocation);
+        let allocation_after_deallocation = allocator
+            .allocate(64.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        assert_eq!(
+            allocation, allocation_after_deallocation,
+            "Did not reclaim most recent allocation"
+        );
+    }
+
+    #[test]
+    pub fn reset() {
+        let allocator = new_allocator(64);
+
+        let allocation = allocator
+            .allocate(64.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        unsafe { allocator.reset() };
+
+        let allocation_after_reset = allocator
+            .allocate(64.non_zero(), 8.non_zero())
+            .expect("Did not allocate after reset");
+        assert_eq!(allocation, allocation_after_reset, "Did not reset");
+    }
+
+    fn new_allocator(memory_size: usize) -> AtomicBumpAllocator<MemoryMapSource> {
+        AtomicBumpAllocator::new(MemoryMapSource::default(), memory_size.non_zero()).unwrap()
+    }
+}
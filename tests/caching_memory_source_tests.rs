@@ -0,0 +1,187 @@
+#![feature(allocator_api)]
+
+#[cfg(test)]
+mod caching_memory_source_tests {
+
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+    use allocator_suite::memory_address::MemoryAddress;
+    use allocator_suite::memory_sources::prelude::*;
+    use std::alloc::AllocError;
+    use std::cell::Cell;
+    use std::num::NonZeroUsize;
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    const SIZE: usize = 64 * 1024;
+
+    #[test]
+    pub fn reuses_released_memory_of_same_size() {
+        let memory_source = new_memory_source(4 * SIZE, CachedMemoryReuse::AsIs, None);
+
+        let memory = memory_source
+            .obtain(SIZE.non_zero())
+            .expect("Did not obtain");
+        memory_source.release(SIZE.non_zero(), memory);
+        assert_eq!(memory_source.cached_bytes(), SIZE);
+        assert_eq!(memory_source.memory_source().released.get(), 0);
+
+        let again = memory_source
+            .obtain(SIZE.non_zero())
+            .expect("Did not obtain");
+        assert_eq!(again, memory);
+        assert_eq!(memory_source.cached_bytes(), 0);
+        assert_eq!(memory_source.memory_source().obtained.get(), 1);
+
+        let other_size = memory_source
+            .obtain((SIZE + 4096).non_zero())
+            .expect("Did not obtain");
+        assert_eq!(memory_source.memory_source().obtained.get(), 2);
+
+        memory_source.release(SIZE.non_zero(), again);
+        memory_source.release((SIZE + 4096).non_zero(), other_size);
+    }
+
+    #[test]
+    pub fn releases_memory_beyond_budget() {
+        let memory_source = new_memory_source(SIZE, CachedMemoryReuse::AsIs, None);
+
+        let first = memory_source
+            .obtain(SIZE.non_zero())
+            .expect("Did not obtain");
+        let second = memory_source
+            .obtain(SIZE.non_zero())
+            .expect("Did not obtain");
+        memory_source.release(SIZE.non_zero(), first);
+        memory_source.release(SIZE.non_zero(), second);
+
+        assert_eq!(memory_source.cached_bytes(), SIZE);
+        assert_eq!(memory_source.memory_source().released.get(), 1);
+    }
+
+    #[test]
+    pub fn trim_releases_all_retained_memory() {
+        let memory_source = new_memory_source(4 * SIZE, CachedMemoryReuse::AsIs, None);
+
+        let first = memory_source
+            .obtain(SIZE.non_zero())
+            .expect("Did not obtain");
+        let second = memory_source
+            .obtain((2 * SIZE).non_zero())
+            .expect("Did not obtain");
+        memory_source.release(SIZE.non_zero(), first);
+        memory_source.release((2 * SIZE).non_zero(), second);
+        assert_eq!(memory_source.cached_bytes(), 3 * SIZE);
+
+        memory_source.trim();
+        assert_eq!(memory_source.cached_bytes(), 0);
+        assert_eq!(memory_source.memory_source().released.get(), 2);
+    }
+
+    #[test]
+    pub fn releases_memory_retained_for_longer_than_maximum_age() {
+        let memory_source = new_memory_source(
+            4 * SIZE,
+            CachedMemoryReuse::AsIs,
+            Some(Duration::from_millis(10)),
+        );
+
+        let memory = memory_source
+            .obtain(SIZE.non_zero())
+            .expect("Did not obtain");
+        memory_source.release(SIZE.non_zero(), memory);
+        sleep(Duration::from_millis(50));
+
+        let again = memory_source
+            .obtain(SIZE.non_zero())
+            .expect("Did not obtain");
+        assert_eq!(memory_source.memory_source().released.get(), 1);
+        assert_eq!(memory_source.memory_source().obtained.get(), 2);
+        memory_source.release(SIZE.non_zero(), again);
+    }
+
+    #[test]
+    pub fn zeroes_reused_memory() {
+        let memory_source = new_memory_source(4 * SIZE, CachedMemoryReuse::Zeroed, None);
+
+        let memory = memory_source
+            .obtain(SIZE.non_zero())
+            .expect("Did not obtain");
+        unsafe { memory.as_ptr().write_bytes(0x77, SIZE) };
+        memory_source.release(SIZE.non_zero(), memory);
+
+        let again = memory_source
+            .obtain(SIZE.non_zero())
+            .expect("Did not obtain");
+        assert!((0..SIZE).all(|index| unsafe { *again.as_ptr().add(index) } == 0));
+    }
+
+    #[test]
+    pub fn decommitted_memory_is_usable_when_reused() {
+        let memory_source = new_memory_source(4 * SIZE, CachedMemoryReuse::Decommitted, None);
+
+        let memory = memory_source
+            .obtain(SIZE.non_zero())
+            .expect("Did not obtain");
+        memory_source.release(SIZE.non_zero(), memory);
+
+        let again = memory_source
+            .obtain(SIZE.non_zero())
+            .expect("Did not obtain");
+        unsafe { again.as_ptr().write_bytes(0x88, SIZE) };
+    }
+
+    fn new_memory_source(
+        maximum_cached_bytes: usize,
+        reuse: CachedMemoryReuse,
+        maximum_age: Option<Duration>,
+    ) -> CachingMemorySource<CountingMemorySource> {
+        CachingMemorySource::new(
+            CountingMemorySource::default(),
+            maximum_cached_bytes,
+            reuse,
+            maximum_age,
+        )
+    }
+
+    #[derive(Debug)]
+    struct CountingMemorySource {
+        memory_map_source: MemoryMapSource,
+        obtained: Cell<usize>,
+        released: Cell<usize>,
+    }
+
+    impl Default for CountingMemorySource {
+        fn default() -> Self {
+            Self {
+                memory_map_source: MemoryMapSource::new(
+                    false,
+                    false,
+                    true,
+                    false,
+                    HugePageSize::None,
+                    None,
+                ),
+                obtained: Cell::new(0),
+                released: Cell::new(0),
+            }
+        }
+    }
+
+    impl MemorySource for CountingMemorySource {
+        fn obtain(&self, non_zero_size: NonZeroUsize) -> Result<MemoryAddress, AllocError> {
+            self.obtained.set(self.obtained.get() + 1);
+            self.memory_map_source.obtain(non_zero_size)
+        }
+
+        fn release(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+            self.released.set(self.released.get() + 1);
+            self.memory_map_source
+                .release(non_zero_size, current_memory)
+        }
+
+        fn decommit(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+            self.memory_map_source
+                .decommit(non_zero_size, current_memory)
+        }
+    }
+}
@@ -0,0 +1,85 @@
+#![feature(allocator_api)]
+
+#[cfg(test)]
+mod concurrent_arena_memory_source_tests {
+
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+    use allocator_suite::memory_sources::prelude::*;
+
+    use std::alloc::AllocError;
+    use std::sync::Arc;
+    use std::thread;
+
+    const NUMBER_OF_THREADS: usize = 4;
+
+    const NUMBER_OF_BLOCKS: usize = 16;
+
+    const BLOCK_SIZE: usize = 4096;
+
+    #[test]
+    pub fn obtain_and_release_from_many_threads() {
+        let arena = Arc::new(new_arena());
+
+        let threads = (0..NUMBER_OF_THREADS)
+            .map(|thread_index| {
+                let arena = arena.clone();
+                thread::spawn(move || {
+                    for _ in 0..1_000 {
+                        let block = arena
+                            .obtain(BLOCK_SIZE.non_zero())
+                            .expect("Did not obtain block");
+                        unsafe { block.as_ptr().write_bytes(thread_index as u8, BLOCK_SIZE) };
+                        assert_eq!(
+                            unsafe { block.as_ptr().add(BLOCK_SIZE - 1).read() },
+                            thread_index as u8,
+                            "Block was shared with another thread"
+                        );
+                        arena.release(BLOCK_SIZE.non_zero(), block);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for thread in threads {
+            thread.join().expect("Thread panicked");
+        }
+
+        assert_all_blocks_can_be_obtained(&arena);
+    }
+
+    #[test]
+    pub fn exhaustion() {
+        let arena = new_arena();
+
+        assert_all_blocks_can_be_obtained(&arena);
+        assert_eq!(
+            arena.obtain(BLOCK_SIZE.non_zero()),
+            Err(AllocError),
+            "Arena was not exhausted"
+        );
+    }
+
+    fn assert_all_blocks_can_be_obtained(arena: &ConcurrentArenaMemorySource<MemoryMapSource>) {
+        let mut blocks = (0..NUMBER_OF_BLOCKS)
+            .map(|_| {
+                arena
+                    .obtain(BLOCK_SIZE.non_zero())
+                    .expect("Did not obtain block")
+                    .as_ptr() as usize
+            })
+            .collect::<Vec<_>>();
+        blocks.sort();
+        blocks.dedup();
+        assert_eq!(blocks.len(), NUMBER_OF_BLOCKS, "Blocks were duplicated");
+    }
+
+    fn new_arena() -> ConcurrentArenaMemorySource<MemoryMapSource> {
+        ConcurrentArenaMemorySource::new(
+            MemoryMapSource::default(),
+            BLOCK_SIZE.non_zero(),
+            NUMBER_OF_BLOCKS.non_zero(),
+            |_, _| {},
+        )
+        .unwrap()
+    }
+}
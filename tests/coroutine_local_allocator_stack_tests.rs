@@ -0,0 +1,169 @@
+#![feature(allocator_api)]
+#![feature(extern_types)]
+#![feature(core_intrinsics)]
+#![feature(libstd_sys_internals)]
+#![feature(thread_local)]
+#![feature(const_fn)]
+#![feature(nonnull_slice_from_raw_parts)]
+
+#[cfg(test)]
+mod coroutine_local_allocator_stack_tests {
+    // Allocator generator macro
+    use allocator_suite::switchable_allocator;
+
+    // General imports
+    use allocator_suite::adaptors::prelude::*;
+    use allocator_suite::allocators::global::prelude::*;
+    use allocator_suite::allocators::prelude::*;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+    use allocator_suite::memory_address::MemoryAddress;
+    use allocator_suite::memory_sources::prelude::*;
+    use std::alloc::{AllocError, System};
+    use std::num::NonZeroUsize;
+    use std::ptr::NonNull;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering::SeqCst;
+    use std::thread;
+
+    switchable_allocator!(
+        application_allocator,
+        BumpAllocator<ReleaseCountingMemorySource>,
+        MultipleBinarySearchTreeAllocator<MemoryMapSource>,
+        GlobalAllocToAllocatorAdaptor<System>,
+        GlobalAllocToAllocatorAdaptor(System)
+    );
+
+    #[test]
+    pub fn nested_coroutine_local_allocators_free_their_own_memory() {
+        thread::spawn(|| {
+            assert_eq!(GLOBAL.coroutine_local_allocator_depth(), 0);
+
+            GLOBAL
+                .push_coroutine_local_allocator(new_bump_allocator())
+                .expect("Did not push request allocator");
+            let request = GLOBAL.callback_with_coroutine_local_allocator(|| vec![1u8; 128]);
+
+            GLOBAL
+                .push_coroutine_local_allocator(new_bump_allocator())
+                .expect("Did not push sub-task allocator");
+            assert_eq!(GLOBAL.coroutine_local_allocator_depth(), 2);
+            let sub_task = GLOBAL.callback_with_coroutine_local_allocator(|| vec![2u8; 128]);
+
+            let current = GLOBAL.coroutine_local_allocator_unchecked();
+            assert!(current.contains(address_of(&sub_task)));
+            assert!(!current.contains(address_of(&request)));
+            assert!(GLOBAL
+                .coroutine_local_allocator_containing(address_of(&request))
+                .is_some());
+
+            drop(sub_task);
+            assert!(GLOBAL.pop_coroutine_local_allocator().is_some());
+            assert_eq!(GLOBAL.coroutine_local_allocator_depth(), 1);
+            assert!(GLOBAL
+                .coroutine_local_allocator_unchecked()
+                .contains(address_of(&request)));
+
+            drop(request);
+            assert!(GLOBAL.pop_coroutine_local_allocator().is_some());
+            assert_eq!(GLOBAL.coroutine_local_allocator_depth(), 0);
+            assert!(GLOBAL.pop_coroutine_local_allocator().is_none());
+        })
+        .join()
+        .expect("Thread panicked");
+    }
+
+    #[test]
+    pub fn pushed_coroutine_local_allocator_frees_memory_freed_by_another_thread() {
+        thread::spawn(|| {
+            GLOBAL
+                .push_coroutine_local_allocator(new_bump_allocator())
+                .expect("Did not push request allocator");
+            let request = GLOBAL.callback_with_coroutine_local_allocator(|| vec![1u8; 128]);
+            let request_address = address_of(&request);
+
+            GLOBAL
+                .push_coroutine_local_allocator(new_bump_allocator())
+                .expect("Did not push sub-task allocator");
+
+            thread::spawn(move || drop(request))
+                .join()
+                .expect("Thread panicked");
+
+            // Allocating from the sub-task allocator frees the memory queued for the pushed request allocator, which, as a bump allocator, can then reuse it.
+            let sub_task = GLOBAL.callback_with_coroutine_local_allocator(|| vec![2u8; 128]);
+            let request_allocator = GLOBAL
+                .coroutine_local_allocator_containing(request_address)
+                .expect("Request allocator is still pushed");
+            let reused = request_allocator
+                .allocate(128.non_zero(), 1.non_zero())
+                .expect("Did not allocate");
+            assert_eq!(reused, request_address);
+            request_allocator.deallocate(128.non_zero(), 1.non_zero(), reused);
+
+            drop(sub_task);
+            assert!(GLOBAL.pop_coroutine_local_allocator().is_some());
+            assert!(GLOBAL.pop_coroutine_local_allocator().is_some());
+        })
+        .join()
+        .expect("Thread panicked");
+    }
+
+    #[test]
+    pub fn coroutine_local_allocators_are_dropped_when_thread_exits() {
+        static RELEASED: AtomicUsize = AtomicUsize::new(0);
+
+        thread::spawn(|| {
+            for _ in 0..3 {
+                GLOBAL
+                    .push_coroutine_local_allocator(new_counted_bump_allocator(&RELEASED))
+                    .expect("Did not push");
+            }
+            assert_eq!(GLOBAL.coroutine_local_allocator_depth(), 3);
+        })
+        .join()
+        .expect("Thread panicked");
+
+        assert_eq!(RELEASED.load(SeqCst), 3);
+    }
+
+    static UNCOUNTED: AtomicUsize = AtomicUsize::new(0);
+
+    fn new_bump_allocator() -> BumpAllocator<ReleaseCountingMemorySource> {
+        new_counted_bump_allocator(&UNCOUNTED)
+    }
+
+    fn new_counted_bump_allocator(
+        released: &'static AtomicUsize,
+    ) -> BumpAllocator<ReleaseCountingMemorySource> {
+        BumpAllocator::new(
+            ReleaseCountingMemorySource {
+                memory_source: MemoryMapSource::default(),
+                released,
+            },
+            (64 * 1024).non_zero(),
+        )
+        .expect("Did not create bump allocator")
+    }
+
+    /// Counts releases, so that the dropping of the allocators using it can be observed.
+    #[derive(Debug)]
+    pub struct ReleaseCountingMemorySource {
+        memory_source: MemoryMapSource,
+        released: &'static AtomicUsize,
+    }
+
+    impl MemorySource for ReleaseCountingMemorySource {
+        fn obtain(&self, non_zero_size: NonZeroUsize) -> Result<MemoryAddress, AllocError> {
+            self.memory_source.obtain(non_zero_size)
+        }
+
+        fn release(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+            self.released.fetch_add(1, SeqCst);
+            self.memory_source.release(non_zero_size, current_memory)
+        }
+    }
+
+    fn address_of(vec: &Vec<u8>) -> NonNull<u8> {
+        NonNull::new(vec.as_ptr() as *mut u8).unwrap()
+    }
+}
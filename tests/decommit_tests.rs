@@ -0,0 +1,226 @@
+#![feature(allocator_api)]
+
+#[cfg(test)]
+mod decommit_tests {
+
+    use allocator_suite::allocators::prelude::*;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+    use allocator_suite::memory_address::MemoryAddress;
+    use allocator_suite::memory_sources::prelude::*;
+    use std::alloc::AllocError;
+    use std::cell::Cell;
+    use std::num::NonZeroUsize;
+
+    const MEMORY_SOURCE_SIZE: usize = 1024 * 1024;
+
+    const DECOMMIT_THRESHOLD: usize = 64 * 1024;
+
+    #[test]
+    pub fn decommits_large_free_blocks_and_recommits_them_when_allocated() {
+        let allocator = MultipleBinarySearchTreeAllocator::new_with_decommit_threshold(
+            RecordingMemorySource::default(),
+            MEMORY_SOURCE_SIZE.non_zero(),
+            DECOMMIT_THRESHOLD.non_zero(),
+        )
+        .expect("Did not create allocator");
+
+        let memory = allocator
+            .allocate((256 * 1024).non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        let recommitted = allocator.memory_source().recommitted.get();
+        assert!(recommitted > 0);
+        unsafe { memory.as_ptr().write_bytes(0x11, 256 * 1024) };
+
+        let decommitted = allocator.memory_source().decommitted.get();
+        allocator.deallocate((256 * 1024).non_zero(), 8.non_zero(), memory);
+        assert!(allocator.memory_source().decommitted.get() > decommitted);
+
+        allocator
+            .allocate((256 * 1024).non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        assert!(allocator.memory_source().recommitted.get() > recommitted);
+    }
+
+    #[test]
+    pub fn never_decommits_by_default() {
+        let allocator = MultipleBinarySearchTreeAllocator::new(
+            RecordingMemorySource::default(),
+            MEMORY_SOURCE_SIZE.non_zero(),
+        )
+        .expect("Did not create allocator");
+
+        let memory = allocator
+            .allocate((256 * 1024).non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        allocator.deallocate((256 * 1024).non_zero(), 8.non_zero(), memory);
+
+        assert_eq!(allocator.memory_source().decommitted.get(), 0);
+        assert_eq!(allocator.memory_source().recommitted.get(), 0);
+    }
+
+    /// `ReservedMemorySource` makes decommitted memory inaccessible, so handing out a block which is still decommitted faults.
+    #[cfg(unix)]
+    #[test]
+    pub fn blocks_split_after_coalescing_are_never_handed_out_decommitted() {
+        let allocator = MultipleBinarySearchTreeAllocator::new_with_decommit_threshold(
+            ReservedMemorySource::new((16 * MEMORY_SOURCE_SIZE).non_zero())
+                .expect("Did not reserve"),
+            MEMORY_SOURCE_SIZE.non_zero(),
+            DECOMMIT_THRESHOLD.non_zero(),
+        )
+        .expect("Did not create allocator");
+
+        for _ in 0..2 {
+            // Free neighbouring large blocks, which are decommitted and coalesce.
+            let large = (0..4)
+                .map(|_| allocate_and_fill(&allocator, DECOMMIT_THRESHOLD))
+                .collect::<Vec<_>>();
+            for memory in large.into_iter().rev() {
+                allocator.deallocate(DECOMMIT_THRESHOLD.non_zero(), 8.non_zero(), memory);
+            }
+
+            // Split the coalesced blocks into blocks both smaller and larger than the threshold.
+            let mixed = [
+                1024,
+                4096,
+                2 * DECOMMIT_THRESHOLD,
+                32,
+                DECOMMIT_THRESHOLD,
+                8192,
+            ]
+            .iter()
+            .cycle()
+            .take(12)
+            .map(|&size| (size, allocate_and_fill(&allocator, size)))
+            .collect::<Vec<_>>();
+            for (size, memory) in mixed {
+                allocator.deallocate(size.non_zero(), 8.non_zero(), memory);
+            }
+        }
+    }
+
+    /// Blocks smaller than two pages have no whole page after their node, so a threshold below that decommits nothing, but must still be safe.
+    #[cfg(unix)]
+    #[test]
+    pub fn threshold_smaller_than_a_page_is_safe() {
+        let allocator = MultipleBinarySearchTreeAllocator::new_with_decommit_threshold(
+            ReservedMemorySource::new(MEMORY_SOURCE_SIZE.non_zero()).expect("Did not reserve"),
+            MEMORY_SOURCE_SIZE.non_zero(),
+            64.non_zero(),
+        )
+        .expect("Did not create allocator");
+
+        for _ in 0..2 {
+            let small = (0..64)
+                .map(|index| {
+                    let size = 32 << (index % 8);
+                    (size, allocate_and_fill(&allocator, size))
+                })
+                .collect::<Vec<_>>();
+            for (size, memory) in small {
+                allocator.deallocate(size.non_zero(), 8.non_zero(), memory);
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    pub fn bump_allocator_decommits_on_reset_and_recommits_as_allocations_reach_it() {
+        let mut allocator = BumpAllocator::new_with_decommit_threshold(
+            ReservedMemorySource::new(MEMORY_SOURCE_SIZE.non_zero()).expect("Did not reserve"),
+            MEMORY_SOURCE_SIZE.non_zero(),
+            DECOMMIT_THRESHOLD.non_zero(),
+        )
+        .expect("Did not create allocator");
+
+        for _ in 0..3 {
+            // A spike, then many small allocations crossing into decommitted memory.
+            allocate_and_fill(&allocator, 4 * DECOMMIT_THRESHOLD);
+            for _ in 0..(4 * DECOMMIT_THRESHOLD / 1024) {
+                allocate_and_fill(&allocator, 1024);
+            }
+            allocator.reset();
+        }
+    }
+
+    #[test]
+    pub fn bump_allocator_decommits_beyond_threshold_on_reset() {
+        let mut allocator = BumpAllocator::new_with_decommit_threshold(
+            RecordingMemorySource::default(),
+            MEMORY_SOURCE_SIZE.non_zero(),
+            DECOMMIT_THRESHOLD.non_zero(),
+        )
+        .expect("Did not create allocator");
+
+        allocate_and_fill(&allocator, DECOMMIT_THRESHOLD / 2);
+        allocator.reset();
+        assert_eq!(allocator.memory_source().decommitted.get(), 1);
+        assert_eq!(allocator.memory_source().recommitted.get(), 0);
+
+        allocate_and_fill(&allocator, DECOMMIT_THRESHOLD / 2);
+        assert_eq!(allocator.memory_source().recommitted.get(), 0);
+        allocate_and_fill(&allocator, DECOMMIT_THRESHOLD);
+        assert_eq!(allocator.memory_source().recommitted.get(), 1);
+    }
+
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    #[test]
+    pub fn memory_map_source_decommitted_memory_reads_as_zero() {
+        let memory_source =
+            MemoryMapSource::new(false, false, true, false, HugePageSize::None, None);
+        let size = (64 * 1024).non_zero();
+
+        let memory = memory_source.obtain(size).expect("Did not obtain");
+        unsafe { memory.as_ptr().write_bytes(0x22, size.get()) };
+
+        memory_source.decommit(size, memory);
+        memory_source
+            .recommit(size, memory)
+            .expect("Did not recommit");
+        assert!((0..size.get()).all(|index| unsafe { *memory.as_ptr().add(index) } == 0));
+
+        memory_source.release(size, memory);
+    }
+
+    fn allocate_and_fill<A: Allocator>(allocator: &A, size: usize) -> MemoryAddress {
+        let memory = allocator
+            .allocate(size.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        unsafe { memory.as_ptr().write_bytes(0x33, size) };
+        memory
+    }
+
+    #[derive(Debug, Default)]
+    struct RecordingMemorySource {
+        memory_map_source: MemoryMapSource,
+        decommitted: Cell<usize>,
+        recommitted: Cell<usize>,
+    }
+
+    impl MemorySource for RecordingMemorySource {
+        fn obtain(&self, non_zero_size: NonZeroUsize) -> Result<MemoryAddress, AllocError> {
+            self.memory_map_source.obtain(non_zero_size)
+        }
+
+        fn release(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+            self.memory_map_source
+                .release(non_zero_size, current_memory)
+        }
+
+        fn decommit(&self, non_zero_size: NonZeroUsize, current_memory: MemoryAddress) {
+            self.decommitted.set(self.decommitted.get() + 1);
+            self.memory_map_source
+                .decommit(non_zero_size, current_memory)
+        }
+
+        fn recommit(
+            &self,
+            non_zero_size: NonZeroUsize,
+            current_memory: MemoryAddress,
+        ) -> Result<(), AllocError> {
+            self.recommitted.set(self.recommitted.get() + 1);
+            self.memory_map_source
+                .recommit(non_zero_size, current_memory)
+        }
+    }
+}
@@ -0,0 +1,152 @@
+#![feature(allocator_api)]
+
+#[cfg(test)]
+mod failing_tests {
+
+    use allocator_suite::allocators::prelude::*;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+    use allocator_suite::memory_sources::prelude::*;
+    use std::num::NonZeroUsize;
+
+    const SIZE: usize = 64 * 1024;
+
+    #[test]
+    pub fn fails_every_nth_call() {
+        let injector = FailureInjector::new(FailurePolicy::EveryNthCall(3.non_zero()));
+
+        let failed = (0..9)
+            .map(|_| injector.call(8.non_zero()).is_err())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            failed,
+            [false, false, true, false, false, true, false, false, true]
+        );
+        assert_eq!(injector.calls(), 9);
+        assert_eq!(injector.failures(), 3);
+        assert_eq!(injector.first_failed_call(), Some(3.non_zero()));
+        assert_eq!(injector.last_failed_call(), Some(9.non_zero()));
+    }
+
+    #[test]
+    pub fn fails_after_bytes() {
+        let injector = FailureInjector::new(FailurePolicy::AfterBytes(100));
+
+        assert!(injector.call(60.non_zero()).is_ok());
+        assert!(injector.call(60.non_zero()).is_err());
+        assert!(injector.call(40.non_zero()).is_ok());
+        assert!(injector.call(1.non_zero()).is_err());
+        assert_eq!(injector.bytes(), 100);
+        assert_eq!(injector.first_failed_call(), Some(2.non_zero()));
+        assert_eq!(injector.last_failed_call(), Some(4.non_zero()));
+    }
+
+    #[test]
+    pub fn fails_when_size() {
+        fn is_large(non_zero_size: NonZeroUsize) -> bool {
+            non_zero_size.get() > 1024
+        }
+        let injector = FailureInjector::new(FailurePolicy::WhenSize(is_large));
+
+        assert!(injector.call(1024.non_zero()).is_ok());
+        assert!(injector.call(1025.non_zero()).is_err());
+        assert_eq!(injector.failures(), 1);
+    }
+
+    #[test]
+    pub fn fails_randomly_but_reproducibly() {
+        let policy = FailurePolicy::Randomly {
+            seed: 42,
+            one_in: 4.non_zero(),
+        };
+        let calls = |injector: &FailureInjector| {
+            (0..1000)
+                .map(|_| injector.call(8.non_zero()).is_err())
+                .collect::<Vec<_>>()
+        };
+
+        let first = FailureInjector::new(policy);
+        let second = FailureInjector::new(policy);
+        assert_eq!(calls(&first), calls(&second));
+        assert!(
+            first.failures() > 150 && first.failures() < 350,
+            "About one call in four should fail, not {}",
+            first.failures()
+        );
+    }
+
+    #[test]
+    pub fn never_fails() {
+        let injector = FailureInjector::new(FailurePolicy::Never);
+
+        assert!(injector.call(usize::MAX.non_zero()).is_ok());
+        assert_eq!(injector.failures(), 0);
+        assert_eq!(injector.first_failed_call(), None);
+    }
+
+    #[test]
+    pub fn bump_allocator_new_fails_when_memory_source_fails() {
+        let memory_source = new_failing_memory_source(FailurePolicy::EveryNthCall(1.non_zero()));
+
+        assert!(BumpAllocator::new(memory_source, SIZE.non_zero()).is_err());
+    }
+
+    #[test]
+    pub fn bump_allocator_fails_once_exhausted() {
+        let allocator = BumpAllocator::new(
+            new_failing_memory_source(FailurePolicy::Never),
+            SIZE.non_zero(),
+        )
+        .expect("Did not create allocator");
+
+        assert!(allocator
+            .allocate((2 * SIZE).non_zero(), 8.non_zero())
+            .is_err());
+    }
+
+    #[test]
+    pub fn bit_set_allocator_new_fails_when_memory_source_fails() {
+        let memory_source = new_failing_memory_source(FailurePolicy::AfterBytes(0));
+
+        assert!(BitSetAllocator::new(memory_source, 64.non_zero(), 1024.non_zero()).is_err());
+    }
+
+    #[test]
+    pub fn arena_memory_source_new_fails_when_memory_source_fails() {
+        let memory_source = new_failing_memory_source(FailurePolicy::EveryNthCall(1.non_zero()));
+
+        assert!(
+            ArenaMemorySource::new(memory_source, 64.non_zero(), 1024.non_zero(), |_, _| ())
+                .is_err()
+        );
+    }
+
+    #[test]
+    pub fn failing_allocator_fails_and_passes_through() {
+        let allocator = FailingAllocator::new(
+            BumpAllocator::new(MemoryMapSource::default(), SIZE.non_zero())
+                .expect("Did not create allocator"),
+            FailurePolicy::EveryNthCall(2.non_zero()),
+        );
+
+        let memory = allocator
+            .allocate(64.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        assert!(allocator
+            .growing_reallocate(128.non_zero(), 8.non_zero(), 64.non_zero(), memory)
+            .is_err());
+        let memory = allocator
+            .growing_reallocate(128.non_zero(), 8.non_zero(), 64.non_zero(), memory)
+            .expect("Did not reallocate");
+        allocator.deallocate(128.non_zero(), 8.non_zero(), memory);
+
+        assert_eq!(allocator.failure_injector().calls(), 3);
+        assert_eq!(
+            allocator.failure_injector().last_failed_call(),
+            Some(2.non_zero())
+        );
+    }
+
+    fn new_failing_memory_source(policy: FailurePolicy) -> FailingMemorySource<MemoryMapSource> {
+        FailingMemorySource::new(MemoryMapSource::default(), policy)
+    }
+}
@@ -0,0 +1,86 @@
+#![feature(allocator_api)]
+
+#[cfg(test)]
+mod fallback_allocator_tests {
+
+    use allocator_suite::adaptors::prelude::*;
+    use allocator_suite::allocators::global::local_allocator::LocalAllocator;
+    use allocator_suite::allocators::prelude::*;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+
+    use allocator_suite::memory_sources::mmap::memory_map_source::MemoryMapSource;
+    use std::alloc::System;
+
+    const PRIMARY_SIZE: usize = 4096;
+
+    #[test]
+    pub fn falls_back_to_secondary_when_primary_is_exhausted() {
+        let allocator = FallbackAllocator::new(
+            new_atomic_bump_allocator(PRIMARY_SIZE),
+            GlobalAllocToAllocatorAdaptor(System),
+        );
+
+        let small = allocator
+            .allocate(64.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        assert!(allocator.primary().contains(small));
+
+        let large = allocator
+            .allocate((PRIMARY_SIZE * 2).non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        assert!(!allocator.primary().contains(large));
+
+        allocator.deallocate((PRIMARY_SIZE * 2).non_zero(), 8.non_zero(), large);
+        allocator.deallocate(64.non_zero(), 8.non_zero(), small);
+    }
+
+    #[test]
+    pub fn growing_from_primary_to_secondary_copies() {
+        let allocator = FallbackAllocator::new(
+            new_atomic_bump_allocator(PRIMARY_SIZE),
+            GlobalAllocToAllocatorAdaptor(System),
+        );
+
+        let memory = allocator
+            .allocate(64.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        unsafe { memory.as_ptr().write_bytes(0xA5, 64) };
+
+        let grown = allocator
+            .growing_reallocate(
+                (PRIMARY_SIZE * 2).non_zero(),
+                8.non_zero(),
+                64.non_zero(),
+                memory,
+            )
+            .expect("Did not grow");
+        assert!(!allocator.primary().contains(grown));
+        assert!((0..64).all(|index| unsafe { *grown.as_ptr().add(index) } == 0xA5));
+
+        allocator.deallocate((PRIMARY_SIZE * 2).non_zero(), 8.non_zero(), grown);
+    }
+
+    #[test]
+    pub fn contains_memory_of_both_local_allocators() {
+        let allocator = FallbackAllocator::new(
+            new_atomic_bump_allocator(PRIMARY_SIZE),
+            new_atomic_bump_allocator(PRIMARY_SIZE * 4),
+        );
+
+        let primary = allocator
+            .allocate(PRIMARY_SIZE.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        let secondary = allocator
+            .allocate(PRIMARY_SIZE.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+
+        assert!(allocator.primary().contains(primary));
+        assert!(allocator.secondary().contains(secondary));
+        assert!(allocator.contains(primary));
+        assert!(allocator.contains(secondary));
+    }
+
+    fn new_atomic_bump_allocator(memory_size: usize) -> AtomicBumpAllocator<MemoryMapSource> {
+        AtomicBumpAllocator::new(MemoryMapSource::default(), memory_size.non_zero()).unwrap()
+    }
+}
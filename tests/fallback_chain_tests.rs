@@ -0,0 +1,162 @@
+#![feature(allocator_api)]
+#![feature(extern_types)]
+#![feature(core_intrinsics)]
+#![feature(libstd_sys_internals)]
+#![feature(thread_local)]
+#![feature(const_fn)]
+#![feature(nonnull_slice_from_raw_parts)]
+
+#[cfg(test)]
+mod fallback_chain_tests {
+    // Allocator generator macro
+    use allocator_suite::switchable_allocator;
+
+    // General imports
+    use allocator_suite::adaptors::prelude::*;
+    use allocator_suite::allocators::global::prelude::*;
+    use allocator_suite::allocators::prelude::*;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+    use allocator_suite::memory_sources::prelude::*;
+    use std::alloc::System;
+    use std::num::NonZeroUsize;
+    use std::ptr::NonNull;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering::Relaxed;
+    use std::thread;
+
+    switchable_allocator!(
+        application_allocator,
+        BumpAllocator<ArenaMemorySource<MemoryMapSource>>,
+        MultipleBinarySearchTreeAllocator<MemoryMapSource>,
+        GlobalAllocToAllocatorAdaptor<System>,
+        GlobalAllocToAllocatorAdaptor(System)
+    );
+
+    /// Spill counts and the spill callback are process-wide, so tests which spill must not run in parallel.
+    static SERIALIZE: SpinLock = SpinLock::UNLOCKED;
+
+    struct Serialized;
+
+    impl Serialized {
+        fn new() -> Self {
+            SERIALIZE.lock();
+            SPILLS_SEEN.store(0, Relaxed);
+            BYTES_SPILLED.store(0, Relaxed);
+            Serialized
+        }
+    }
+
+    impl Drop for Serialized {
+        fn drop(&mut self) {
+            GLOBAL.spills().set_callback(None);
+            SERIALIZE.unlock()
+        }
+    }
+
+    static SPILLS_SEEN: AtomicUsize = AtomicUsize::new(0);
+
+    static BYTES_SPILLED: AtomicUsize = AtomicUsize::new(0);
+
+    fn spilled(
+        _from: CurrentAllocatorInUse,
+        _spill_reason: SpillReason,
+        non_zero_size: NonZeroUsize,
+    ) {
+        SPILLS_SEEN.fetch_add(1, Relaxed);
+        BYTES_SPILLED.fetch_add(non_zero_size.get(), Relaxed);
+    }
+
+    #[test]
+    pub fn absent_coroutine_local_allocator_spills_to_thread_local_then_global() {
+        let _serialized = Serialized::new();
+        let spills = GLOBAL.spills();
+        let coroutine_local_before =
+            spills.count(CurrentAllocatorInUse::CoroutineLocal, SpillReason::Absent);
+        let thread_local_before =
+            spills.count(CurrentAllocatorInUse::ThreadLocal, SpillReason::Absent);
+        let total_before = spills.total();
+        spills.set_callback(Some(spilled));
+
+        let allocated =
+            thread::spawn(|| GLOBAL.callback_with_coroutine_local_allocator(|| vec![5u8; 32]))
+                .join()
+                .expect("Thread panicked");
+        spills.set_callback(None);
+        assert_eq!(allocated, vec![5u8; 32]);
+
+        assert_eq!(
+            spills.count(CurrentAllocatorInUse::CoroutineLocal, SpillReason::Absent),
+            coroutine_local_before + 1
+        );
+        assert_eq!(
+            spills.count(CurrentAllocatorInUse::ThreadLocal, SpillReason::Absent),
+            thread_local_before + 1
+        );
+        assert_eq!(spills.total(), total_before + 2);
+        assert_eq!(SPILLS_SEEN.load(Relaxed), 2);
+        assert_eq!(BYTES_SPILLED.load(Relaxed), 2 * 32);
+    }
+
+    #[test]
+    pub fn exhausted_thread_local_allocator_spills_to_global() {
+        const SIZE: usize = 1024 * 1024;
+
+        let _serialized = Serialized::new();
+        let spills = GLOBAL.spills();
+        let exhausted_before =
+            spills.count(CurrentAllocatorInUse::ThreadLocal, SpillReason::Exhausted);
+        let total_before = spills.total();
+        spills.set_callback(Some(spilled));
+
+        thread::spawn(|| {
+            GLOBAL.initialize_thread_local_allocator(
+                MultipleBinarySearchTreeAllocator::new(
+                    MemoryMapSource::new(false, false, false, false, HugePageSize::None, None),
+                    (64 * 1024).non_zero(),
+                )
+                .expect("Did not create thread local allocator"),
+            );
+
+            let allocated = GLOBAL.callback_with_thread_local_allocator(|| vec![9u8; SIZE]);
+
+            assert!(!GLOBAL
+                .thread_local_allocator_unchecked()
+                .contains(NonNull::new(allocated.as_ptr() as *mut u8).unwrap()));
+            drop(allocated);
+            GLOBAL.drop_thread_local_allocator();
+        })
+        .join()
+        .expect("Thread panicked");
+        spills.set_callback(None);
+
+        assert_eq!(
+            spills.count(CurrentAllocatorInUse::ThreadLocal, SpillReason::Exhausted),
+            exhausted_before + 1
+        );
+        assert_eq!(spills.total(), total_before + 1);
+        assert_eq!(SPILLS_SEEN.load(Relaxed), 1);
+        assert_eq!(BYTES_SPILLED.load(Relaxed), SIZE);
+    }
+
+    #[test]
+    pub fn allocation_fails_without_fallback_chain() {
+        let _serialized = Serialized::new();
+        let total_before = GLOBAL.spills().total();
+
+        thread::spawn(|| {
+            GLOBAL.set_fallback_chain(FallbackChain::None);
+
+            let was =
+                GLOBAL.replace_current_allocator_in_use(CurrentAllocatorInUse::CoroutineLocal);
+            let result = GLOBAL.allocate(64.non_zero(), 8.non_zero());
+            GLOBAL.restore_current_allocator_in_use(was);
+
+            GLOBAL.set_fallback_chain(FallbackChain::default());
+            assert!(result.is_err());
+        })
+        .join()
+        .expect("Thread panicked");
+
+        assert_eq!(GLOBAL.spills().total(), total_before);
+    }
+}
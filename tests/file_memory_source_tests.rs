@@ -0,0 +1,61 @@
+#![feature(allocator_api)]
+
+#[cfg(test)]
+mod file_memory_source_tests {
+
+    use allocator_suite::allocators::prelude::*;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+    use allocator_suite::memory_sources::prelude::*;
+    use std::env::temp_dir;
+    use std::fs::{read, remove_file};
+    use std::path::PathBuf;
+    use std::process;
+
+    #[test]
+    pub fn released_memory_is_written_to_file() {
+        let path = temporary_file_path("released_memory_is_written_to_file");
+
+        {
+            let memory_source =
+                FileMemorySource::open(&path, false, true).expect("Did not open file");
+            let memory = memory_source
+                .obtain(8192.non_zero())
+                .expect("Did not obtain");
+            unsafe { memory.as_ptr().write_bytes(0xC3, 8192) };
+            memory_source.release(8192.non_zero(), memory);
+        }
+
+        let contents = read(&path).expect("Did not read file");
+        assert!(contents.len() >= 8192);
+        assert!(contents[..8192].iter().all(|&byte| byte == 0xC3));
+        remove_file(&path).expect("Did not remove file");
+    }
+
+    #[test]
+    pub fn reopening_maps_previous_contents() {
+        let path = temporary_file_path("reopening_maps_previous_contents");
+
+        for (run, preallocate) in [false, true].iter().enumerate() {
+            let allocator = BumpAllocator::new(
+                FileMemorySource::open(&path, *preallocate, true).expect("Did not open file"),
+                (64 * 1024).non_zero(),
+            )
+            .expect("Did not create allocator");
+            let memory = allocator
+                .allocate(64.non_zero(), 8.non_zero())
+                .expect("Did not allocate");
+
+            if run == 0 {
+                unsafe { memory.as_ptr().write_bytes(0x3C, 64) };
+            } else {
+                assert!((0..64).all(|index| unsafe { *memory.as_ptr().add(index) } == 0x3C));
+            }
+        }
+
+        remove_file(&path).expect("Did not remove file");
+    }
+
+    fn temporary_file_path(test_name: &str) -> PathBuf {
+        temp_dir().join(format!("allocator_suite_{}_{}", test_name, process::id()))
+    }
+}
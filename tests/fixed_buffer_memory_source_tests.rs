@@ -0,0 +1,79 @@
+#![feature(allocator_api)]
+#![feature(min_const_generics)]
+
+#[cfg(test)]
+mod fixed_buffer_memory_source_tests {
+
+    use allocator_suite::allocators::global::local_allocator::LocalAllocator;
+    use allocator_suite::allocators::prelude::*;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+    use allocator_suite::memory_sources::prelude::*;
+
+    const BUFFER_SIZE: usize = 64 * 1024;
+
+    static STATIC_MEMORY_SOURCE: StaticFixedBufferMemorySource<{ 4 * BUFFER_SIZE }> =
+        StaticFixedBufferMemorySource::new();
+
+    #[test]
+    pub fn obtains_aligned_memory_within_buffer_until_exhausted() {
+        let mut buffer = vec![0u8; BUFFER_SIZE];
+        let buffer_range = buffer.as_ptr_range();
+        let buffer_range = (buffer_range.start as usize)..(buffer_range.end as usize);
+        let memory_source = FixedBufferMemorySource::new(&mut buffer[..]);
+
+        let mut obtained = 0;
+        while let Ok(memory) = memory_source.obtain(1000.non_zero()) {
+            let address = memory.as_ptr() as usize;
+            assert_eq!(address % FIXED_BUFFER_ALIGNMENT, 0);
+            assert!(buffer_range.contains(&address));
+            assert!(address + 1000 <= buffer_range.end);
+            obtained += 1;
+        }
+        assert!(obtained > 0);
+        assert!(memory_source.obtain(BUFFER_SIZE.non_zero()).is_err());
+    }
+
+    #[test]
+    pub fn reclaims_most_recently_obtained_memory() {
+        let mut buffer = [0u8; 4096];
+        let memory_source = FixedBufferMemorySource::new(&mut buffer[..]);
+
+        let first = memory_source
+            .obtain(128.non_zero())
+            .expect("Did not obtain");
+        memory_source.release(128.non_zero(), first);
+        let again = memory_source
+            .obtain(128.non_zero())
+            .expect("Did not obtain");
+        assert_eq!(first, again);
+    }
+
+    #[test]
+    pub fn bit_set_allocator_runs_on_stack_buffer() {
+        let mut buffer = [0u8; BUFFER_SIZE];
+        let allocator = BitSetAllocator::new_by_amount_8(
+            FixedBufferMemorySource::new(&mut buffer[..]),
+            (BUFFER_SIZE / 2).non_zero(),
+        )
+        .expect("Did not create allocator");
+
+        let memory = allocator
+            .allocate(64.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        assert!(allocator.contains(memory));
+        allocator.deallocate(64.non_zero(), 8.non_zero(), memory);
+    }
+
+    #[test]
+    pub fn multiple_binary_search_tree_allocator_runs_on_static_buffer() {
+        let allocator =
+            MultipleBinarySearchTreeAllocator::new(&STATIC_MEMORY_SOURCE, BUFFER_SIZE.non_zero())
+                .expect("Did not create allocator");
+
+        let memory = allocator
+            .allocate(256.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        assert!(allocator.contains(memory));
+        allocator.deallocate(256.non_zero(), 8.non_zero(), memory);
+    }
+}
@@ -0,0 +1,91 @@
+#![feature(allocator_api)]
+#![feature(extern_types)]
+#![feature(core_intrinsics)]
+#![feature(libstd_sys_internals)]
+#![feature(thread_local)]
+#![feature(const_fn)]
+#![feature(nonnull_slice_from_raw_parts)]
+
+#[cfg(all(test, unix))]
+mod growable_bump_allocator_tests {
+    // Allocator generator macro
+    use allocator_suite::switchable_allocator;
+
+    // General imports
+    use allocator_suite::adaptors::prelude::*;
+    use allocator_suite::allocators::global::prelude::*;
+    use allocator_suite::allocators::prelude::*;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+    use allocator_suite::memory_sources::prelude::*;
+    use std::alloc::System;
+    use std::ptr::NonNull;
+    use std::thread;
+
+    switchable_allocator!(
+        application_allocator,
+        BumpAllocator<ReservedMemorySource>,
+        MultipleBinarySearchTreeAllocator<MemoryMapSource>,
+        GlobalAllocToAllocatorAdaptor<System>,
+        GlobalAllocToAllocatorAdaptor(System)
+    );
+
+    const INITIAL_SIZE: usize = 64 * 1024;
+
+    const GROWN_SIZE: usize = 1024 * 1024;
+
+    #[test]
+    pub fn routes_memory_allocated_after_extending_in_place_to_coroutine_local_allocator() {
+        thread::spawn(|| {
+            let memory_source =
+                ReservedMemorySource::new((64 * 1024 * 1024).non_zero()).expect("Did not reserve");
+            let reservation = memory_source.reservation().expect("Has a reservation");
+            let allocator = BumpAllocator::new_growable(memory_source, INITIAL_SIZE.non_zero())
+                .expect("Did not create allocator");
+            assert!(GLOBAL
+                .replace_coroutine_local_allocator(Some(allocator))
+                .is_none());
+            assert_eq!(
+                GLOBAL.coroutine_local_allocator_unchecked().memory_range(),
+                reservation
+            );
+
+            let mut vec = GLOBAL.callback_with_coroutine_local_allocator(|| {
+                let mut vec = Vec::<u8>::with_capacity(1024);
+                let before = address_of(&vec);
+                vec.resize(GROWN_SIZE, 0x88);
+                assert_eq!(address_of(&vec), before, "Did not grow in place");
+                vec
+            });
+
+            let last_byte = unsafe { NonNull::new_unchecked(vec.as_mut_ptr().add(GROWN_SIZE - 1)) };
+            assert!(
+                last_byte.as_ptr() as usize - reservation.from.as_ptr() as usize >= INITIAL_SIZE
+            );
+            assert!(GLOBAL
+                .coroutine_local_allocator_containing(last_byte)
+                .is_some());
+            assert_eq!(
+                GLOBAL.coroutine_local_allocator_unchecked().memory_range(),
+                reservation
+            );
+            assert!(
+                GLOBAL
+                    .coroutine_local_allocator_unchecked()
+                    .memory_source()
+                    .obtained()
+                    >= GROWN_SIZE
+            );
+
+            // Freed whilst the global allocator is current, so routed to the coroutine local allocator by its memory range alone.
+            drop(vec);
+
+            assert!(GLOBAL.replace_coroutine_local_allocator(None).is_some());
+        })
+        .join()
+        .expect("Thread panicked");
+    }
+
+    fn address_of(vec: &Vec<u8>) -> NonNull<u8> {
+        NonNull::new(vec.as_ptr() as *mut u8).unwrap()
+    }
+}
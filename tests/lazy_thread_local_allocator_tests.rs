@@ -0,0 +1,49 @@
+#![feature(allocator_api)]
+#![feature(extern_types)]
+#![feature(core_intrinsics)]
+#![feature(libstd_sys_internals)]
+#![feature(thread_local)]
+#![feature(const_fn)]
+#![feature(nonnull_slice_from_raw_parts)]
+
+#[cfg(test)]
+mod lazy_thread_local_allocator_tests {
+    // Allocator generator macro
+    use allocator_suite::switchable_allocator;
+
+    // General imports
+    use allocator_suite::adaptors::prelude::*;
+    use allocator_suite::allocators::global::prelude::*;
+    use std::alloc::System;
+    use std::ptr::NonNull;
+    use std::thread;
+
+    switchable_allocator!(
+        application_allocator,
+        BumpAllocator<ArenaMemorySource<MemoryMapSource>>,
+        MultipleBinarySearchTreeAllocator<MemoryMapSource>,
+        GlobalAllocToAllocatorAdaptor<System>,
+        GlobalAllocToAllocatorAdaptor(System),
+        MultipleBinarySearchTreeAllocator::new(
+            MemoryMapSource::new(false, false, false, false, HugePageSize::None, None),
+            (1024 * 1024).non_zero()
+        )
+    );
+
+    #[test]
+    pub fn creates_thread_local_allocator_on_first_thread_local_allocation() {
+        thread::spawn(|| {
+            assert!(GLOBAL.thread_local_allocator().is_none());
+
+            let allocated = GLOBAL.callback_with_thread_local_allocator(|| vec![3u64; 16]);
+
+            let thread_local_allocator = GLOBAL
+                .thread_local_allocator()
+                .expect("Did not create thread local allocator");
+            assert!(thread_local_allocator
+                .contains(NonNull::new(allocated.as_ptr() as *mut u8).unwrap()));
+        })
+        .join()
+        .expect("Thread panicked");
+    }
+}
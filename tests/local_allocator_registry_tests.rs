@@ -0,0 +1,110 @@
+#![feature(allocator_api)]
+
+#[cfg(test)]
+mod local_allocator_registry_tests {
+
+    use allocator_suite::allocators::allocator::Allocator;
+    use allocator_suite::allocators::global::prelude::*;
+    use allocator_suite::allocators::prelude::*;
+    use allocator_suite::extensions::non_null_u8_ext::NonNullU8Ext;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+    use allocator_suite::memory_address::MemoryAddress;
+    use allocator_suite::memory_sources::prelude::*;
+
+    const RANGE_SIZE: usize = 4096;
+
+    fn memory_range(index: usize) -> MemoryRange {
+        let from = MemoryAddress::from_usize((index + 1) * RANGE_SIZE);
+        MemoryRange::new(from, from.add(RANGE_SIZE))
+    }
+
+    #[test]
+    pub fn finds_remote_free_queue_of_registered_memory() {
+        let registry = LocalAllocatorRegistry::new();
+        let first = registry
+            .register(memory_range(0))
+            .expect("Could not register");
+        registry
+            .register(memory_range(1))
+            .expect("Could not register");
+
+        let in_first = memory_range(0).from.add(8);
+        let in_second = memory_range(1).to.subtract(8);
+        let first_queue = registry
+            .remote_free_queue_for(in_first)
+            .expect("First not found") as *const RemoteFreeQueue;
+        let second_queue = registry
+            .remote_free_queue_for(in_second)
+            .expect("Second not found") as *const RemoteFreeQueue;
+        assert_ne!(first_queue, second_queue);
+        assert!(registry
+            .remote_free_queue_for(memory_range(2).from)
+            .is_none());
+
+        registry.unregister(first);
+        assert!(registry.remote_free_queue_for(in_first).is_none());
+        assert_eq!(
+            registry
+                .remote_free_queue_for(in_second)
+                .map(|queue| queue as *const RemoteFreeQueue),
+            Some(second_queue)
+        );
+    }
+
+    #[test]
+    pub fn refuses_registrations_when_full() {
+        let registry = Box::new(LocalAllocatorRegistry::new());
+
+        let registrations = (0..LocalAllocatorRegistry::MAXIMUM_NUMBER_OF_REGISTRATIONS)
+            .map(|index| {
+                registry
+                    .register(memory_range(index))
+                    .expect("Could not register")
+            })
+            .collect::<Vec<_>>();
+
+        let overflow = memory_range(LocalAllocatorRegistry::MAXIMUM_NUMBER_OF_REGISTRATIONS);
+        assert!(registry.register(overflow).is_none());
+        assert!(registry.remote_free_queue_for(overflow.from).is_none());
+
+        registry.unregister(registrations[7]);
+        let reregistered = registry.register(overflow).expect("Could not register");
+        assert_eq!(reregistered, registrations[7]);
+        assert!(registry.remote_free_queue_for(overflow.from).is_some());
+        assert!(registry
+            .remote_free_queue_for(memory_range(7).from)
+            .is_none());
+    }
+
+    #[test]
+    pub fn frees_remotely_freed_memory_of_local_allocator() {
+        let local_allocator = MultipleBinarySearchTreeAllocator::new(
+            MemoryMapSource::default(),
+            (1024 * 1024).non_zero(),
+        )
+        .expect("Could not create allocator");
+        let registry = LocalAllocatorRegistry::new();
+        let registration = registry
+            .register(local_allocator.memory_range())
+            .expect("Could not register");
+
+        let memory = local_allocator
+            .allocate(64.non_zero(), 8.non_zero())
+            .expect("Could not allocate");
+        let remote_free_queue = registry
+            .remote_free_queue_for(memory)
+            .expect("Not registered");
+        assert!(remote_free_queue.push(memory, 64.non_zero(), 8.non_zero()));
+
+        assert_eq!(
+            registry.free_remotely_freed(registration, &local_allocator),
+            1
+        );
+        assert_eq!(
+            registry.free_remotely_freed(registration, &local_allocator),
+            0
+        );
+
+        registry.unregister(registration);
+    }
+}
@@ -0,0 +1,96 @@
+#![feature(allocator_api)]
+
+#[cfg(test)]
+mod locked_allocator_tests {
+
+    use allocator_suite::allocators::prelude::*;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+
+    use allocator_suite::memory_sources::mmap::memory_map_source::MemoryMapSource;
+    use std::alloc::AllocError;
+    use std::sync::Arc;
+    use std::thread;
+
+    const NUMBER_OF_THREADS: usize = 4;
+
+    const ALLOCATIONS_PER_THREAD: usize = 1_000;
+
+    #[test]
+    pub fn spin_lock_shared_between_threads() {
+        test_shared_between_threads::<SpinLock>();
+    }
+
+    #[test]
+    pub fn ticket_lock_shared_between_threads() {
+        test_shared_between_threads::<TicketLock>();
+    }
+
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    #[test]
+    pub fn futex_mutex_shared_between_threads() {
+        test_shared_between_threads::<FutexMutex>();
+    }
+
+    #[test]
+    pub fn lazy_initialization() {
+        static ALLOCATOR: LockedAllocator<
+            MultipleBinarySearchTreeAllocator<MemoryMapSource>,
+            SpinLock,
+        > = LockedAllocator::lazy(new_allocator);
+
+        assert!(
+            !ALLOCATOR.contains(LockedAllocator::<
+                MultipleBinarySearchTreeAllocator<MemoryMapSource>,
+                SpinLock,
+            >::ZERO_SIZED_ALLOCATION),
+            "Uninitialized allocator should have an empty memory range"
+        );
+
+        let allocation = ALLOCATOR
+            .allocate(64.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        assert!(
+            ALLOCATOR.contains(allocation),
+            "Allocation was not within memory range"
+        );
+        ALLOCATOR.deallocate(64.non_zero(), 8.non_zero(), allocation);
+    }
+
+    fn new_allocator() -> Result<MultipleBinarySearchTreeAllocator<MemoryMapSource>, AllocError> {
+        MultipleBinarySearchTreeAllocator::new(MemoryMapSource::default(), 4096.non_zero())
+    }
+
+    fn test_shared_between_threads<L: 'static + AllocatorLock>() {
+        let allocator = Arc::new(LockedAllocator::<_, L>::new(
+            MultipleBinarySearchTreeAllocator::new(
+                MemoryMapSource::default(),
+                (1024 * 1024).non_zero(),
+            )
+            .unwrap(),
+        ));
+
+        let threads = (0..NUMBER_OF_THREADS)
+            .map(|thread_index| {
+                let allocator = allocator.clone();
+                thread::spawn(move || {
+                    for _ in 0..ALLOCATIONS_PER_THREAD {
+                        let allocation = allocator
+                            .allocate(64.non_zero(), 8.non_zero())
+                            .expect("Did not allocate");
+                        unsafe { allocation.as_ptr().write_bytes(thread_index as u8, 64) };
+                        assert_eq!(
+                            unsafe { allocation.as_ptr().add(63).read() },
+                            thread_index as u8,
+                            "Allocation was shared with another thread"
+                        );
+                        allocator.deallocate(64.non_zero(), 8.non_zero(), allocation);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for thread in threads {
+            thread.join().expect("Thread panicked");
+        }
+    }
+}
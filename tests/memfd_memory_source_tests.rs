@@ -0,0 +1,91 @@
+#![feature(allocator_api)]
+
+#[cfg(all(test, any(target_os = "android", target_os = "linux")))]
+mod memfd_memory_source_tests {
+
+    use allocator_suite::allocators::global::local_allocator::LocalAllocator;
+    use allocator_suite::allocators::prelude::*;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+    use allocator_suite::memory_sources::prelude::*;
+    use libc::{pread, F_SEAL_GROW, F_SEAL_SHRINK};
+    use std::ffi::CString;
+
+    #[test]
+    pub fn obtained_memory_is_readable_through_file_descriptor_at_offset() {
+        let memory_source = new_memory_source(true);
+
+        let first = memory_source
+            .obtain(4096.non_zero())
+            .expect("Did not obtain");
+        let second = memory_source
+            .obtain(4096.non_zero())
+            .expect("Did not obtain");
+        unsafe { second.as_ptr().write_bytes(0xA5, 4096) };
+
+        assert_eq!(memory_source.offset_of(first), Some(0));
+        let offset = memory_source.offset_of(second).expect("No offset");
+        assert!(offset >= 4096);
+
+        let mut contents = [0u8; 4096];
+        let read = unsafe {
+            pread(
+                memory_source.file_descriptor(),
+                contents.as_mut_ptr() as *mut _,
+                contents.len(),
+                offset as _,
+            )
+        };
+        assert_eq!(read, 4096);
+        assert!(contents.iter().all(|&byte| byte == 0xA5));
+
+        memory_source.release(4096.non_zero(), second);
+        memory_source.release(4096.non_zero(), first);
+    }
+
+    #[test]
+    pub fn sealed_against_growing_does_not_obtain() {
+        let memory_source = new_memory_source(true);
+        memory_source
+            .obtain(4096.non_zero())
+            .expect("Did not obtain");
+
+        memory_source
+            .add_seals(F_SEAL_GROW | F_SEAL_SHRINK)
+            .expect("Did not seal");
+        assert_eq!(
+            memory_source.seals().expect("No seals") & (F_SEAL_GROW | F_SEAL_SHRINK),
+            F_SEAL_GROW | F_SEAL_SHRINK
+        );
+        assert!(memory_source.obtain(4096.non_zero()).is_err());
+    }
+
+    #[test]
+    pub fn does_not_seal_unless_allowed() {
+        let memory_source = new_memory_source(false);
+
+        assert!(memory_source.add_seals(F_SEAL_GROW).is_err());
+    }
+
+    #[test]
+    pub fn bump_allocator_allocates_from_memfd() {
+        let allocator = BumpAllocator::new(new_memory_source(false), (64 * 1024).non_zero())
+            .expect("Did not create allocator");
+
+        let memory = allocator
+            .allocate(64.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        unsafe { memory.as_ptr().write_bytes(0x5A, 64) };
+        assert!(allocator.contains(memory));
+    }
+
+    fn new_memory_source(allow_sealing: bool) -> MemfdMemorySource {
+        let name = CString::new("allocator_suite_test").unwrap();
+        MemfdMemorySource::new(
+            &name,
+            (1024 * 1024).non_zero(),
+            HugePageSize::None,
+            allow_sealing,
+        )
+        .expect("Did not create memory source")
+    }
+}
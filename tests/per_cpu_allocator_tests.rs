@@ -0,0 +1,180 @@
+#![feature(allocator_api)]
+
+#[cfg(all(test, any(target_os = "android", target_os = "linux")))]
+mod per_cpu_allocator_tests {
+
+    use allocator_suite::allocators::prelude::*;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+
+    use allocator_suite::memory_sources::mmap::prelude::*;
+    use libc::{cpu_set_t, sched_getaffinity, sched_setaffinity, CPU_ISSET, CPU_SET, CPU_SETSIZE};
+    use std::alloc::AllocError;
+    use std::mem::{size_of, zeroed};
+    use std::ptr::NonNull;
+    use std::sync::Arc;
+    use std::thread;
+
+    const NUMBER_OF_THREADS: usize = 8;
+
+    const ALLOCATIONS_PER_THREAD: usize = 256;
+
+    const SHARD_SIZE: usize = 1024 * 1024;
+
+    #[test]
+    pub fn allocations_from_many_threads_do_not_overlap() {
+        let allocator = Arc::new(
+            PerCpuAllocator::<_, SpinLock>::new(false, new_shard).expect("Did not create shards"),
+        );
+
+        let threads = (0..NUMBER_OF_THREADS)
+            .map(|thread_index| {
+                let allocator = allocator.clone();
+                thread::spawn(move || {
+                    let allocations = (0..ALLOCATIONS_PER_THREAD)
+                        .map(|_| {
+                            let memory = allocator
+                                .allocate(64.non_zero(), 16.non_zero())
+                                .expect("Did not allocate");
+                            unsafe { memory.as_ptr().write_bytes(thread_index as u8, 64) };
+                            memory
+                        })
+                        .collect::<Vec<_>>();
+
+                    allocations
+                        .into_iter()
+                        .map(|memory| {
+                            assert_eq!(
+                                unsafe { memory.as_ptr().add(63).read() },
+                                thread_index as u8,
+                                "Allocation was shared with another thread"
+                            );
+                            memory.as_ptr() as usize
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut allocations = threads
+            .into_iter()
+            .flat_map(|thread| thread.join().expect("Thread panicked"))
+            .collect::<Vec<_>>();
+        allocations.sort();
+        allocations.dedup();
+        assert_eq!(
+            allocations.len(),
+            NUMBER_OF_THREADS * ALLOCATIONS_PER_THREAD,
+            "Allocations overlapped"
+        );
+    }
+
+    #[test]
+    pub fn deallocates_from_another_thread() {
+        let allocator = Arc::new(
+            PerCpuAllocator::<_, TicketLock>::new(false, new_shard).expect("Did not create shards"),
+        );
+
+        thread::spawn(move || {
+            // Pinned so that both allocations are made from the same shard.
+            let owning_cpu = current_cpu();
+            pin_to_cpu(owning_cpu);
+
+            let memory = allocator
+                .allocate(128.non_zero(), 8.non_zero())
+                .expect("Did not allocate");
+            let memory = memory.as_ptr() as usize;
+
+            let other = allocator.clone();
+            thread::spawn(move || {
+                if let Some(other_cpu) = allowed_cpus().into_iter().find(|&cpu| cpu != owning_cpu) {
+                    pin_to_cpu(other_cpu);
+                }
+                other.deallocate(
+                    128.non_zero(),
+                    8.non_zero(),
+                    NonNull::new(memory as *mut u8).unwrap(),
+                )
+            })
+            .join()
+            .expect("Thread panicked");
+
+            // The bump allocator of the owning shard only reclaims memory if the deallocation was made by it.
+            let reused = allocator
+                .allocate(128.non_zero(), 8.non_zero())
+                .expect("Did not allocate");
+            assert_eq!(
+                reused.as_ptr() as usize,
+                memory,
+                "Deallocation was not made by the owning shard"
+            );
+        })
+        .join()
+        .expect("Thread panicked");
+    }
+
+    #[test]
+    pub fn ignores_memory_it_did_not_allocate() {
+        let allocator =
+            PerCpuAllocator::<_, SpinLock>::new(false, new_shard).expect("Did not create shards");
+        let mut foreign = [0u8; 64];
+        let foreign = NonNull::new(foreign.as_mut_ptr()).unwrap();
+
+        allocator.deallocate(64.non_zero(), 8.non_zero(), foreign);
+        assert!(allocator
+            .growing_reallocate(128.non_zero(), 8.non_zero(), 64.non_zero(), foreign)
+            .is_err());
+        assert!(allocator
+            .shrinking_reallocate(32.non_zero(), 8.non_zero(), 64.non_zero(), foreign)
+            .is_err());
+    }
+
+    #[test]
+    pub fn lazy_instance_creates_shards_on_first_allocation() {
+        static ALLOCATOR: PerCpuAllocator<AtomicBumpAllocator<MemoryMapSource>> =
+            PerCpuAllocator::new_lazy(false, new_shard);
+
+        let memory = ALLOCATOR
+            .allocate(32.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        ALLOCATOR.deallocate(32.non_zero(), 8.non_zero(), memory);
+    }
+
+    fn allowed_cpus() -> Vec<usize> {
+        let mut cpu_set = unsafe { zeroed::<cpu_set_t>() };
+        assert_eq!(
+            unsafe { sched_getaffinity(0, size_of::<cpu_set_t>(), &mut cpu_set) },
+            0,
+            "Could not get CPU affinity"
+        );
+        (0..CPU_SETSIZE as usize)
+            .filter(|&cpu| unsafe { CPU_ISSET(cpu, &cpu_set) })
+            .collect()
+    }
+
+    fn pin_to_cpu(cpu: usize) {
+        let mut cpu_set = unsafe { zeroed::<cpu_set_t>() };
+        unsafe { CPU_SET(cpu, &mut cpu_set) };
+        assert_eq!(
+            unsafe { sched_setaffinity(0, size_of::<cpu_set_t>(), &cpu_set) },
+            0,
+            "Could not pin thread to CPU"
+        );
+    }
+
+    fn new_shard(
+        _cpu: usize,
+        numa_settings: Option<NumaSettings>,
+    ) -> Result<AtomicBumpAllocator<MemoryMapSource>, AllocError> {
+        AtomicBumpAllocator::new(
+            MemoryMapSource::new(
+                false,
+                false,
+                false,
+                false,
+                HugePageSize::None,
+                numa_settings,
+            ),
+            SHARD_SIZE.non_zero(),
+        )
+    }
+}
@@ -0,0 +1,134 @@
+#![feature(allocator_api)]
+
+#[cfg(test)]
+mod quota_memory_source_tests {
+
+    use allocator_suite::extensions::non_null_u8_ext::NonNullU8Ext;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+    use allocator_suite::memory_address::MemoryAddress;
+    use allocator_suite::memory_sources::prelude::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering::SeqCst;
+    use std::sync::Arc;
+
+    const SIZE: usize = 64 * 1024;
+
+    #[test]
+    pub fn fails_once_limit_reached() {
+        let memory_source = QuotaMemorySource::new(new_memory_map_source(), 2 * SIZE);
+
+        let first = memory_source
+            .obtain(SIZE.non_zero())
+            .expect("Did not obtain");
+        let second = memory_source
+            .obtain(SIZE.non_zero())
+            .expect("Did not obtain");
+        assert_eq!(memory_source.used(), 2 * SIZE);
+        assert_eq!(memory_source.remaining(), 0);
+        assert!(memory_source.obtain(SIZE.non_zero()).is_err());
+
+        memory_source.release(SIZE.non_zero(), first);
+        assert_eq!(memory_source.used(), SIZE);
+        let third = memory_source
+            .obtain(SIZE.non_zero())
+            .expect("Did not obtain");
+
+        memory_source.release(SIZE.non_zero(), second);
+        memory_source.release(SIZE.non_zero(), third);
+        assert_eq!(memory_source.used(), 0);
+    }
+
+    #[test]
+    pub fn out_of_quota_callback_can_free_memory_and_retry() {
+        let quota_address = Arc::new(AtomicUsize::new(0));
+        let freeable = Arc::new(AtomicUsize::new(0));
+
+        let memory_source = {
+            let quota_address = quota_address.clone();
+            let freeable = freeable.clone();
+            Box::new(QuotaMemorySource::with_out_of_quota_callback(
+                new_memory_map_source(),
+                SIZE,
+                Box::new(move |_requested, _used, _limit| {
+                    let memory = freeable.swap(0, SeqCst);
+                    if memory == 0 {
+                        return OutOfQuotaAction::Fail;
+                    }
+                    let quota = unsafe {
+                        &*(quota_address.load(SeqCst) as *const QuotaMemorySource<MemoryMapSource>)
+                    };
+                    quota.release(SIZE.non_zero(), MemoryAddress::from_usize(memory));
+                    OutOfQuotaAction::Retry
+                }),
+            ))
+        };
+        quota_address.store(&*memory_source as *const _ as usize, SeqCst);
+
+        let first = memory_source
+            .obtain(SIZE.non_zero())
+            .expect("Did not obtain");
+        freeable.store(first.to_usize(), SeqCst);
+
+        let second = memory_source
+            .obtain(SIZE.non_zero())
+            .expect("Did not obtain");
+        assert_eq!(memory_source.used(), SIZE);
+        assert!(memory_source.obtain(SIZE.non_zero()).is_err());
+
+        memory_source.release(SIZE.non_zero(), second);
+    }
+
+    #[test]
+    pub fn out_of_quota_callback_retries_are_limited() {
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let memory_source = {
+            let calls = calls.clone();
+            QuotaMemorySource::with_out_of_quota_callback(
+                new_memory_map_source(),
+                SIZE,
+                Box::new(move |_requested, _used, _limit| {
+                    calls.fetch_add(1, SeqCst);
+                    OutOfQuotaAction::Retry
+                }),
+            )
+        };
+
+        assert!(memory_source.obtain((2 * SIZE).non_zero()).is_err());
+        assert_eq!(
+            calls.load(SeqCst),
+            QuotaMemorySource::<MemoryMapSource>::MAXIMUM_RETRIES
+        );
+    }
+
+    #[test]
+    pub fn child_quotas_count_against_parent() {
+        let parent = RcMemorySource::new(QuotaMemorySource::new(new_memory_map_source(), 2 * SIZE));
+        let first_child = QuotaMemorySource::new(parent.clone(), 2 * SIZE);
+        let second_child = QuotaMemorySource::new(parent.clone(), 2 * SIZE);
+
+        let first = first_child.obtain(SIZE.non_zero()).expect("Did not obtain");
+        let second = second_child
+            .obtain(SIZE.non_zero())
+            .expect("Did not obtain");
+        assert_eq!(parent.used(), 2 * SIZE);
+
+        assert!(second_child.obtain(SIZE.non_zero()).is_err());
+        assert_eq!(second_child.used(), SIZE);
+
+        first_child.release(SIZE.non_zero(), first);
+        let third = second_child
+            .obtain(SIZE.non_zero())
+            .expect("Did not obtain");
+        assert_eq!(second_child.used(), 2 * SIZE);
+        assert_eq!(parent.used(), 2 * SIZE);
+
+        second_child.release(SIZE.non_zero(), second);
+        second_child.release(SIZE.non_zero(), third);
+        assert_eq!(parent.used(), 0);
+    }
+
+    fn new_memory_map_source() -> MemoryMapSource {
+        MemoryMapSource::new(false, false, true, false, HugePageSize::None, None)
+    }
+}
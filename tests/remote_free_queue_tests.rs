@@ -0,0 +1,71 @@
+#![feature(allocator_api)]
+
+#[cfg(test)]
+mod remote_free_queue_tests {
+
+    use allocator_suite::allocators::global::prelude::*;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+    use allocator_suite::memory_address::MemoryAddress;
+
+    use std::sync::Arc;
+    use std::thread;
+
+    const NUMBER_OF_THREADS: usize = 4;
+
+    const ALLOCATIONS_PER_THREAD: usize = 256;
+
+    const ALLOCATION_SIZE: usize = 24;
+
+    const ALIGNMENT: usize = 8;
+
+    #[test]
+    pub fn drains_everything_pushed_from_many_threads() {
+        let queue = Arc::new(RemoteFreeQueue::new());
+        assert!(queue.is_empty());
+
+        let threads = (0..NUMBER_OF_THREADS)
+            .map(|_| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    for _ in 0..ALLOCATIONS_PER_THREAD {
+                        let memory = Box::leak(Box::new([0u64; ALLOCATION_SIZE / 8]));
+                        let current_memory = MemoryAddress::from(&mut memory[0]).cast::<u8>();
+                        assert!(queue.push(
+                            current_memory,
+                            ALLOCATION_SIZE.non_zero(),
+                            ALIGNMENT.non_zero()
+                        ));
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for thread in threads {
+            thread.join().expect("Thread panicked");
+        }
+
+        let mut drained = 0;
+        queue.drain(
+            |current_memory, non_zero_size, non_zero_power_of_two_alignment| {
+                assert_eq!(non_zero_size.get(), ALLOCATION_SIZE);
+                assert_eq!(non_zero_power_of_two_alignment.get(), ALIGNMENT);
+                drop(unsafe {
+                    Box::from_raw(current_memory.as_ptr() as *mut [u64; ALLOCATION_SIZE / 8])
+                });
+                drained += 1;
+            },
+        );
+
+        assert_eq!(drained, NUMBER_OF_THREADS * ALLOCATIONS_PER_THREAD);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    pub fn refuses_allocations_too_small_to_queue() {
+        let queue = RemoteFreeQueue::new();
+        let mut memory = 0u8;
+
+        assert!(!queue.push(MemoryAddress::from(&mut memory), 1.non_zero(), 1.non_zero()));
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,138 @@
+#![feature(allocator_api)]
+
+#[cfg(all(test, unix))]
+mod reserved_memory_source_tests {
+
+    use allocator_suite::allocators::global::prelude::*;
+    use allocator_suite::allocators::prelude::*;
+    use allocator_suite::extensions::non_null_u8_ext::NonNullU8Ext;
+    use allocator_suite::extensions::non_zero_usize_ext::NonZeroUsizeExt;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+    use allocator_suite::memory_sources::prelude::*;
+
+    const RESERVATION_SIZE: usize = 16 * 1024 * 1024;
+
+    #[test]
+    pub fn obtains_contiguous_memory_within_reservation() {
+        let memory_source = new_memory_source();
+        let page_size = memory_source.page_size();
+
+        let first = memory_source.obtain(page_size).expect("Did not obtain");
+        let second = memory_source.obtain(page_size).expect("Did not obtain");
+        assert_eq!(second, first.add_non_zero(page_size));
+
+        let reservation = memory_source.reservation().expect("Has a reservation");
+        assert_eq!(reservation.from, first);
+        assert_eq!(
+            reservation.to.difference(reservation.from),
+            RESERVATION_SIZE
+        );
+        unsafe { second.as_ptr().write_bytes(0x33, page_size.get()) };
+    }
+
+    #[test]
+    pub fn does_not_obtain_more_than_reservation() {
+        let memory_source = new_memory_source();
+
+        memory_source
+            .obtain(RESERVATION_SIZE.non_zero())
+            .expect("Did not obtain");
+        assert!(memory_source.obtain(1.non_zero()).is_err());
+    }
+
+    #[test]
+    pub fn extends_most_recently_obtained_memory_in_place() {
+        let memory_source = new_memory_source();
+        let page_size = memory_source.page_size();
+
+        let first = memory_source.obtain(page_size).expect("Did not obtain");
+        memory_source
+            .extend(first, page_size, page_size.doubled())
+            .expect("Did not extend");
+        unsafe { first.as_ptr().write_bytes(0x44, page_size.doubled().get()) };
+
+        let second = memory_source.obtain(page_size).expect("Did not obtain");
+        assert_eq!(second, first.add_non_zero(page_size.doubled()));
+        assert!(memory_source
+            .extend(first, page_size.doubled(), (page_size.get() * 3).non_zero())
+            .is_err());
+    }
+
+    #[test]
+    pub fn releasing_most_recently_obtained_memory_reuses_it() {
+        let memory_source = new_memory_source();
+        let page_size = memory_source.page_size();
+
+        let first = memory_source.obtain(page_size).expect("Did not obtain");
+        memory_source.release(page_size, first);
+        assert_eq!(memory_source.obtained(), 0);
+
+        let again = memory_source.obtain(page_size).expect("Did not obtain");
+        assert_eq!(again, first);
+        assert_eq!(unsafe { *again.as_ptr() }, 0);
+    }
+
+    #[test]
+    pub fn recommitted_memory_is_usable() {
+        let memory_source = new_memory_source();
+        let size = memory_source.page_size().doubled();
+
+        let memory = memory_source.obtain(size).expect("Did not obtain");
+        unsafe { memory.as_ptr().write_bytes(0x55, size.get()) };
+
+        memory_source.decommit(size, memory);
+        memory_source
+            .recommit(size, memory)
+            .expect("Did not recommit");
+        unsafe { memory.as_ptr().write_bytes(0x66, size.get()) };
+    }
+
+    #[test]
+    pub fn bump_allocator_allocates_from_reservation() {
+        let memory_source = new_memory_source();
+        let reservation = memory_source.reservation().expect("Has a reservation");
+        let allocator = BumpAllocator::new(memory_source, (64 * 1024).non_zero())
+            .expect("Did not create allocator");
+
+        let memory = allocator
+            .allocate(64.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        assert!(memory >= reservation.from && memory < reservation.to);
+    }
+
+    #[test]
+    pub fn growable_bump_allocator_extends_in_place_within_reservation() {
+        let memory_source = new_memory_source();
+        let page_size = memory_source.page_size();
+        let reservation = memory_source.reservation().expect("Has a reservation");
+        let allocator = BumpAllocator::new_growable(memory_source, page_size)
+            .expect("Did not create allocator");
+        assert_eq!(allocator.memory_range(), reservation);
+
+        let memory = allocator
+            .allocate(page_size, 8.non_zero())
+            .expect("Did not allocate");
+        let grown = allocator
+            .growing_reallocate(
+                (page_size.get() * 5).non_zero(),
+                8.non_zero(),
+                page_size,
+                memory,
+            )
+            .expect("Did not grow");
+        assert_eq!(grown, memory);
+        unsafe { grown.as_ptr().write_bytes(0x77, page_size.get() * 5) };
+
+        assert_eq!(allocator.memory_range(), reservation);
+        assert_eq!(allocator.memory_source().obtained(), page_size.get() * 5);
+    }
+
+    #[test]
+    pub fn bump_allocator_is_not_growable_without_reservation() {
+        assert!(BumpAllocator::new_growable(MemoryMapSource::default(), 4096.non_zero()).is_err());
+    }
+
+    fn new_memory_source() -> ReservedMemorySource {
+        ReservedMemorySource::new(RESERVATION_SIZE.non_zero()).expect("Did not reserve")
+    }
+}
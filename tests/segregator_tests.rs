@@ -0,0 +1,71 @@
+#![feature(allocator_api)]
+#![feature(min_const_generics)]
+
+#[cfg(test)]
+mod segregator_tests {
+
+    use allocator_suite::adaptors::prelude::*;
+    use allocator_suite::allocators::global::local_allocator::LocalAllocator;
+    use allocator_suite::allocators::prelude::*;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+
+    use allocator_suite::memory_sources::mmap::memory_map_source::MemoryMapSource;
+    use std::alloc::System;
+
+    const THRESHOLD: usize = 256;
+
+    type TestSegregator = Segregator<
+        THRESHOLD,
+        AtomicBumpAllocator<MemoryMapSource>,
+        GlobalAllocToAllocatorAdaptor<System>,
+    >;
+
+    #[test]
+    pub fn routes_by_size() {
+        let allocator = new_segregator();
+
+        let small = allocator
+            .allocate(THRESHOLD.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        assert!(allocator.small().contains(small));
+
+        let large = allocator
+            .allocate((THRESHOLD + 1).non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        assert!(!allocator.small().contains(large));
+
+        allocator.deallocate((THRESHOLD + 1).non_zero(), 8.non_zero(), large);
+        allocator.deallocate(THRESHOLD.non_zero(), 8.non_zero(), small);
+    }
+
+    #[test]
+    pub fn reallocating_across_threshold_copies() {
+        let allocator = new_segregator();
+
+        let memory = allocator
+            .allocate(64.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        unsafe { memory.as_ptr().write_bytes(0x5A, 64) };
+
+        let grown = allocator
+            .growing_reallocate(1024.non_zero(), 8.non_zero(), 64.non_zero(), memory)
+            .expect("Did not grow");
+        assert!(!allocator.small().contains(grown));
+        assert!((0..64).all(|index| unsafe { *grown.as_ptr().add(index) } == 0x5A));
+
+        let shrunk = allocator
+            .shrinking_reallocate(32.non_zero(), 8.non_zero(), 1024.non_zero(), grown)
+            .expect("Did not shrink");
+        assert!(allocator.small().contains(shrunk));
+        assert!((0..32).all(|index| unsafe { *shrunk.as_ptr().add(index) } == 0x5A));
+
+        allocator.deallocate(32.non_zero(), 8.non_zero(), shrunk);
+    }
+
+    fn new_segregator() -> TestSegregator {
+        Segregator::new(
+            AtomicBumpAllocator::new(MemoryMapSource::default(), 4096.non_zero()).unwrap(),
+            GlobalAllocToAllocatorAdaptor(System),
+        )
+    }
+}
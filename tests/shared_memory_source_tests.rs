@@ -0,0 +1,221 @@
+#![feature(allocator_api)]
+
+#[cfg(all(test, unix))]
+mod shared_memory_source_tests {
+
+    use allocator_suite::allocators::global::local_allocator::LocalAllocator;
+    use allocator_suite::allocators::prelude::*;
+    use allocator_suite::extensions::non_null_u8_ext::NonNullU8Ext;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+    use allocator_suite::memory_sources::prelude::*;
+    use std::ffi::CString;
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    use std::mem::size_of;
+    use std::process;
+
+    const BLOCK_SIZE: usize = 64;
+
+    const NUMBER_OF_BLOCKS: usize = 1024;
+
+    #[test]
+    pub fn attached_bit_set_allocator_shares_heap_mapped_at_different_address() {
+        let name = segment_name("attached_bit_set_allocator_shares_heap");
+        let creator = SharedMemorySource::create(&name, (1024 * 1024).non_zero(), 0o600, None)
+            .expect("Did not create segment");
+        let opener = SharedMemorySource::open(&name, None).expect("Did not open segment");
+        SharedMemorySource::unlink(&name).expect("Did not unlink segment");
+        assert_ne!(creator.mapped_at(), opener.mapped_at());
+
+        let created =
+            BitSetAllocator::new(creator, BLOCK_SIZE.non_zero(), NUMBER_OF_BLOCKS.non_zero())
+                .expect("Did not create allocator");
+        let attached =
+            BitSetAllocator::attach(opener, BLOCK_SIZE.non_zero(), NUMBER_OF_BLOCKS.non_zero())
+                .expect("Did not attach allocator");
+
+        let first = created
+            .allocate(BLOCK_SIZE.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        unsafe { first.as_ptr().write_bytes(0x7E, BLOCK_SIZE) };
+
+        let second = attached
+            .allocate(BLOCK_SIZE.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        assert!(attached.contains(second));
+
+        let first_offset = first.difference(created.memory_range().from);
+        let second_offset = second.difference(attached.memory_range().from);
+        assert_ne!(first_offset, second_offset);
+
+        let first_in_attached = attached.memory_range().from.add(first_offset);
+        assert!(
+            (0..BLOCK_SIZE).all(|index| unsafe { *first_in_attached.as_ptr().add(index) } == 0x7E)
+        );
+    }
+
+    #[test]
+    pub fn offsets_are_position_independent() {
+        let name = segment_name("offsets_are_position_independent");
+        let creator = SharedMemorySource::create(&name, (64 * 1024).non_zero(), 0o600, None)
+            .expect("Did not create segment");
+        let opener = SharedMemorySource::open(&name, None).expect("Did not open segment");
+        SharedMemorySource::unlink(&name).expect("Did not unlink segment");
+
+        let memory = creator.obtain(4096.non_zero()).expect("Did not obtain");
+        unsafe { memory.as_ptr().write(0xA5) };
+
+        let offset = creator.offset_of(memory).expect("Not in segment");
+        let address = opener.address_of(offset).expect("Not in segment");
+        assert_eq!(unsafe { *address.as_ptr() }, 0xA5);
+        assert_eq!(opener.obtain(4096.non_zero()).ok(), Some(address));
+        assert_eq!(opener.offset_of(address), Some(offset));
+    }
+
+    #[test]
+    pub fn does_not_map_at_fixed_address_in_use() {
+        let name = segment_name("does_not_map_at_fixed_address_in_use");
+        let creator = SharedMemorySource::create(&name, (64 * 1024).non_zero(), 0o600, None)
+            .expect("Did not create segment");
+
+        let result = SharedMemorySource::open(&name, Some(creator.mapped_at()));
+        SharedMemorySource::unlink(&name).expect("Did not unlink segment");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    pub fn process_shared_locked_allocator_allocates() {
+        let name = segment_name("process_shared_locked_allocator_allocates");
+        let creator = SharedMemorySource::create(&name, (1024 * 1024).non_zero(), 0o600, None)
+            .expect("Did not create segment");
+        let opener = SharedMemorySource::open(&name, None).expect("Did not open segment");
+        SharedMemorySource::unlink(&name).expect("Did not unlink segment");
+
+        let created = ProcessSharedLockedAllocator::with_lock(
+            creator.lock::<SpinLock>(),
+            BitSetAllocator::new(&creator, BLOCK_SIZE.non_zero(), NUMBER_OF_BLOCKS.non_zero())
+                .expect("Did not create allocator"),
+        );
+        let attached = ProcessSharedLockedAllocator::with_lock(
+            opener.lock::<SpinLock>(),
+            BitSetAllocator::attach(&opener, BLOCK_SIZE.non_zero(), NUMBER_OF_BLOCKS.non_zero())
+                .expect("Did not attach allocator"),
+        );
+
+        let first = created
+            .allocate(BLOCK_SIZE.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        let second = attached
+            .allocate(BLOCK_SIZE.non_zero(), 8.non_zero())
+            .expect("Did not allocate");
+        assert_ne!(creator.offset_of(first), opener.offset_of(second));
+    }
+
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    #[test]
+    pub fn process_shared_futex_mutex_serializes_forked_processes() {
+        const INCREMENTS: usize = 10_000;
+
+        let name = segment_name("process_shared_futex_mutex_serializes_forked_processes");
+        let creator = SharedMemorySource::create(&name, (1024 * 1024).non_zero(), 0o600, None)
+            .expect("Did not create segment");
+        let counter = creator
+            .obtain(size_of::<usize>().non_zero())
+            .expect("Did not obtain")
+            .as_ptr() as *mut usize;
+        let allocator = ProcessSharedLockedAllocator::with_lock(
+            creator.lock::<ProcessSharedFutexMutex>(),
+            BitSetAllocator::new(&creator, BLOCK_SIZE.non_zero(), NUMBER_OF_BLOCKS.non_zero())
+                .expect("Did not create allocator"),
+        );
+
+        let child = unsafe { libc::fork() };
+        assert_ne!(child, -1, "Did not fork");
+        if child == 0 {
+            // Only async-signal-safe work is done in the child, which never returns to the test harness.
+            let succeeded = match SharedMemorySource::open(&name, None) {
+                Err(_) => false,
+                Ok(opener) => {
+                    let counter = match opener.obtain(size_of::<usize>().non_zero()) {
+                        Err(_) => unsafe { libc::_exit(1) },
+                        Ok(counter) => counter.as_ptr() as *mut usize,
+                    };
+                    match BitSetAllocator::attach(
+                        &opener,
+                        BLOCK_SIZE.non_zero(),
+                        NUMBER_OF_BLOCKS.non_zero(),
+                    ) {
+                        Err(_) => false,
+                        Ok(attached) => {
+                            let attached = ProcessSharedLockedAllocator::with_lock(
+                                opener.lock::<ProcessSharedFutexMutex>(),
+                                attached,
+                            );
+                            increment_under_lock(
+                                &attached,
+                                opener.lock::<ProcessSharedFutexMutex>(),
+                                counter,
+                                INCREMENTS,
+                                0x55,
+                            )
+                        }
+                    }
+                }
+            };
+            unsafe { libc::_exit(if succeeded { 0 } else { 1 }) }
+        }
+
+        let parent_succeeded = increment_under_lock(
+            &allocator,
+            creator.lock::<ProcessSharedFutexMutex>(),
+            counter,
+            INCREMENTS,
+            0xAA,
+        );
+
+        let mut status = 0;
+        assert_eq!(unsafe { libc::waitpid(child, &mut status, 0) }, child);
+        SharedMemorySource::unlink(&name).expect("Did not unlink segment");
+
+        assert!(parent_succeeded);
+        assert!(libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0);
+        assert_eq!(
+            unsafe { counter.read_volatile() },
+            2 * INCREMENTS,
+            "Increments were lost"
+        );
+    }
+
+    /// Allocates a block, filled with `tag`, and increments the shared, non-atomic, `counter` with `lock` held, `increments` times; returns false if two processes were handed the same block.
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    fn increment_under_lock<A: Allocator>(
+        allocator: &A,
+        lock: &ProcessSharedFutexMutex,
+        counter: *mut usize,
+        increments: usize,
+        tag: u8,
+    ) -> bool {
+        for _ in 0..increments {
+            let block = match allocator.allocate(BLOCK_SIZE.non_zero(), 8.non_zero()) {
+                Ok(block) => block,
+                Err(_) => return false,
+            };
+            unsafe { block.as_ptr().write_bytes(tag, BLOCK_SIZE) };
+
+            lock.lock();
+            unsafe { counter.write_volatile(counter.read_volatile() + 1) };
+            lock.unlock();
+
+            let unchanged =
+                (0..BLOCK_SIZE).all(|index| unsafe { *block.as_ptr().add(index) } == tag);
+            allocator.deallocate(BLOCK_SIZE.non_zero(), 8.non_zero(), block);
+            if !unchanged {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn segment_name(test_name: &str) -> CString {
+        CString::new(format!("/allocator_suite_{}_{}", test_name, process::id())).unwrap()
+    }
+}
@@ -13,7 +13,14 @@ mod switchable_allocator_tests {
 
     // General imports
     use allocator_suite::adaptors::prelude::*;
+    use allocator_suite::allocators::global::prelude::*;
+    use allocator_suite::allocators::prelude::*;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+    use allocator_suite::memory_sources::prelude::*;
     use std::alloc::System;
+    use std::panic::catch_unwind;
+    use std::ptr::NonNull;
+    use std::thread;
 
     switchable_allocator!(
         application_allocator,
@@ -23,8 +30,120 @@ mod switchable_allocator_tests {
         GlobalAllocToAllocatorAdaptor(System)
     );
 
+    fn new_thread_local_allocator() -> MultipleBinarySearchTreeAllocator<MemoryMapSource> {
+        MultipleBinarySearchTreeAllocator::new(
+            MemoryMapSource::new(false, false, false, false, HugePageSize::None, None),
+            (1024 * 1024).non_zero(),
+        )
+        .expect("Did not create thread local allocator")
+    }
+
     #[test]
     pub fn switchable_generation() {
         let _vec = Vec::<usize>::with_capacity(1234);
     }
+
+    #[test]
+    pub fn memory_outlives_thread_local_allocator_of_exited_thread() {
+        let outlives_thread = thread::spawn(|| {
+            GLOBAL.initialize_thread_local_allocator(new_thread_local_allocator());
+            GLOBAL.set_thread_exit_policy(ThreadExitPolicy::HandToGlobal);
+
+            GLOBAL.callback_with_thread_local_allocator(|| vec![7usize; 64])
+        })
+        .join()
+        .expect("Thread panicked");
+
+        assert_eq!(outlives_thread, vec![7usize; 64]);
+        drop(outlives_thread);
+    }
+
+    #[test]
+    pub fn shrinks_memory_of_thread_local_allocator() {
+        thread::spawn(|| {
+            GLOBAL.initialize_thread_local_allocator(new_thread_local_allocator());
+
+            GLOBAL.callback_with_thread_local_allocator(|| {
+                let mut shrunk = Vec::<usize>::with_capacity(4096);
+                shrunk.extend(0..16);
+                shrunk.shrink_to_fit();
+
+                assert_eq!(shrunk.capacity(), 16);
+                assert_eq!(shrunk, (0..16).collect::<Vec<_>>());
+                assert!(GLOBAL
+                    .thread_local_allocator_unchecked()
+                    .contains(NonNull::new(shrunk.as_mut_ptr() as *mut u8).unwrap()));
+            });
+
+            GLOBAL.drop_thread_local_allocator();
+        })
+        .join()
+        .expect("Thread panicked");
+    }
+
+    #[test]
+    pub fn reuses_memory_of_thread_local_allocator_freed_by_another_thread() {
+        thread::spawn(|| {
+            GLOBAL.initialize_thread_local_allocator(new_thread_local_allocator());
+
+            let freed_by_another_thread =
+                GLOBAL.callback_with_thread_local_allocator(|| Box::into_raw(Box::new([7u8; 64])));
+            let address = freed_by_another_thread as usize;
+
+            thread::spawn(move || drop(unsafe { Box::from_raw(address as *mut [u8; 64]) }))
+                .join()
+                .expect("Thread panicked");
+
+            let reused = GLOBAL.callback_with_thread_local_allocator(|| Box::new([9u8; 64]));
+            assert_eq!(&*reused as *const [u8; 64] as usize, address);
+            drop(reused);
+
+            GLOBAL.drop_thread_local_allocator();
+        })
+        .join()
+        .expect("Thread panicked");
+    }
+
+    #[test]
+    pub fn restores_current_allocator_in_use_after_panic() {
+        thread::spawn(|| {
+            assert_eq!(
+                GLOBAL.save_current_allocator_in_use(),
+                CurrentAllocatorInUse::Global
+            );
+
+            let result = catch_unwind(|| {
+                GLOBAL.callback_with_thread_local_allocator(|| panic!("Unwinding"))
+            });
+            assert!(result.is_err());
+
+            assert_eq!(
+                GLOBAL.save_current_allocator_in_use(),
+                CurrentAllocatorInUse::Global
+            );
+        })
+        .join()
+        .expect("Thread panicked");
+    }
+
+    #[test]
+    pub fn scope_guard_restores_on_drop() {
+        thread::spawn(|| {
+            {
+                let guard = GLOBAL.enter(CurrentAllocatorInUse::CoroutineLocal);
+                assert_eq!(guard.restore_to(), CurrentAllocatorInUse::Global);
+                assert_eq!(
+                    GLOBAL.save_current_allocator_in_use(),
+                    CurrentAllocatorInUse::CoroutineLocal
+                );
+            }
+
+            assert_eq!(
+                GLOBAL.save_current_allocator_in_use(),
+                CurrentAllocatorInUse::Global
+            );
+        })
+        .join()
+        .expect("Thread panicked");
+    }
 }
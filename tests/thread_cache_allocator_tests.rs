@@ -0,0 +1,190 @@
+#![feature(allocator_api)]
+
+#[cfg(all(test, unix))]
+mod thread_cache_allocator_tests {
+
+    use allocator_suite::adaptors::prelude::*;
+    use allocator_suite::allocators::prelude::*;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+    use allocator_suite::memory_address::MemoryAddress;
+
+    use std::alloc::{AllocError, System};
+    use std::num::NonZeroUsize;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering::SeqCst;
+    use std::thread;
+
+    /// Counts blocks outstanding from `System`.
+    #[derive(Debug)]
+    struct CountingAllocator {
+        inner: GlobalAllocToAllocatorAdaptor<System>,
+        outstanding: AtomicUsize,
+    }
+
+    impl Allocator for CountingAllocator {
+        fn allocate(
+            &self,
+            non_zero_size: NonZeroUsize,
+            non_zero_power_of_two_alignment: NonZeroUsize,
+        ) -> Result<MemoryAddress, AllocError> {
+            self.outstanding.fetch_add(1, SeqCst);
+            self.inner
+                .allocate(non_zero_size, non_zero_power_of_two_alignment)
+        }
+
+        fn deallocate(
+            &self,
+            non_zero_size: NonZeroUsize,
+            non_zero_power_of_two_alignment: NonZeroUsize,
+            current_memory: MemoryAddress,
+        ) {
+            self.outstanding.fetch_sub(1, SeqCst);
+            self.inner.deallocate(
+                non_zero_size,
+                non_zero_power_of_two_alignment,
+                current_memory,
+            )
+        }
+
+        fn growing_reallocate(
+            &self,
+            non_zero_new_size: NonZeroUsize,
+            non_zero_power_of_two_alignment: NonZeroUsize,
+            non_zero_current_size: NonZeroUsize,
+            current_memory: MemoryAddress,
+        ) -> Result<MemoryAddress, AllocError> {
+            self.inner.growing_reallocate(
+                non_zero_new_size,
+                non_zero_power_of_two_alignment,
+                non_zero_current_size,
+                current_memory,
+            )
+        }
+
+        fn shrinking_reallocate(
+            &self,
+            non_zero_new_size: NonZeroUsize,
+            non_zero_power_of_two_alignment: NonZeroUsize,
+            non_zero_current_size: NonZeroUsize,
+            current_memory: MemoryAddress,
+        ) -> Result<MemoryAddress, AllocError> {
+            self.inner.shrinking_reallocate(
+                non_zero_new_size,
+                non_zero_power_of_two_alignment,
+                non_zero_current_size,
+                current_memory,
+            )
+        }
+    }
+
+    const fn new_counting_allocator() -> CountingAllocator {
+        CountingAllocator {
+            inner: GlobalAllocToAllocatorAdaptor(System),
+            outstanding: AtomicUsize::new(0),
+        }
+    }
+
+    #[test]
+    pub fn reuses_cached_blocks() {
+        static ALLOCATOR: ThreadCacheAllocator<CountingAllocator> =
+            ThreadCacheAllocator::with_defaults(new_counting_allocator());
+
+        thread::spawn(|| {
+            let first = ALLOCATOR
+                .allocate(24.non_zero(), 8.non_zero())
+                .expect("Did not allocate");
+            ALLOCATOR.deallocate(24.non_zero(), 8.non_zero(), first);
+
+            let second = ALLOCATOR
+                .allocate(32.non_zero(), 16.non_zero())
+                .expect("Did not allocate");
+            assert_eq!(
+                first, second,
+                "Cached block of the same size class was not reused"
+            );
+            assert_eq!(
+                second.as_ptr() as usize % 32,
+                0,
+                "Block not aligned to its size class"
+            );
+
+            ALLOCATOR.deallocate(32.non_zero(), 16.non_zero(), second);
+            assert_eq!(ALLOCATOR.backing_allocator().outstanding.load(SeqCst), 1);
+
+            ALLOCATOR.flush();
+            assert_eq!(ALLOCATOR.backing_allocator().outstanding.load(SeqCst), 0);
+        })
+        .join()
+        .expect("Thread panicked");
+    }
+
+    #[test]
+    pub fn returns_cached_blocks_on_thread_exit() {
+        static ALLOCATOR: ThreadCacheAllocator<CountingAllocator> =
+            ThreadCacheAllocator::new(new_counting_allocator(), 16, 1024, 0);
+
+        let threads = (0..4)
+            .map(|_| {
+                thread::spawn(|| {
+                    let blocks = (0..64)
+                        .map(|index| {
+                            let size = (16 << (index % 7)).non_zero();
+                            let block = ALLOCATOR
+                                .allocate(size, 8.non_zero())
+                                .expect("Did not allocate");
+                            (size, block)
+                        })
+                        .collect::<Vec<_>>();
+
+                    for (size, block) in blocks {
+                        ALLOCATOR.deallocate(size, 8.non_zero(), block)
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for thread in threads {
+            thread.join().expect("Thread panicked");
+        }
+
+        assert_eq!(ALLOCATOR.backing_allocator().outstanding.load(SeqCst), 0);
+    }
+
+    #[test]
+    pub fn large_allocations_pass_through() {
+        static ALLOCATOR: ThreadCacheAllocator<CountingAllocator> =
+            ThreadCacheAllocator::new(new_counting_allocator(), 16, 256, 0);
+
+        thread::spawn(|| {
+            let block = ALLOCATOR
+                .allocate(512.non_zero(), 8.non_zero())
+                .expect("Did not allocate");
+            ALLOCATOR.deallocate(512.non_zero(), 8.non_zero(), block);
+            assert_eq!(ALLOCATOR.backing_allocator().outstanding.load(SeqCst), 0);
+        })
+        .join()
+        .expect("Thread panicked");
+    }
+
+    #[test]
+    pub fn scavenges_blocks_not_needed() {
+        static ALLOCATOR: ThreadCacheAllocator<CountingAllocator> =
+            ThreadCacheAllocator::new(new_counting_allocator(), 16, 4096, 0);
+
+        thread::spawn(|| {
+            let block = ALLOCATOR
+                .allocate(64.non_zero(), 8.non_zero())
+                .expect("Did not allocate");
+            ALLOCATOR.deallocate(64.non_zero(), 8.non_zero(), block);
+
+            // The first scavenge only records that the block is idle.
+            ALLOCATOR.scavenge();
+            assert_eq!(ALLOCATOR.backing_allocator().outstanding.load(SeqCst), 1);
+
+            ALLOCATOR.scavenge();
+            assert_eq!(ALLOCATOR.backing_allocator().outstanding.load(SeqCst), 0);
+        })
+        .join()
+        .expect("Thread panicked");
+    }
+}
@@ -0,0 +1,234 @@
+#![feature(allocator_api)]
+#![feature(extern_types)]
+#![feature(core_intrinsics)]
+#![feature(libstd_sys_internals)]
+#![feature(thread_local)]
+#![feature(const_fn)]
+#![feature(nonnull_slice_from_raw_parts)]
+
+#[cfg(test)]
+mod thread_exit_tests {
+    // Allocator generator macro
+    use allocator_suite::switchable_allocator;
+
+    // General imports
+    use allocator_suite::adaptors::prelude::*;
+    use allocator_suite::allocators::global::prelude::*;
+    use allocator_suite::allocators::prelude::*;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+    use allocator_suite::memory_sources::prelude::*;
+    use std::alloc::System;
+    use std::mem::forget;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering::Relaxed;
+    use std::sync::mpsc::channel;
+    use std::thread;
+
+    switchable_allocator!(
+        application_allocator,
+        BumpAllocator<ArenaMemorySource<MemoryMapSource>>,
+        MultipleBinarySearchTreeAllocator<MemoryMapSource>,
+        GlobalAllocToAllocatorAdaptor<System>,
+        GlobalAllocToAllocatorAdaptor(System)
+    );
+
+    /// Leaks and orphans are counted for all threads, so tests which check them must not run concurrently.
+    static SERIALIZE: SpinLock = SpinLock::UNLOCKED;
+
+    struct Serialized;
+
+    impl Serialized {
+        fn new() -> Self {
+            SERIALIZE.lock();
+            Serialized
+        }
+    }
+
+    impl Drop for Serialized {
+        fn drop(&mut self) {
+            SERIALIZE.unlock()
+        }
+    }
+
+    static LEAKED_ALLOCATIONS_SEEN: AtomicUsize = AtomicUsize::new(0);
+
+    fn leaked(allocations_outstanding: usize) {
+        LEAKED_ALLOCATIONS_SEEN.fetch_add(allocations_outstanding, Relaxed);
+    }
+
+    #[test]
+    pub fn drop_policy_reports_leak_and_drops_allocator() {
+        let _serialized = Serialized::new();
+        let leaks = GLOBAL.thread_exit_leaks();
+        let threads_before = leaks.threads();
+        let allocations_before = leaks.allocations();
+        let orphans_before = GLOBAL
+            .orphaned_thread_local_allocators()
+            .number_of_orphans();
+        leaks.set_callback(Some(leaked));
+
+        // The leaked memory is released with the allocator, so must never be touched again.
+        thread::spawn(|| {
+            initialize_thread_local_allocator();
+            GLOBAL.set_thread_exit_policy(ThreadExitPolicy::Drop);
+
+            forget(
+                GLOBAL
+                    .callback_with_thread_local_allocator(|| (vec![7usize; 64], vec![8usize; 64])),
+            );
+        })
+        .join()
+        .expect("Thread panicked");
+
+        assert_eq!(leaks.threads(), threads_before + 1);
+        assert_eq!(leaks.allocations(), allocations_before + 2);
+        assert_eq!(LEAKED_ALLOCATIONS_SEEN.load(Relaxed), 2);
+        leaks.set_callback(None);
+
+        assert_eq!(
+            GLOBAL
+                .orphaned_thread_local_allocators()
+                .number_of_orphans(),
+            orphans_before
+        );
+    }
+
+    #[test]
+    pub fn drop_policy_does_not_report_when_nothing_leaked() {
+        let _serialized = Serialized::new();
+        let threads_before = GLOBAL.thread_exit_leaks().threads();
+
+        thread::spawn(|| {
+            initialize_thread_local_allocator();
+            GLOBAL.set_thread_exit_policy(ThreadExitPolicy::Drop);
+            drop(GLOBAL.callback_with_thread_local_allocator(|| vec![7usize; 64]));
+        })
+        .join()
+        .expect("Thread panicked");
+
+        assert_eq!(GLOBAL.thread_exit_leaks().threads(), threads_before);
+    }
+
+    #[test]
+    pub fn hand_to_global_orphan_is_adopted_then_freed() {
+        let _serialized = Serialized::new();
+        let orphans = GLOBAL.orphaned_thread_local_allocators();
+        let orphans_before = orphans.number_of_orphans();
+        let threads_before = GLOBAL.thread_exit_leaks().threads();
+
+        let (first, second) = spawn_thread_handing_to_global();
+        assert_eq!(orphans.number_of_orphans(), orphans_before + 1);
+        assert_eq!(GLOBAL.thread_exit_leaks().threads(), threads_before);
+        assert_eq!(first, vec![7usize; 64]);
+
+        drop(first);
+        assert_eq!(orphans.number_of_orphans(), orphans_before + 1);
+
+        assert_eq!(second, vec![8usize; 64]);
+        drop(second);
+        assert_eq!(orphans.number_of_orphans(), orphans_before);
+    }
+
+    #[test]
+    pub fn orphan_is_freed_after_remote_frees_drained_on_allocation() {
+        let _serialized = Serialized::new();
+        let orphans_before = GLOBAL
+            .orphaned_thread_local_allocators()
+            .number_of_orphans();
+
+        let (to_main_thread, from_thread) = channel();
+        let (to_thread, from_main_thread) = channel();
+        let thread = thread::spawn(move || {
+            initialize_thread_local_allocator();
+            GLOBAL.set_thread_exit_policy(ThreadExitPolicy::HandToGlobal);
+
+            let (kept, freed_remotely) = GLOBAL
+                .callback_with_thread_local_allocator(|| (vec![1usize; 64], vec![2usize; 64]));
+            to_main_thread.send(freed_remotely).unwrap();
+            from_main_thread.recv().unwrap();
+
+            // Drains the remote free queue.
+            drop(GLOBAL.callback_with_thread_local_allocator(|| vec![3usize; 64]));
+            kept
+        });
+
+        drop(from_thread.recv().unwrap());
+        to_thread.send(()).unwrap();
+        let kept = thread.join().expect("Thread panicked");
+        assert_eq!(
+            GLOBAL
+                .orphaned_thread_local_allocators()
+                .number_of_orphans(),
+            orphans_before + 1
+        );
+
+        drop(kept);
+        assert_eq!(
+            GLOBAL
+                .orphaned_thread_local_allocators()
+                .number_of_orphans(),
+            orphans_before
+        );
+    }
+
+    #[test]
+    pub fn orphan_is_freed_after_remote_frees_drained_on_thread_exit() {
+        let _serialized = Serialized::new();
+        let orphans_before = GLOBAL
+            .orphaned_thread_local_allocators()
+            .number_of_orphans();
+
+        let (to_main_thread, from_thread) = channel();
+        let (to_thread, from_main_thread) = channel();
+        let thread = thread::spawn(move || {
+            initialize_thread_local_allocator();
+            GLOBAL.set_thread_exit_policy(ThreadExitPolicy::HandToGlobal);
+
+            let (kept, freed_remotely) = GLOBAL
+                .callback_with_thread_local_allocator(|| (vec![1usize; 64], vec![2usize; 64]));
+            to_main_thread.send(freed_remotely).unwrap();
+            from_main_thread.recv().unwrap();
+            kept
+        });
+
+        drop(from_thread.recv().unwrap());
+        to_thread.send(()).unwrap();
+        let kept = thread.join().expect("Thread panicked");
+        assert_eq!(
+            GLOBAL
+                .orphaned_thread_local_allocators()
+                .number_of_orphans(),
+            orphans_before + 1
+        );
+
+        drop(kept);
+        assert_eq!(
+            GLOBAL
+                .orphaned_thread_local_allocators()
+                .number_of_orphans(),
+            orphans_before
+        );
+    }
+
+    /// Returns two allocations made by the thread's thread local allocator which are still in use when it exits.
+    fn spawn_thread_handing_to_global() -> (Vec<usize>, Vec<usize>) {
+        thread::spawn(|| {
+            initialize_thread_local_allocator();
+            GLOBAL.set_thread_exit_policy(ThreadExitPolicy::HandToGlobal);
+
+            GLOBAL.callback_with_thread_local_allocator(|| (vec![7usize; 64], vec![8usize; 64]))
+        })
+        .join()
+        .expect("Thread panicked")
+    }
+
+    fn initialize_thread_local_allocator() {
+        GLOBAL.initialize_thread_local_allocator(
+            MultipleBinarySearchTreeAllocator::new(
+                MemoryMapSource::new(false, false, false, false, HugePageSize::None, None),
+                (1024 * 1024).non_zero(),
+            )
+            .expect("Did not create thread local allocator"),
+        );
+    }
+}
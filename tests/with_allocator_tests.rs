@@ -0,0 +1,148 @@
+#![feature(allocator_api)]
+#![feature(extern_types)]
+#![feature(core_intrinsics)]
+#![feature(libstd_sys_internals)]
+#![feature(thread_local)]
+#![feature(const_fn)]
+#![feature(nonnull_slice_from_raw_parts)]
+
+#[cfg(test)]
+mod with_allocator_tests {
+    // Allocator generator macro
+    use allocator_suite::switchable_allocator;
+
+    // General imports
+    use allocator_suite::adaptors::prelude::*;
+    use allocator_suite::allocators::global::prelude::*;
+    use allocator_suite::allocators::prelude::*;
+    use allocator_suite::extensions::usize_ext::UsizeExt;
+    use allocator_suite::memory_sources::prelude::*;
+    use std::alloc::System;
+    use std::cell::Cell;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::ptr::{null, NonNull};
+    use std::rc::Rc;
+    use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+    use std::thread;
+
+    switchable_allocator!(
+        application_allocator,
+        BumpAllocator<MemoryMapSource>,
+        MultipleBinarySearchTreeAllocator<MemoryMapSource>,
+        GlobalAllocToAllocatorAdaptor<System>,
+        GlobalAllocToAllocatorAdaptor(System)
+    );
+
+    /// Returns `Pending` once, so the task is polled twice.
+    struct YieldOnce(bool);
+
+    impl Future for YieldOnce {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+            if self.0 {
+                Poll::Ready(())
+            } else {
+                self.0 = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    #[test]
+    pub fn polls_with_own_coroutine_local_allocator() {
+        thread::spawn(|| {
+            let task = async {
+                let before_yield = vec![1u8; 64];
+                YieldOnce(false).await;
+                let after_yield = vec![2u8; 64];
+
+                let coroutine_local_allocator = GLOBAL.coroutine_local_allocator_unchecked();
+                coroutine_local_allocator.contains(address_of(&before_yield))
+                    && coroutine_local_allocator.contains(address_of(&after_yield))
+            };
+
+            let allocated_from_arena = block_on(WithAllocator::new(task, new_arena(), &GLOBAL));
+
+            assert!(allocated_from_arena);
+            assert!(GLOBAL.coroutine_local_allocator().is_none());
+            assert_eq!(
+                GLOBAL.save_current_allocator_in_use(),
+                CurrentAllocatorInUse::Global
+            );
+        })
+        .join()
+        .expect("Thread panicked");
+    }
+
+    #[test]
+    pub fn memory_freed_outside_task_between_polls_is_freed_by_its_arena() {
+        thread::spawn(|| {
+            let handed_out: Rc<Cell<Option<Box<[u8; 64]>>>> = Rc::new(Cell::new(None));
+
+            let task_handed_out = handed_out.clone();
+            let task = async move {
+                task_handed_out.set(Some(Box::new([3u8; 64])));
+                YieldOnce(false).await;
+                let after_yield = Box::new([4u8; 64]);
+                address_of_box(&after_yield)
+            };
+            let mut task = Box::pin(WithAllocator::new(task, new_arena(), &GLOBAL));
+            let waker = unsafe { Waker::from_raw(no_op_raw_waker()) };
+            let mut cx = Context::from_waker(&waker);
+
+            assert!(task.as_mut().poll(&mut cx).is_pending());
+
+            // Freed whilst the arena is swapped out, so it is queued for the arena rather than passed to the global allocator.
+            let before_yield = handed_out.take().expect("Task did not hand out memory");
+            let before_yield_address = address_of_box(&before_yield);
+            drop(before_yield);
+
+            // The arena, a bump allocator, frees the queued memory before allocating, so can reuse it.
+            match task.as_mut().poll(&mut cx) {
+                Poll::Ready(after_yield_address) => {
+                    assert_eq!(after_yield_address, before_yield_address)
+                }
+                Poll::Pending => panic!("Task did not complete"),
+            }
+        })
+        .join()
+        .expect("Thread panicked");
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        let waker = unsafe { Waker::from_raw(no_op_raw_waker()) };
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    fn no_op_raw_waker() -> RawWaker {
+        fn clone(_: *const ()) -> RawWaker {
+            no_op_raw_waker()
+        }
+        fn no_op(_: *const ()) {}
+
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, no_op, no_op, no_op);
+        RawWaker::new(null(), &VTABLE)
+    }
+
+    fn new_arena() -> BumpAllocator<MemoryMapSource> {
+        BumpAllocator::new(MemoryMapSource::default(), (64 * 1024).non_zero())
+            .expect("Did not create arena")
+    }
+
+    fn address_of_box(boxed: &Box<[u8; 64]>) -> NonNull<u8> {
+        NonNull::new(boxed.as_ptr() as *mut u8).unwrap()
+    }
+
+    fn address_of(vec: &Vec<u8>) -> NonNull<u8> {
+        NonNull::new(vec.as_ptr() as *mut u8).unwrap()
+    }
+}